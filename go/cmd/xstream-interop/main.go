@@ -0,0 +1,173 @@
+// Command xstream-interop is the driver for the cross-language interop tests
+// in rust/tests/interop_test.rs. It reads a JSON request from stdin and
+// writes a JSON response to stdout, with all binary values hex encoded.
+package main
+
+import (
+	"encoding/hex"
+	"encoding/json"
+	"fmt"
+	"os"
+
+	xstream "github.com/miscreant/xstream/go"
+)
+
+// encryptionAlgs maps the XSTREAM algorithms this implementation supports
+// to their STREAM encryption algorithm
+var encryptionAlgs = map[string]string{
+	"XSTREAM_X25519_HKDF_SHA256_AES128_SIV":      "AES-SIV",
+	"XSTREAM_X25519_HKDF_SHA256_AES128_PMAC_SIV": "AES-PMAC-SIV",
+}
+
+// hexBytes is a byte slice encoded in JSON as a hex string (or null)
+type hexBytes []byte
+
+func (h hexBytes) MarshalJSON() ([]byte, error) {
+	return json.Marshal(hex.EncodeToString(h))
+}
+
+func (h *hexBytes) UnmarshalJSON(data []byte) error {
+	var s *string
+	if err := json.Unmarshal(data, &s); err != nil {
+		return err
+	}
+
+	if s == nil {
+		*h = nil
+		return nil
+	}
+
+	b, err := hex.DecodeString(*s)
+	if err != nil {
+		return err
+	}
+
+	*h = b
+	return nil
+}
+
+type segment struct {
+	AD         hexBytes `json:"ad"`
+	Plaintext  hexBytes `json:"plaintext"`
+	Ciphertext hexBytes `json:"ciphertext"`
+}
+
+type request struct {
+	Command      string    `json:"command"`
+	Algorithm    string    `json:"algorithm"`
+	PublicKey    hexBytes  `json:"public_key"`
+	PrivateKey   hexBytes  `json:"private_key"`
+	EphemeralKey hexBytes  `json:"ephemeral_key"`
+	Salt         hexBytes  `json:"salt"`
+	Segments     []segment `json:"segments"`
+}
+
+type response struct {
+	EphemeralKey hexBytes   `json:"ephemeral_key,omitempty"`
+	Ciphertexts  []hexBytes `json:"ciphertexts,omitempty"`
+	Plaintexts   []hexBytes `json:"plaintexts,omitempty"`
+	Error        string     `json:"error,omitempty"`
+}
+
+func main() {
+	var req request
+	if err := json.NewDecoder(os.Stdin).Decode(&req); err != nil {
+		fail(err)
+	}
+
+	alg, ok := encryptionAlgs[req.Algorithm]
+	if !ok {
+		fail(fmt.Errorf("unsupported algorithm: %s", req.Algorithm))
+	}
+
+	var resp *response
+	var err error
+
+	switch req.Command {
+	case "encrypt":
+		resp, err = encrypt(&req, alg)
+	case "decrypt":
+		resp, err = decrypt(&req, alg)
+	default:
+		err = fmt.Errorf("unknown command: %s", req.Command)
+	}
+
+	if err != nil {
+		fail(err)
+	}
+
+	if err := json.NewEncoder(os.Stdout).Encode(resp); err != nil {
+		fail(err)
+	}
+}
+
+// encrypt seals the request's plaintexts under its public key
+func encrypt(req *request, alg string) (*response, error) {
+	publicKey, err := x25519Key(req.PublicKey)
+	if err != nil {
+		return nil, err
+	}
+
+	enc, ephemeralKey, err := xstream.NewEncryptor(alg, publicKey, req.Salt)
+	if err != nil {
+		return nil, err
+	}
+
+	resp := &response{EphemeralKey: ephemeralKey[:]}
+
+	for i, s := range req.Segments {
+		lastBlock := i+1 == len(req.Segments)
+		resp.Ciphertexts = append(resp.Ciphertexts, enc.Seal(nil, s.Plaintext, s.AD, lastBlock))
+	}
+
+	return resp, nil
+}
+
+// decrypt opens the request's ciphertexts, reporting authentication
+// failures in the response
+func decrypt(req *request, alg string) (*response, error) {
+	privateKey, err := x25519Key(req.PrivateKey)
+	if err != nil {
+		return nil, err
+	}
+
+	ephemeralKey, err := x25519Key(req.EphemeralKey)
+	if err != nil {
+		return nil, err
+	}
+
+	dec, err := xstream.NewDecryptor(alg, privateKey, ephemeralKey, req.Salt)
+	if err != nil {
+		return nil, err
+	}
+
+	resp := &response{}
+
+	for i, s := range req.Segments {
+		lastBlock := i+1 == len(req.Segments)
+		pt, err := dec.Open(nil, s.Ciphertext, s.AD, lastBlock)
+		if err != nil {
+			return &response{Error: err.Error()}, nil
+		}
+
+		resp.Plaintexts = append(resp.Plaintexts, pt)
+	}
+
+	return resp, nil
+}
+
+// x25519Key converts a decoded key into the array type used by xstream
+func x25519Key(b hexBytes) (*[32]byte, error) {
+	var key [32]byte
+	if len(b) != len(key) {
+		return nil, fmt.Errorf("invalid key length: %d", len(b))
+	}
+
+	copy(key[:], b)
+	return &key, nil
+}
+
+func fail(err error) {
+	fmt.Fprintln(os.Stderr, "xstream-interop:", err)
+	os.Exit(1)
+}
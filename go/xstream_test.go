@@ -48,12 +48,18 @@ func loadXSTREAMKeypairExample(k interface{}) xSTREAMKeypairExample {
 	return xSTREAMKeypairExample{pubkey, seckey}
 }
 
-// Load XSTREAM test vectors from xstream.tjson
-// TODO: switch to a native Go TJSON parser when available
+// Load XSTREAM test vectors from xstream.tjson and xstream_generated.tjson
 func loadXSTREAMExamples() []xSTREAMExample {
+	examples := loadXSTREAMExamplesFromFile("../vectors/xstream.tjson")
+	return append(examples, loadXSTREAMExamplesFromFile("../vectors/xstream_generated.tjson")...)
+}
+
+// Load XSTREAM test vectors from the given file
+// TODO: switch to a native Go TJSON parser when available
+func loadXSTREAMExamplesFromFile(filename string) []xSTREAMExample {
 	var examplesJSON map[string]interface{}
 
-	exampleData, err := ioutil.ReadFile("../vectors/xstream.tjson")
+	exampleData, err := ioutil.ReadFile(filename)
 	if err != nil {
 		panic(err)
 	}
@@ -127,25 +133,37 @@ func loadXSTREAMExamples() []xSTREAMExample {
 	return result
 }
 
-// getEncryptionAlg returns the encryption algorithm string for a XSTREAM ciphersuite
-func getEncryptionAlg(ciphersuite string) string {
+// getEncryptionAlg returns the encryption algorithm string for a XSTREAM
+// ciphersuite, or false if the ciphersuite is a known one which isn't
+// implemented by this package yet
+func getEncryptionAlg(ciphersuite string) (string, bool) {
 	switch ciphersuite {
 	case "XSTREAM_X25519_HKDF_SHA256_AES128_SIV":
-		return "AES-SIV"
+		return "AES-SIV", true
 	case "XSTREAM_X25519_HKDF_SHA256_AES128_PMAC_SIV":
-		return "AES-PMAC-SIV"
+		return "AES-PMAC-SIV", true
+	case "XSTREAM_X25519_HKDF_SHA256_AES256_SIV",
+		"XSTREAM_X25519_HKDF_SHA256_AES256_PMAC_SIV",
+		"XSTREAM_X25519_HKDF_SHA256_AES128_GCM_SIV",
+		"XSTREAM_X25519_HKDF_SHA256_AES256_GCM_SIV":
+		return "", false
 	default:
-		panic("XSTREAM: unknown ciphersuite")
+		panic("XSTREAM: unknown ciphersuite: " + ciphersuite)
 	}
 }
 
 func TestXSTREAMEncryptor(t *testing.T) {
 	for _, v := range loadXSTREAMExamples() {
+		encryptionAlg, ok := getEncryptionAlg(v.alg)
+		if !ok {
+			continue
+		}
+
 		fakerng := bytes.NewReader(v.ephemeralkey.seckey)
 		var sealingkey [32]byte
 		copy(sealingkey[:], v.sealingkey.pubkey)
 
-		enc, ek, err := newEncryptorWithRNG(getEncryptionAlg(v.alg), &sealingkey, v.salt, fakerng)
+		enc, ek, err := newEncryptorWithRNG(encryptionAlg, &sealingkey, v.salt, fakerng)
 		if err != nil {
 			t.Fatal(err)
 		}
@@ -166,11 +184,16 @@ func TestXSTREAMEncryptor(t *testing.T) {
 
 func TestXSTREAMDecryptor(t *testing.T) {
 	for _, v := range loadXSTREAMExamples() {
+		encryptionAlg, ok := getEncryptionAlg(v.alg)
+		if !ok {
+			continue
+		}
+
 		var sealingkey, ephemeralpub [32]byte
 		copy(sealingkey[:], v.sealingkey.seckey)
 		copy(ephemeralpub[:], v.ephemeralkey.pubkey)
 
-		dec, err := NewDecryptor(getEncryptionAlg(v.alg), &sealingkey, &ephemeralpub, v.salt)
+		dec, err := NewDecryptor(encryptionAlg, &sealingkey, &ephemeralpub, v.salt)
 		if err != nil {
 			t.Fatal(err)
 		}
@@ -0,0 +1,129 @@
+/**
+ * driver.ts: driver for the cross-language interop tests in
+ * rust/tests/interop_test.rs. Reads a JSON request from stdin and writes a
+ * JSON response to stdout, with all binary values hex encoded.
+ *
+ * Run with: node --require ts-node/register interop/driver.ts
+ */
+
+import * as crypto from "crypto";
+import * as miscreant from "miscreant";
+import * as xstream from "../src/index";
+import { IOptions } from "../src/x25519hkdf";
+
+/** XSTREAM algorithms supported by this implementation, and their STREAM encryption algorithm */
+const ENCRYPTION_ALGS: { [alg: string]: string } = {
+  XSTREAM_X25519_HKDF_SHA256_AES128_PMAC_SIV: "AES-PMAC-SIV",
+  XSTREAM_X25519_HKDF_SHA256_AES128_SIV: "AES-SIV",
+};
+
+interface ISegment {
+  readonly ad: string | null;
+  readonly plaintext?: string;
+  readonly ciphertext?: string;
+}
+
+interface IRequest {
+  readonly command: string;
+  readonly algorithm: string;
+  readonly public_key?: string;
+  readonly private_key?: string;
+  readonly ephemeral_key?: string;
+  readonly salt: string | null;
+  readonly segments: ISegment[];
+}
+
+/** Decode a hex string, passing through null */
+function unhex(value: string | null | undefined): Uint8Array | undefined {
+  return value == null ? undefined : new Uint8Array(Buffer.from(value, "hex"));
+}
+
+/** Encode bytes as a hex string */
+function tohex(value: Uint8Array): string {
+  return Buffer.from(value.buffer, value.byteOffset, value.byteLength).toString("hex");
+}
+
+/** Options for the port, using the (non-constant time) polyfill provider which works under Node */
+function options(request: IRequest): IOptions {
+  return {
+    encryptionAlg: ENCRYPTION_ALGS[request.algorithm],
+    provider: new miscreant.PolyfillCryptoProvider(),
+    rng: (bytes: Uint8Array) => crypto.randomFillSync(bytes),
+    salt: unhex(request.salt),
+  };
+}
+
+/** Seal the request's plaintexts under its public key */
+async function encrypt(request: IRequest) {
+  const [encryptor, ephemeralPublic] = await xstream.StreamEncryptor.generateFromPublicKey(
+    unhex(request.public_key)!,
+    options(request),
+  );
+
+  const ciphertexts: string[] = [];
+
+  for (let i = 0; i < request.segments.length; i++) {
+    const segment = request.segments[i];
+    const last = i + 1 === request.segments.length;
+    ciphertexts.push(tohex(await encryptor.seal(unhex(segment.plaintext)!, last, unhex(segment.ad))));
+  }
+
+  return { ephemeral_key: tohex(ephemeralPublic), ciphertexts };
+}
+
+/** Open the request's ciphertexts, reporting authentication failures in the response */
+async function decrypt(request: IRequest) {
+  const decryptor = await xstream.StreamDecryptor.generateFromKeys(
+    unhex(request.private_key)!,
+    unhex(request.ephemeral_key)!,
+    options(request),
+  );
+
+  const plaintexts: string[] = [];
+
+  for (let i = 0; i < request.segments.length; i++) {
+    const segment = request.segments[i];
+    const last = i + 1 === request.segments.length;
+
+    try {
+      plaintexts.push(tohex(await decryptor.open(unhex(segment.ciphertext)!, last, unhex(segment.ad))));
+    } catch (e) {
+      if (!(e instanceof miscreant.IntegrityError)) {
+        throw e;
+      }
+
+      return { error: e.message || "authentication failed" };
+    }
+  }
+
+  return { plaintexts };
+}
+
+async function main(input: string) {
+  const request: IRequest = JSON.parse(input);
+
+  if (!(request.algorithm in ENCRYPTION_ALGS)) {
+    throw new Error(`unsupported algorithm: ${request.algorithm}`);
+  }
+
+  switch (request.command) {
+    case "encrypt":
+      return encrypt(request);
+    case "decrypt":
+      return decrypt(request);
+    default:
+      throw new Error(`unknown command: ${request.command}`);
+  }
+}
+
+const chunks: Buffer[] = [];
+process.stdin.on("data", (chunk: Buffer) => chunks.push(chunk));
+process.stdin.on("end", () => {
+  main(Buffer.concat(chunks).toString("utf8")).then(
+    (response) => process.stdout.write(JSON.stringify(response)),
+    (error) => {
+      process.stderr.write(`${error.stack || error}\n`);
+      process.exit(1);
+    },
+  );
+});
@@ -4,6 +4,7 @@ import TJSON from "tjson-js";
 /** STREAM (AES-SIV/AES-PMAC-SIV) test vectors */
 export class XStreamExample {
   static readonly DEFAULT_EXAMPLES_PATH = "../vectors/xstream.tjson";
+  static readonly GENERATED_EXAMPLES_PATH = "../vectors/xstream_generated.tjson";
 
   public readonly name: string;
   public readonly alg: string;
@@ -13,7 +14,9 @@ export class XStreamExample {
   public readonly blocks: XStreamBlock[];
 
   static async loadAll(): Promise<XStreamExample[]> {
-    return XStreamExample.loadFromFile(XStreamExample.DEFAULT_EXAMPLES_PATH);
+    const examples = await XStreamExample.loadFromFile(XStreamExample.DEFAULT_EXAMPLES_PATH);
+    const generated = await XStreamExample.loadFromFile(XStreamExample.GENERATED_EXAMPLES_PATH);
+    return examples.concat(generated);
   }
 
   static async loadFromFile(filename: string): Promise<XStreamExample[]> {
@@ -0,0 +1,101 @@
+#!/usr/bin/env python
+
+"""
+interop.py: driver for the cross-language interop tests in
+rust/tests/interop_test.rs. Reads a JSON request from stdin and writes a
+JSON response to stdout, with all binary values hex encoded.
+"""
+
+import binascii
+import json
+import sys
+
+from miscreant.exceptions import IntegrityError
+
+from xstream import (Encryptor, Decryptor)
+
+# XSTREAM algorithms supported by this implementation, and their STREAM
+# encryption algorithm
+ENCRYPTION_ALGS = {
+    u"XSTREAM_X25519_HKDF_SHA256_AES128_SIV": "AES-SIV",
+    u"XSTREAM_X25519_HKDF_SHA256_AES128_PMAC_SIV": "AES-PMAC-SIV",
+}
+
+
+def unhex(value):
+    """Decode a hex string, passing through None"""
+    return None if value is None else binascii.unhexlify(value)
+
+
+def tohex(value):
+    """Encode bytes as a hex string"""
+    return binascii.hexlify(value).decode("ascii")
+
+
+def encrypt(request, encryption_alg):
+    """Seal the request's plaintexts under its public key"""
+    encryptor, ephemeral_public = Encryptor.generate(
+        public_key=unhex(request["public_key"]),
+        encryption_alg=encryption_alg,
+        salt=unhex(request["salt"])
+    )
+
+    segments = request["segments"]
+    ciphertexts = [
+        tohex(encryptor.seal(
+            unhex(segment["plaintext"]),
+            associated_data=unhex(segment["ad"]),
+            last_block=i + 1 == len(segments)
+        ))
+        for i, segment in enumerate(segments)
+    ]
+
+    return {"ephemeral_key": tohex(ephemeral_public), "ciphertexts": ciphertexts}
+
+
+def decrypt(request, encryption_alg):
+    """Open the request's ciphertexts, reporting authentication failures in the response"""
+    decryptor = Decryptor(
+        private_key=unhex(request["private_key"]),
+        ephemeral_public=unhex(request["ephemeral_key"]),
+        encryption_alg=encryption_alg,
+        salt=unhex(request["salt"])
+    )
+
+    segments = request["segments"]
+    plaintexts = []
+
+    for i, segment in enumerate(segments):
+        try:
+            plaintext = decryptor.open(
+                unhex(segment["ciphertext"]),
+                associated_data=unhex(segment["ad"]),
+                last_block=i + 1 == len(segments)
+            )
+        except IntegrityError as e:
+            return {"error": str(e) or "authentication failed"}
+
+        plaintexts.append(tohex(plaintext))
+
+    return {"plaintexts": plaintexts}
+
+
+def main():
+    request = json.load(sys.stdin)
+    encryption_alg = ENCRYPTION_ALGS.get(request["algorithm"])
+
+    if encryption_alg is None:
+        sys.exit("unsupported algorithm: " + request["algorithm"])
+
+    if request["command"] == "encrypt":
+        response = encrypt(request, encryption_alg)
+    elif request["command"] == "decrypt":
+        response = decrypt(request, encryption_alg)
+    else:
+        sys.exit("unknown command: " + request["command"])
+
+    json.dump(response, sys.stdout)
+
+
+if __name__ == "__main__":
+    main()
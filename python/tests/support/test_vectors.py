@@ -1,4 +1,4 @@
-"""test_vectors.py: Parse examples from vectors/xstream.tjson"""
+"""test_vectors.py: Parse examples from the XSTREAM test vectors"""
 
 import binascii
 import json
@@ -31,8 +31,9 @@ class XStreamExample(namedtuple("XStreamExample", [
 
     @staticmethod
     def load():
-        """Load message examples from vectors/xstream.tjson"""
-        return XStreamExample.load_from_file("../vectors/xstream.tjson")
+        """Load the known-answer and generated message examples"""
+        return (XStreamExample.load_from_file("../vectors/xstream.tjson") +
+                XStreamExample.load_from_file("../vectors/xstream_generated.tjson"))
 
     @staticmethod
     def load_from_file(filename):
@@ -14,6 +14,14 @@ from xstream import (Encryptor, Decryptor)
 
 from .support.test_vectors import XStreamExample
 
+# Ciphersuites in the test vectors which aren't implemented yet
+UNSUPPORTED_ALGS = (
+    u'XSTREAM_X25519_HKDF_SHA256_AES256_SIV',
+    u'XSTREAM_X25519_HKDF_SHA256_AES256_PMAC_SIV',
+    u'XSTREAM_X25519_HKDF_SHA256_AES128_GCM_SIV',
+    u'XSTREAM_X25519_HKDF_SHA256_AES256_GCM_SIV',
+)
+
 
 class TestEncryptor(unittest.TestCase):
     def test_seal(self):
@@ -23,6 +31,9 @@ class TestEncryptor(unittest.TestCase):
                 encryption_alg = "AES-SIV"
             elif ex.alg == u'XSTREAM_X25519_HKDF_SHA256_AES128_PMAC_SIV':
                 encryption_alg = "AES-PMAC-SIV"
+            elif ex.alg in UNSUPPORTED_ALGS:
+                # Ciphersuite not yet supported by this implementation
+                continue
             else:
                 raise RuntimeError("unknown encryption algorithm: " + ex.alg)
 
@@ -46,6 +57,9 @@ class TestDecryptor(unittest.TestCase):
                 encryption_alg = "AES-SIV"
             elif ex.alg == u'XSTREAM_X25519_HKDF_SHA256_AES128_PMAC_SIV':
                 encryption_alg = "AES-PMAC-SIV"
+            elif ex.alg in UNSUPPORTED_ALGS:
+                # Ciphersuite not yet supported by this implementation
+                continue
             else:
                 raise RuntimeError("unknown encryption algorithm: " + ex.alg)
 
@@ -12,12 +12,19 @@ class XStream::X25519HKDF::Example
   # Default file to load examples from
   DEFAULT_EXAMPLES = File.expand_path("../../../../vectors/xstream.tjson", __FILE__)
 
+  # Examples generated from seeds by the Rust implementation
+  GENERATED_EXAMPLES = File.expand_path("../../../../vectors/xstream_generated.tjson", __FILE__)
+
   # X25519 keypairs
   KeyPair = Struct.new(:seckey, :pubkey)
 
   # STREAM blocks
   Block = Struct.new(:ad, :plaintext, :ciphertext)
 
+  def self.load_all
+    load_file(DEFAULT_EXAMPLES) + load_file(GENERATED_EXAMPLES)
+  end
+
   def self.load_file(filename = DEFAULT_EXAMPLES)
     examples = TJSON.load_file(filename).fetch("examples")
     raise ParseError, "expected a toplevel array of examples" unless examples.is_a?(Array)
@@ -4,7 +4,17 @@
 require "stringio"
 
 RSpec.describe XStream::X25519HKDF do
-  let(:test_vectors) { described_class::Example.load_file }
+  let(:test_vectors) { described_class::Example.load_all }
+
+  # Ciphersuites in the test vectors which aren't implemented yet
+  let(:unsupported_algs) do
+    %w[
+      XSTREAM_X25519_HKDF_SHA256_AES256_SIV
+      XSTREAM_X25519_HKDF_SHA256_AES256_PMAC_SIV
+      XSTREAM_X25519_HKDF_SHA256_AES128_GCM_SIV
+      XSTREAM_X25519_HKDF_SHA256_AES256_GCM_SIV
+    ]
+  end
 
   context "Encryptor" do
     describe "seal" do
@@ -17,6 +27,7 @@ RSpec.describe XStream::X25519HKDF do
           when "XSTREAM_X25519_HKDF_SHA256_AES128_PMAC_SIV"
             encryption_alg = "AES-PMAC-SIV"
             digest_alg = "SHA-256"
+          when *unsupported_algs then next # ciphersuite not yet supported by this implementation
           else raise "unknown algorithm in test vectors: #{ex.alg}"
           end
 
@@ -48,6 +59,7 @@ RSpec.describe XStream::X25519HKDF do
           when "XSTREAM_X25519_HKDF_SHA256_AES128_PMAC_SIV"
             encryption_alg = "AES-PMAC-SIV"
             digest_alg = "SHA-256"
+          when *unsupported_algs then next # ciphersuite not yet supported by this implementation
           else raise "unknown algorithm in test vectors: #{ex.alg}"
           end
 
@@ -0,0 +1,114 @@
+//! Write seed corpora for the fuzz targets in `fuzz/` from the test vectors
+//! in `vectors/xstream.tjson`, `vectors/xstream_generated.tjson` and
+//! `vectors/xstream_invalid.tjson`.
+//!
+//! Usage: `cargo run --example fuzz_corpus`
+//!
+//! The seeds are checked in, so fuzzing can start without running this first.
+
+extern crate rand;
+extern crate xstream;
+
+#[path = "../tests/test_rng/mod.rs"]
+mod test_rng;
+
+#[allow(dead_code)]
+#[path = "../tests/xstream_vectors/mod.rs"]
+mod xstream_vectors;
+
+use std::fs;
+use std::path::Path;
+use xstream::armor::ArmorWriter;
+use xstream::container::{self, Header};
+use xstream::{Algorithm, PublicKey};
+use xstream_vectors::{XStreamExample, XStreamInvalidExample};
+
+const CORPUS_DIR: &str = "fuzz/corpus";
+
+fn main() {
+    let mut public_keys = Vec::new();
+
+    for (i, ex) in XStreamExample::load_known().iter().enumerate() {
+        write_example(&format!("known-{:02}", i), ex, &mut public_keys);
+    }
+
+    for (i, ex) in XStreamExample::load_generated().iter().enumerate() {
+        write_example(&format!("vector-{:02}", i), ex, &mut public_keys);
+    }
+
+    for ex in XStreamInvalidExample::load_all() {
+        if !public_keys.contains(&ex.ephemeral_pubkey) {
+            public_keys.push(ex.ephemeral_pubkey.clone());
+        }
+    }
+
+    for (i, public_key) in public_keys.iter().enumerate() {
+        write("public_key", &format!("key-{:02}", i), public_key);
+    }
+}
+
+/// Write seeds for every target from a single example, collecting its
+/// ephemeral key if it's suitable for `public_key`
+fn write_example(name: &str, ex: &XStreamExample, public_keys: &mut Vec<Vec<u8>>) {
+    let algorithm: Algorithm = ex.alg.parse().expect("supported algorithm");
+    let index = Algorithm::all().iter().position(|&a| a == algorithm).unwrap();
+
+    // open_segments: keys and salt, then flag, AD and ciphertext per block
+    let mut input = vec![index as u8];
+    input.extend_from_slice(&ex.sealingkey.seckey);
+    input.extend_from_slice(&ex.ephemeralkey.pubkey);
+
+    match ex.salt {
+        Some(ref salt) => {
+            input.push(salt.len() as u8 + 1);
+            input.extend_from_slice(salt);
+        }
+        None => input.push(0),
+    }
+
+    for (j, block) in ex.blocks.iter().enumerate() {
+        input.push((j + 1 == ex.blocks.len()) as u8);
+        input.push(block.ad.len() as u8);
+        input.extend_from_slice(&block.ad);
+        input.extend_from_slice(&(block.ciphertext.len() as u16).to_be_bytes());
+        input.extend_from_slice(&block.ciphertext);
+    }
+
+    write("open_segments", name, &input);
+
+    let unbound = ex.blocks.len() == 1 && ex.salt.is_none() && ex.blocks[0].ad.is_empty();
+
+    if unbound && !public_keys.contains(&ex.ephemeralkey.pubkey) {
+        public_keys.push(ex.ephemeralkey.pubkey.clone());
+    }
+
+    // Containers don't carry associated data
+    if ex.blocks.iter().any(|block| !block.ad.is_empty()) {
+        return;
+    }
+
+    let ephemeral_key = PublicKey::new(&ex.ephemeralkey.pubkey);
+    let header = Header::new(algorithm, &ephemeral_key, ex.salt.as_deref());
+    let (last, rest) = ex.blocks.split_last().unwrap();
+
+    let mut binary = header.to_bytes();
+    let mut armor = ArmorWriter::new(Vec::new(), &header).unwrap();
+
+    for block in rest {
+        container::write_frame(&mut binary, false, &block.ciphertext).unwrap();
+        armor.write_next(&block.ciphertext).unwrap();
+    }
+
+    container::write_frame(&mut binary, true, &last.ciphertext).unwrap();
+    armor.write_last(&last.ciphertext).unwrap();
+
+    write("container", name, &binary);
+    write("armor", &format!("{}-binary", name), &binary);
+    write("armor", name, &armor.into_inner());
+}
+
+fn write(target: &str, name: &str, data: &[u8]) {
+    let dir = Path::new(CORPUS_DIR).join(target);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join(name), data).unwrap();
+}
@@ -0,0 +1,66 @@
+//! Regenerate `vectors/xstream_generated.tjson` and
+//! `vectors/xstream_invalid.tjson` deterministically from seeds (see
+//! `tests/xstream_vectors/generator.rs` for how inputs are derived). The
+//! known-answer examples in `vectors/xstream.tjson` are never overwritten.
+//!
+//! Usage: `cargo run --example generate_vectors [--check] [dir]`
+//!
+//! Writes to `../vectors` by default. With `--check`, exits with status 1
+//! if the files aren't up to date instead of writing them.
+
+extern crate rand;
+extern crate xstream;
+
+#[path = "../tests/test_rng/mod.rs"]
+mod test_rng;
+
+#[path = "../tests/xstream_vectors/generator.rs"]
+mod generator;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+const DEFAULT_DIR: &str = "../vectors";
+
+fn main() {
+    let mut check = false;
+    let mut dir = None;
+
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--check" => check = true,
+            _ if dir.is_none() && !arg.starts_with('-') => dir = Some(arg),
+            _ => {
+                eprintln!("Usage: generate_vectors [--check] [dir]");
+                process::exit(2);
+            }
+        }
+    }
+
+    let dir = dir.unwrap_or_else(|| DEFAULT_DIR.to_owned());
+    let files = [
+        ("xstream_generated.tjson", generator::generate()),
+        ("xstream_invalid.tjson", generator::generate_invalid()),
+    ];
+
+    for &(name, ref vectors) in &files {
+        let path = Path::new(&dir).join(name);
+
+        if check {
+            if fs::read_to_string(&path).ok().as_ref() != Some(vectors) {
+                eprintln!(
+                    "{} is out of date: run `cargo run --example generate_vectors`",
+                    path.display()
+                );
+                process::exit(1);
+            }
+        } else {
+            fs::write(&path, vectors).unwrap_or_else(|err| {
+                eprintln!("couldn't write {}: {}", path.display(), err);
+                process::exit(1);
+            });
+        }
+    }
+}
@@ -0,0 +1,169 @@
+/* xstream.h: C bindings for XSTREAM (generated by cbindgen, do not edit) */
+
+#ifndef XSTREAM_H
+#define XSTREAM_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Version of the ABI described by `xstream.h`, incremented whenever a
+// function signature or status code changes incompatibly
+#define XSTREAM_ABI_VERSION 1
+
+// Length of an X25519 key (private or public) in bytes
+#define XSTREAM_KEY_SIZE 32
+
+// Size of the authentication tag added to every segment
+#define XSTREAM_TAG_SIZE 16
+
+// The operation succeeded
+#define XSTREAM_OK 0
+
+// A required pointer was null
+#define XSTREAM_ERROR_NULL_POINTER 1
+
+// An argument was malformed, e.g. a key of the wrong length or an unknown
+// algorithm identifier
+#define XSTREAM_ERROR_INVALID_ARGUMENT 2
+
+// The output buffer is too small. The required length is stored in the
+// output length, and the handle is left unchanged.
+#define XSTREAM_ERROR_BUFFER_TOO_SMALL 3
+
+// A public key can't be used for key agreement (e.g. a low-order point)
+#define XSTREAM_ERROR_INVALID_KEY 4
+
+// A segment failed to authenticate
+#define XSTREAM_ERROR_AUTHENTICATION 5
+
+// The STREAM counter is exhausted, so only the last segment can be sealed
+#define XSTREAM_ERROR_EXHAUSTED 6
+
+// The last segment has already been sealed or opened with this handle
+#define XSTREAM_ERROR_FINISHED 7
+
+// An unexpected internal failure, e.g. the system RNG is unavailable
+#define XSTREAM_ERROR_INTERNAL 8
+
+// Opaque `XSTREAM` decryptor handle
+typedef struct XStreamDecryptor XStreamDecryptor;
+
+// Opaque `XSTREAM` encryptor handle
+typedef struct XStreamEncryptor XStreamEncryptor;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Obtain the version of the ABI implemented by this library, which callers
+// should compare against `XSTREAM_ABI_VERSION`
+uint32_t xstream_abi_version(void);
+
+// Obtain a static, NUL-terminated description of a status code
+const char *xstream_status_message(int status);
+
+// Generate a random private key, and its public key, using the operating
+// system's RNG. Both buffers must be `XSTREAM_KEY_SIZE` bytes.
+int xstream_keygen(uint8_t *private_key,
+                   size_t private_key_len,
+                   uint8_t *public_key,
+                   size_t public_key_len);
+
+// Compute the public key for a private key. Both buffers must be
+// `XSTREAM_KEY_SIZE` bytes.
+int xstream_public_key(const uint8_t *private_key,
+                       size_t private_key_len,
+                       uint8_t *public_key,
+                       size_t public_key_len);
+
+// Create an encryptor for the algorithm with the given `XSTREAM_*`
+// identifier, sealing a stream under `public_key` with an optional salt
+// (pass null for none).
+//
+// The ephemeral public key, which the recipient needs to open the stream,
+// is written to `ephemeral_key` (`XSTREAM_KEY_SIZE` bytes), and the new
+// handle to `*encryptor`.
+int xstream_encryptor_new(const char *algorithm,
+                          const uint8_t *public_key,
+                          size_t public_key_len,
+                          const uint8_t *salt,
+                          size_t salt_len,
+                          uint8_t *ephemeral_key,
+                          size_t ephemeral_key_len,
+                          XStreamEncryptor **encryptor);
+
+// Encrypt the next segment in the stream into `ciphertext`, which must
+// have room for `plaintext_len + XSTREAM_TAG_SIZE` bytes. The length of
+// the ciphertext is stored in `*ciphertext_len`.
+int xstream_encryptor_seal_next(XStreamEncryptor *encryptor,
+                                const uint8_t *ad,
+                                size_t ad_len,
+                                const uint8_t *plaintext,
+                                size_t plaintext_len,
+                                uint8_t *ciphertext,
+                                size_t ciphertext_capacity,
+                                size_t *ciphertext_len);
+
+// Encrypt the last segment in the stream, as `xstream_encryptor_seal_next`.
+// The handle can't seal anything else afterwards, but must still be freed.
+int xstream_encryptor_seal_last(XStreamEncryptor *encryptor,
+                                const uint8_t *ad,
+                                size_t ad_len,
+                                const uint8_t *plaintext,
+                                size_t plaintext_len,
+                                uint8_t *ciphertext,
+                                size_t ciphertext_capacity,
+                                size_t *ciphertext_len);
+
+// Free an encryptor handle. Null is ignored.
+void xstream_encryptor_free(XStreamEncryptor *encryptor);
+
+// Create a decryptor for the algorithm with the given `XSTREAM_*`
+// identifier, opening a stream sealed to `private_key` under the given
+// ephemeral key and optional salt (pass null for none). The new handle is
+// written to `*decryptor`.
+int xstream_decryptor_new(const char *algorithm,
+                          const uint8_t *private_key,
+                          size_t private_key_len,
+                          const uint8_t *ephemeral_key,
+                          size_t ephemeral_key_len,
+                          const uint8_t *salt,
+                          size_t salt_len,
+                          XStreamDecryptor **decryptor);
+
+// Decrypt the next segment in the stream into `plaintext`, which must have
+// room for `ciphertext_len - XSTREAM_TAG_SIZE` bytes. The length of the
+// plaintext is stored in `*plaintext_len`. If the segment fails to
+// authenticate, the handle is left unchanged.
+int xstream_decryptor_open_next(XStreamDecryptor *decryptor,
+                                const uint8_t *ad,
+                                size_t ad_len,
+                                const uint8_t *ciphertext,
+                                size_t ciphertext_len,
+                                uint8_t *plaintext,
+                                size_t plaintext_capacity,
+                                size_t *plaintext_len);
+
+// Decrypt the last segment in the stream, as `xstream_decryptor_open_next`.
+// The handle can't open anything else afterwards, even if the segment
+// fails to authenticate, but must still be freed.
+int xstream_decryptor_open_last(XStreamDecryptor *decryptor,
+                                const uint8_t *ad,
+                                size_t ad_len,
+                                const uint8_t *ciphertext,
+                                size_t ciphertext_len,
+                                uint8_t *plaintext,
+                                size_t plaintext_capacity,
+                                size_t *plaintext_len);
+
+// Free a decryptor handle. Null is ignored.
+void xstream_decryptor_free(XStreamDecryptor *decryptor);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* XSTREAM_H */
@@ -0,0 +1,461 @@
+//! `lib.rs`: C bindings for `XSTREAM`, built as a `cdylib` and `staticlib`
+//! and described by the cbindgen-generated `include/xstream.h`.
+//!
+//! Encryptors and decryptors are opaque handles which must be released
+//! with `xstream_encryptor_free` and `xstream_decryptor_free`. Every other
+//! function returns `XSTREAM_OK` or one of the `XSTREAM_ERROR_*` codes, and
+//! panics are never allowed to unwind into the caller.
+//!
+//! # Safety
+//!
+//! Buffers are passed as a pointer and an explicit length, and the pointer
+//! must be valid for that many bytes (it may be null if the length is
+//! zero). Output buffers must not overlap any input. Handles must come
+//! from the corresponding `*_new` function, and must not be used after
+//! they have been freed or concurrently from several threads.
+
+#![crate_name = "xstream_ffi"]
+
+#![deny(warnings, missing_docs, trivial_casts, trivial_numeric_casts)]
+#![deny(unused_import_braces, unused_qualifications)]
+#![allow(clippy::missing_safety_doc, clippy::too_many_arguments)]
+
+extern crate rand;
+extern crate xstream;
+
+use rand::OsRng;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+use xstream::{Algorithm, DynDecryptor, DynEncryptor, PrivateKey, PublicKey};
+
+/// Version of the ABI described by `xstream.h`, incremented whenever a
+/// function signature or status code changes incompatibly
+pub const XSTREAM_ABI_VERSION: u32 = 1;
+
+/// Length of an X25519 key (private or public) in bytes
+pub const XSTREAM_KEY_SIZE: usize = 32;
+
+/// Size of the authentication tag added to every segment
+pub const XSTREAM_TAG_SIZE: usize = 16;
+
+/// The operation succeeded
+pub const XSTREAM_OK: c_int = 0;
+
+/// A required pointer was null
+pub const XSTREAM_ERROR_NULL_POINTER: c_int = 1;
+
+/// An argument was malformed, e.g. a key of the wrong length or an unknown
+/// algorithm identifier
+pub const XSTREAM_ERROR_INVALID_ARGUMENT: c_int = 2;
+
+/// The output buffer is too small. The required length is stored in the
+/// output length, and the handle is left unchanged.
+pub const XSTREAM_ERROR_BUFFER_TOO_SMALL: c_int = 3;
+
+/// A public key can't be used for key agreement (e.g. a low-order point)
+pub const XSTREAM_ERROR_INVALID_KEY: c_int = 4;
+
+/// A segment failed to authenticate
+pub const XSTREAM_ERROR_AUTHENTICATION: c_int = 5;
+
+/// The STREAM counter is exhausted, so only the last segment can be sealed
+pub const XSTREAM_ERROR_EXHAUSTED: c_int = 6;
+
+/// The last segment has already been sealed or opened with this handle
+pub const XSTREAM_ERROR_FINISHED: c_int = 7;
+
+/// An unexpected internal failure, e.g. the system RNG is unavailable
+pub const XSTREAM_ERROR_INTERNAL: c_int = 8;
+
+/// Opaque `XSTREAM` encryptor handle
+pub struct XStreamEncryptor(Option<DynEncryptor>);
+
+/// Opaque `XSTREAM` decryptor handle
+pub struct XStreamDecryptor(Option<DynDecryptor>);
+
+/// Obtain the version of the ABI implemented by this library, which callers
+/// should compare against `XSTREAM_ABI_VERSION`
+#[no_mangle]
+pub extern "C" fn xstream_abi_version() -> u32 {
+    XSTREAM_ABI_VERSION
+}
+
+/// Obtain a static, NUL-terminated description of a status code
+#[no_mangle]
+pub extern "C" fn xstream_status_message(status: c_int) -> *const c_char {
+    let message: &'static [u8] = match status {
+        XSTREAM_OK => b"success\0",
+        XSTREAM_ERROR_NULL_POINTER => b"null pointer\0",
+        XSTREAM_ERROR_INVALID_ARGUMENT => b"invalid argument\0",
+        XSTREAM_ERROR_BUFFER_TOO_SMALL => b"buffer too small\0",
+        XSTREAM_ERROR_INVALID_KEY => b"invalid key\0",
+        XSTREAM_ERROR_AUTHENTICATION => b"authentication failed\0",
+        XSTREAM_ERROR_EXHAUSTED => b"stream counter exhausted\0",
+        XSTREAM_ERROR_FINISHED => b"stream already finished\0",
+        XSTREAM_ERROR_INTERNAL => b"internal error\0",
+        _ => b"unknown status\0",
+    };
+
+    message.as_ptr() as *const c_char
+}
+
+/// Generate a random private key, and its public key, using the operating
+/// system's RNG. Both buffers must be `XSTREAM_KEY_SIZE` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn xstream_keygen(
+    private_key: *mut u8,
+    private_key_len: usize,
+    public_key: *mut u8,
+    public_key_len: usize,
+) -> c_int {
+    call(|| {
+        let private_key = key_output(private_key, private_key_len)?;
+        let public_key = key_output(public_key, public_key_len)?;
+        let mut csprng = OsRng::new().or(Err(XSTREAM_ERROR_INTERNAL))?;
+        let generated = PrivateKey::generate(&mut csprng);
+
+        private_key.copy_from_slice(generated.as_bytes());
+        public_key.copy_from_slice(generated.public_key().as_bytes());
+        Ok(())
+    })
+}
+
+/// Compute the public key for a private key. Both buffers must be
+/// `XSTREAM_KEY_SIZE` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn xstream_public_key(
+    private_key: *const u8,
+    private_key_len: usize,
+    public_key: *mut u8,
+    public_key_len: usize,
+) -> c_int {
+    call(|| {
+        let private_key = parse_private_key(private_key, private_key_len)?;
+        let public_key = key_output(public_key, public_key_len)?;
+
+        public_key.copy_from_slice(private_key.public_key().as_bytes());
+        Ok(())
+    })
+}
+
+/// Create an encryptor for the algorithm with the given `XSTREAM_*`
+/// identifier, sealing a stream under `public_key` with an optional salt
+/// (pass null for none).
+///
+/// The ephemeral public key, which the recipient needs to open the stream,
+/// is written to `ephemeral_key` (`XSTREAM_KEY_SIZE` bytes), and the new
+/// handle to `*encryptor`.
+#[no_mangle]
+pub unsafe extern "C" fn xstream_encryptor_new(
+    algorithm: *const c_char,
+    public_key: *const u8,
+    public_key_len: usize,
+    salt: *const u8,
+    salt_len: usize,
+    ephemeral_key: *mut u8,
+    ephemeral_key_len: usize,
+    encryptor: *mut *mut XStreamEncryptor,
+) -> c_int {
+    call(|| {
+        let algorithm = parse_algorithm(algorithm)?;
+        let public_key = parse_public_key(public_key, public_key_len)?;
+        let salt = optional_input(salt, salt_len)?;
+        let ephemeral_key = key_output(ephemeral_key, ephemeral_key_len)?;
+        let encryptor = handle_output(encryptor)?;
+
+        let mut csprng = OsRng::new().or(Err(XSTREAM_ERROR_INTERNAL))?;
+        let (inner, ephemeral_public) =
+            DynEncryptor::new(algorithm, &mut csprng, &public_key, salt)
+                .or(Err(XSTREAM_ERROR_INVALID_KEY))?;
+
+        ephemeral_key.copy_from_slice(ephemeral_public.as_bytes());
+        *encryptor = Box::into_raw(Box::new(XStreamEncryptor(Some(inner))));
+        Ok(())
+    })
+}
+
+/// Encrypt the next segment in the stream into `ciphertext`, which must
+/// have room for `plaintext_len + XSTREAM_TAG_SIZE` bytes. The length of
+/// the ciphertext is stored in `*ciphertext_len`.
+#[no_mangle]
+pub unsafe extern "C" fn xstream_encryptor_seal_next(
+    encryptor: *mut XStreamEncryptor,
+    ad: *const u8,
+    ad_len: usize,
+    plaintext: *const u8,
+    plaintext_len: usize,
+    ciphertext: *mut u8,
+    ciphertext_capacity: usize,
+    ciphertext_len: *mut usize,
+) -> c_int {
+    call(|| {
+        let encryptor = handle(encryptor)?;
+        let ad = input(ad, ad_len)?;
+        let plaintext = input(plaintext, plaintext_len)?;
+        let ciphertext = sized_output(
+            ciphertext,
+            ciphertext_capacity,
+            ciphertext_len,
+            plaintext.len() + XSTREAM_TAG_SIZE,
+        )?;
+
+        let inner = encryptor.0.as_mut().ok_or(XSTREAM_ERROR_FINISHED)?;
+        let sealed = inner
+            .try_seal_next(ad, plaintext)
+            .or(Err(XSTREAM_ERROR_EXHAUSTED))?;
+
+        ciphertext.copy_from_slice(&sealed);
+        Ok(())
+    })
+}
+
+/// Encrypt the last segment in the stream, as `xstream_encryptor_seal_next`.
+/// The handle can't seal anything else afterwards, but must still be freed.
+#[no_mangle]
+pub unsafe extern "C" fn xstream_encryptor_seal_last(
+    encryptor: *mut XStreamEncryptor,
+    ad: *const u8,
+    ad_len: usize,
+    plaintext: *const u8,
+    plaintext_len: usize,
+    ciphertext: *mut u8,
+    ciphertext_capacity: usize,
+    ciphertext_len: *mut usize,
+) -> c_int {
+    call(|| {
+        let encryptor = handle(encryptor)?;
+        let ad = input(ad, ad_len)?;
+        let plaintext = input(plaintext, plaintext_len)?;
+        let ciphertext = sized_output(
+            ciphertext,
+            ciphertext_capacity,
+            ciphertext_len,
+            plaintext.len() + XSTREAM_TAG_SIZE,
+        )?;
+
+        let inner = encryptor.0.take().ok_or(XSTREAM_ERROR_FINISHED)?;
+        ciphertext.copy_from_slice(&inner.seal_last(ad, plaintext));
+        Ok(())
+    })
+}
+
+/// Free an encryptor handle. Null is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn xstream_encryptor_free(encryptor: *mut XStreamEncryptor) {
+    if !encryptor.is_null() {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(encryptor))));
+    }
+}
+
+/// Create a decryptor for the algorithm with the given `XSTREAM_*`
+/// identifier, opening a stream sealed to `private_key` under the given
+/// ephemeral key and optional salt (pass null for none). The new handle is
+/// written to `*decryptor`.
+#[no_mangle]
+pub unsafe extern "C" fn xstream_decryptor_new(
+    algorithm: *const c_char,
+    private_key: *const u8,
+    private_key_len: usize,
+    ephemeral_key: *const u8,
+    ephemeral_key_len: usize,
+    salt: *const u8,
+    salt_len: usize,
+    decryptor: *mut *mut XStreamDecryptor,
+) -> c_int {
+    call(|| {
+        let algorithm = parse_algorithm(algorithm)?;
+        let private_key = parse_private_key(private_key, private_key_len)?;
+        let ephemeral_key = parse_public_key(ephemeral_key, ephemeral_key_len)?;
+        let salt = optional_input(salt, salt_len)?;
+        let decryptor = handle_output(decryptor)?;
+
+        let inner = DynDecryptor::new(algorithm, &private_key, &ephemeral_key, salt)
+            .or(Err(XSTREAM_ERROR_INVALID_KEY))?;
+
+        *decryptor = Box::into_raw(Box::new(XStreamDecryptor(Some(inner))));
+        Ok(())
+    })
+}
+
+/// Decrypt the next segment in the stream into `plaintext`, which must have
+/// room for `ciphertext_len - XSTREAM_TAG_SIZE` bytes. The length of the
+/// plaintext is stored in `*plaintext_len`. If the segment fails to
+/// authenticate, the handle is left unchanged.
+#[no_mangle]
+pub unsafe extern "C" fn xstream_decryptor_open_next(
+    decryptor: *mut XStreamDecryptor,
+    ad: *const u8,
+    ad_len: usize,
+    ciphertext: *const u8,
+    ciphertext_len: usize,
+    plaintext: *mut u8,
+    plaintext_capacity: usize,
+    plaintext_len: *mut usize,
+) -> c_int {
+    call(|| {
+        let decryptor = handle(decryptor)?;
+        let ad = input(ad, ad_len)?;
+        let ciphertext = input(ciphertext, ciphertext_len)?;
+        let plaintext = sized_output(
+            plaintext,
+            plaintext_capacity,
+            plaintext_len,
+            ciphertext.len().saturating_sub(XSTREAM_TAG_SIZE),
+        )?;
+
+        let inner = decryptor.0.as_mut().ok_or(XSTREAM_ERROR_FINISHED)?;
+        let opened = inner
+            .open_next(ad, ciphertext)
+            .or(Err(XSTREAM_ERROR_AUTHENTICATION))?;
+
+        plaintext.copy_from_slice(&opened);
+        Ok(())
+    })
+}
+
+/// Decrypt the last segment in the stream, as `xstream_decryptor_open_next`.
+/// The handle can't open anything else afterwards, even if the segment
+/// fails to authenticate, but must still be freed.
+#[no_mangle]
+pub unsafe extern "C" fn xstream_decryptor_open_last(
+    decryptor: *mut XStreamDecryptor,
+    ad: *const u8,
+    ad_len: usize,
+    ciphertext: *const u8,
+    ciphertext_len: usize,
+    plaintext: *mut u8,
+    plaintext_capacity: usize,
+    plaintext_len: *mut usize,
+) -> c_int {
+    call(|| {
+        let decryptor = handle(decryptor)?;
+        let ad = input(ad, ad_len)?;
+        let ciphertext = input(ciphertext, ciphertext_len)?;
+        let plaintext = sized_output(
+            plaintext,
+            plaintext_capacity,
+            plaintext_len,
+            ciphertext.len().saturating_sub(XSTREAM_TAG_SIZE),
+        )?;
+
+        let inner = decryptor.0.take().ok_or(XSTREAM_ERROR_FINISHED)?;
+        let opened = inner
+            .open_last(ad, ciphertext)
+            .or(Err(XSTREAM_ERROR_AUTHENTICATION))?;
+
+        plaintext.copy_from_slice(&opened);
+        Ok(())
+    })
+}
+
+/// Free a decryptor handle. Null is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn xstream_decryptor_free(decryptor: *mut XStreamDecryptor) {
+    if !decryptor.is_null() {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(decryptor))));
+    }
+}
+
+/// Run the body of an FFI function, converting its result (or a panic) into
+/// a status code
+fn call<F: FnOnce() -> Result<(), c_int>>(body: F) -> c_int {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => XSTREAM_OK,
+        Ok(Err(status)) => status,
+        Err(_) => XSTREAM_ERROR_INTERNAL,
+    }
+}
+
+/// Borrow an input buffer
+unsafe fn input<'a>(ptr: *const u8, len: usize) -> Result<&'a [u8], c_int> {
+    if len == 0 {
+        Ok(&[])
+    } else if ptr.is_null() {
+        Err(XSTREAM_ERROR_NULL_POINTER)
+    } else {
+        Ok(slice::from_raw_parts(ptr, len))
+    }
+}
+
+/// Borrow an input buffer which may be omitted by passing null
+unsafe fn optional_input<'a>(ptr: *const u8, len: usize) -> Result<Option<&'a [u8]>, c_int> {
+    if ptr.is_null() && len == 0 {
+        Ok(None)
+    } else {
+        input(ptr, len).map(Some)
+    }
+}
+
+/// Borrow an output buffer for a key
+unsafe fn key_output<'a>(ptr: *mut u8, len: usize) -> Result<&'a mut [u8], c_int> {
+    if len != XSTREAM_KEY_SIZE {
+        Err(XSTREAM_ERROR_INVALID_ARGUMENT)
+    } else if ptr.is_null() {
+        Err(XSTREAM_ERROR_NULL_POINTER)
+    } else {
+        Ok(slice::from_raw_parts_mut(ptr, len))
+    }
+}
+
+/// Store the length of an output, and borrow exactly that much of the
+/// output buffer if it's large enough
+unsafe fn sized_output<'a>(
+    ptr: *mut u8,
+    capacity: usize,
+    len_out: *mut usize,
+    len: usize,
+) -> Result<&'a mut [u8], c_int> {
+    if len_out.is_null() {
+        return Err(XSTREAM_ERROR_NULL_POINTER);
+    }
+
+    *len_out = len;
+
+    if capacity < len {
+        Err(XSTREAM_ERROR_BUFFER_TOO_SMALL)
+    } else if len == 0 {
+        Ok(&mut [])
+    } else if ptr.is_null() {
+        Err(XSTREAM_ERROR_NULL_POINTER)
+    } else {
+        Ok(slice::from_raw_parts_mut(ptr, len))
+    }
+}
+
+/// Borrow the location a new handle is written to
+unsafe fn handle_output<'a, T>(ptr: *mut *mut T) -> Result<&'a mut *mut T, c_int> {
+    ptr.as_mut().ok_or(XSTREAM_ERROR_NULL_POINTER)
+}
+
+/// Borrow a handle
+unsafe fn handle<'a, T>(ptr: *mut T) -> Result<&'a mut T, c_int> {
+    ptr.as_mut().ok_or(XSTREAM_ERROR_NULL_POINTER)
+}
+
+/// Parse a NUL-terminated `XSTREAM_*` algorithm identifier
+unsafe fn parse_algorithm(ptr: *const c_char) -> Result<Algorithm, c_int> {
+    if ptr.is_null() {
+        return Err(XSTREAM_ERROR_NULL_POINTER);
+    }
+
+    CStr::from_ptr(ptr)
+        .to_str()
+        .ok()
+        .and_then(|identifier| identifier.parse().ok())
+        .ok_or(XSTREAM_ERROR_INVALID_ARGUMENT)
+}
+
+/// Parse a private key, which must be `XSTREAM_KEY_SIZE` bytes
+unsafe fn parse_private_key(ptr: *const u8, len: usize) -> Result<PrivateKey, c_int> {
+    match input(ptr, len)? {
+        bytes if bytes.len() == XSTREAM_KEY_SIZE => Ok(PrivateKey::new(bytes)),
+        _ => Err(XSTREAM_ERROR_INVALID_ARGUMENT),
+    }
+}
+
+/// Parse a public key, which must be `XSTREAM_KEY_SIZE` bytes
+unsafe fn parse_public_key(ptr: *const u8, len: usize) -> Result<PublicKey, c_int> {
+    PublicKey::from_slice(input(ptr, len)?).or(Err(XSTREAM_ERROR_INVALID_ARGUMENT))
+}
@@ -0,0 +1,253 @@
+/* xstream_test.c: exercises the C bindings through include/xstream.h */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "xstream.h"
+
+#define ALGORITHM "XSTREAM_X25519_HKDF_SHA256_AES128_SIV"
+
+#define CHECK(expr)                                                         \
+    do {                                                                    \
+        if (!(expr)) {                                                      \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,          \
+                    __LINE__, #expr);                                       \
+            exit(1);                                                        \
+        }                                                                   \
+    } while (0)
+
+#define CHECK_STATUS(expr, expected)                                        \
+    do {                                                                    \
+        int status_ = (expr);                                               \
+        if (status_ != (expected)) {                                        \
+            fprintf(stderr, "%s:%d: %s returned %d (%s)\n", __FILE__,       \
+                    __LINE__, #expr, status_,                               \
+                    xstream_status_message(status_));                       \
+            exit(1);                                                        \
+        }                                                                   \
+    } while (0)
+
+static const uint8_t AD[] = "associated data";
+static const uint8_t SALT[] = "salt";
+static const char *MESSAGES[] = {"Hello, ", "world", "!"};
+
+#define MESSAGE_COUNT (sizeof(MESSAGES) / sizeof(MESSAGES[0]))
+#define MAX_CIPHERTEXT 64
+
+typedef struct {
+    uint8_t ephemeral_key[XSTREAM_KEY_SIZE];
+    uint8_t ciphertexts[MESSAGE_COUNT][MAX_CIPHERTEXT];
+    size_t lengths[MESSAGE_COUNT];
+} sealed_stream;
+
+static void seal_stream(const uint8_t *public_key, sealed_stream *sealed) {
+    XStreamEncryptor *encryptor = NULL;
+    size_t i;
+
+    CHECK_STATUS(xstream_encryptor_new(ALGORITHM, public_key, XSTREAM_KEY_SIZE,
+                                       SALT, sizeof(SALT) - 1,
+                                       sealed->ephemeral_key, XSTREAM_KEY_SIZE,
+                                       &encryptor),
+                 XSTREAM_OK);
+    CHECK(encryptor != NULL);
+
+    for (i = 0; i < MESSAGE_COUNT; i++) {
+        const uint8_t *plaintext = (const uint8_t *)MESSAGES[i];
+        size_t plaintext_len = strlen(MESSAGES[i]);
+
+        if (i + 1 < MESSAGE_COUNT) {
+            CHECK_STATUS(xstream_encryptor_seal_next(
+                             encryptor, AD, sizeof(AD) - 1, plaintext,
+                             plaintext_len, sealed->ciphertexts[i],
+                             MAX_CIPHERTEXT, &sealed->lengths[i]),
+                         XSTREAM_OK);
+        } else {
+            CHECK_STATUS(xstream_encryptor_seal_last(
+                             encryptor, AD, sizeof(AD) - 1, plaintext,
+                             plaintext_len, sealed->ciphertexts[i],
+                             MAX_CIPHERTEXT, &sealed->lengths[i]),
+                         XSTREAM_OK);
+        }
+
+        CHECK(sealed->lengths[i] == plaintext_len + XSTREAM_TAG_SIZE);
+    }
+
+    xstream_encryptor_free(encryptor);
+}
+
+/* Open a sealed stream, returning the status of the first failure */
+static int open_stream(const uint8_t *private_key, const sealed_stream *sealed,
+                       size_t count) {
+    XStreamDecryptor *decryptor = NULL;
+    uint8_t plaintext[MAX_CIPHERTEXT];
+    size_t plaintext_len, i;
+    int status;
+
+    CHECK_STATUS(xstream_decryptor_new(ALGORITHM, private_key, XSTREAM_KEY_SIZE,
+                                       sealed->ephemeral_key, XSTREAM_KEY_SIZE,
+                                       SALT, sizeof(SALT) - 1, &decryptor),
+                 XSTREAM_OK);
+
+    for (i = 0; i < count; i++) {
+        if (i + 1 < count) {
+            status = xstream_decryptor_open_next(
+                decryptor, AD, sizeof(AD) - 1, sealed->ciphertexts[i],
+                sealed->lengths[i], plaintext, sizeof(plaintext),
+                &plaintext_len);
+        } else {
+            status = xstream_decryptor_open_last(
+                decryptor, AD, sizeof(AD) - 1, sealed->ciphertexts[i],
+                sealed->lengths[i], plaintext, sizeof(plaintext),
+                &plaintext_len);
+        }
+
+        if (status != XSTREAM_OK) {
+            xstream_decryptor_free(decryptor);
+            return status;
+        }
+
+        CHECK(plaintext_len == strlen(MESSAGES[i]));
+        CHECK(memcmp(plaintext, MESSAGES[i], plaintext_len) == 0);
+    }
+
+    xstream_decryptor_free(decryptor);
+    return XSTREAM_OK;
+}
+
+static void test_round_trip(void) {
+    uint8_t private_key[XSTREAM_KEY_SIZE], public_key[XSTREAM_KEY_SIZE];
+    uint8_t derived[XSTREAM_KEY_SIZE];
+    sealed_stream sealed;
+
+    CHECK_STATUS(xstream_keygen(private_key, sizeof(private_key), public_key,
+                                sizeof(public_key)),
+                 XSTREAM_OK);
+    CHECK_STATUS(xstream_public_key(private_key, sizeof(private_key), derived,
+                                    sizeof(derived)),
+                 XSTREAM_OK);
+    CHECK(memcmp(derived, public_key, XSTREAM_KEY_SIZE) == 0);
+
+    seal_stream(public_key, &sealed);
+    CHECK_STATUS(open_stream(private_key, &sealed, MESSAGE_COUNT), XSTREAM_OK);
+}
+
+static void test_authentication_failure(void) {
+    uint8_t private_key[XSTREAM_KEY_SIZE], public_key[XSTREAM_KEY_SIZE];
+    sealed_stream sealed, tampered;
+
+    CHECK_STATUS(xstream_keygen(private_key, sizeof(private_key), public_key,
+                                sizeof(public_key)),
+                 XSTREAM_OK);
+    seal_stream(public_key, &sealed);
+
+    tampered = sealed;
+    tampered.ciphertexts[1][0] ^= 1;
+    CHECK_STATUS(open_stream(private_key, &tampered, MESSAGE_COUNT),
+                 XSTREAM_ERROR_AUTHENTICATION);
+
+    /* Truncated streams end with a segment that wasn't sealed as the last */
+    CHECK_STATUS(open_stream(private_key, &sealed, MESSAGE_COUNT - 1),
+                 XSTREAM_ERROR_AUTHENTICATION);
+}
+
+static void test_errors(void) {
+    uint8_t private_key[XSTREAM_KEY_SIZE], public_key[XSTREAM_KEY_SIZE];
+    uint8_t ephemeral_key[XSTREAM_KEY_SIZE], low_order[XSTREAM_KEY_SIZE];
+    uint8_t ciphertext[MAX_CIPHERTEXT], plaintext[MAX_CIPHERTEXT];
+    size_t ciphertext_len = 0, plaintext_len = 0;
+    XStreamEncryptor *encryptor = NULL;
+    XStreamDecryptor *decryptor = NULL;
+
+    CHECK(xstream_abi_version() == XSTREAM_ABI_VERSION);
+    CHECK(strcmp(xstream_status_message(XSTREAM_ERROR_AUTHENTICATION),
+                 "authentication failed") == 0);
+
+    CHECK_STATUS(xstream_keygen(private_key, sizeof(private_key), public_key,
+                                sizeof(public_key)),
+                 XSTREAM_OK);
+    CHECK_STATUS(xstream_keygen(NULL, XSTREAM_KEY_SIZE, public_key,
+                                sizeof(public_key)),
+                 XSTREAM_ERROR_NULL_POINTER);
+    CHECK_STATUS(xstream_public_key(private_key, 31, public_key,
+                                    sizeof(public_key)),
+                 XSTREAM_ERROR_INVALID_ARGUMENT);
+
+    CHECK_STATUS(xstream_encryptor_new("XSTREAM_UNKNOWN", public_key,
+                                       XSTREAM_KEY_SIZE, NULL, 0,
+                                       ephemeral_key, XSTREAM_KEY_SIZE,
+                                       &encryptor),
+                 XSTREAM_ERROR_INVALID_ARGUMENT);
+    CHECK(encryptor == NULL);
+
+    memset(low_order, 0, sizeof(low_order));
+    CHECK_STATUS(xstream_encryptor_new(ALGORITHM, low_order, XSTREAM_KEY_SIZE,
+                                       NULL, 0, ephemeral_key,
+                                       XSTREAM_KEY_SIZE, &encryptor),
+                 XSTREAM_ERROR_INVALID_KEY);
+    CHECK_STATUS(xstream_decryptor_new(ALGORITHM, private_key,
+                                       XSTREAM_KEY_SIZE, low_order,
+                                       XSTREAM_KEY_SIZE, NULL, 0, &decryptor),
+                 XSTREAM_ERROR_INVALID_KEY);
+    CHECK(decryptor == NULL);
+
+    CHECK_STATUS(xstream_encryptor_new(ALGORITHM, public_key, XSTREAM_KEY_SIZE,
+                                       NULL, 0, ephemeral_key,
+                                       XSTREAM_KEY_SIZE, &encryptor),
+                 XSTREAM_OK);
+
+    /* A short buffer reports the required length without sealing */
+    CHECK_STATUS(xstream_encryptor_seal_next(encryptor, NULL, 0,
+                                             (const uint8_t *)"hello", 5,
+                                             ciphertext, 20, &ciphertext_len),
+                 XSTREAM_ERROR_BUFFER_TOO_SMALL);
+    CHECK(ciphertext_len == 5 + XSTREAM_TAG_SIZE);
+
+    CHECK_STATUS(xstream_encryptor_seal_last(encryptor, NULL, 0,
+                                             (const uint8_t *)"hello", 5,
+                                             ciphertext, sizeof(ciphertext),
+                                             &ciphertext_len),
+                 XSTREAM_OK);
+    CHECK_STATUS(xstream_encryptor_seal_next(encryptor, NULL, 0, NULL, 0,
+                                             ciphertext, sizeof(ciphertext),
+                                             &ciphertext_len),
+                 XSTREAM_ERROR_FINISHED);
+    xstream_encryptor_free(encryptor);
+
+    /* Only the first segment was sealed, as the last one */
+    CHECK_STATUS(xstream_decryptor_new(ALGORITHM, private_key,
+                                       XSTREAM_KEY_SIZE, ephemeral_key,
+                                       XSTREAM_KEY_SIZE, NULL, 0, &decryptor),
+                 XSTREAM_OK);
+    CHECK_STATUS(xstream_decryptor_open_last(decryptor, NULL, 0, ciphertext,
+                                             5 + XSTREAM_TAG_SIZE, plaintext,
+                                             4, &plaintext_len),
+                 XSTREAM_ERROR_BUFFER_TOO_SMALL);
+    CHECK(plaintext_len == 5);
+    CHECK_STATUS(xstream_decryptor_open_last(decryptor, NULL, 0, ciphertext,
+                                             5 + XSTREAM_TAG_SIZE, plaintext,
+                                             sizeof(plaintext), &plaintext_len),
+                 XSTREAM_OK);
+    CHECK(memcmp(plaintext, "hello", 5) == 0);
+    CHECK_STATUS(xstream_decryptor_open_last(decryptor, NULL, 0, ciphertext,
+                                             5 + XSTREAM_TAG_SIZE, plaintext,
+                                             sizeof(plaintext), &plaintext_len),
+                 XSTREAM_ERROR_FINISHED);
+    xstream_decryptor_free(decryptor);
+
+    CHECK_STATUS(xstream_decryptor_open_next(NULL, NULL, 0, NULL, 0, NULL, 0,
+                                             &plaintext_len),
+                 XSTREAM_ERROR_NULL_POINTER);
+
+    xstream_encryptor_free(NULL);
+    xstream_decryptor_free(NULL);
+}
+
+int main(void) {
+    test_round_trip();
+    test_authentication_failure();
+    test_errors();
+
+    printf("ok\n");
+    return 0;
+}
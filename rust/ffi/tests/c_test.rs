@@ -0,0 +1,45 @@
+//! Compiles `tests/c/xstream_test.c` against `include/xstream.h` and the
+//! static library, and runs it
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Directory containing the library, i.e. the parent of `deps/` where this
+/// test executable lives
+fn library_dir() -> PathBuf {
+    let exe = env::current_exe().unwrap();
+    exe.parent().unwrap().parent().unwrap().to_owned()
+}
+
+#[cfg(unix)]
+#[test]
+fn c_tests() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let library = library_dir().join("libxstream_ffi.a");
+    assert!(library.exists(), "missing {}", library.display());
+
+    let executable = env::temp_dir().join(format!("xstream-ffi-test-{}", std::process::id()));
+    let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_owned());
+
+    let status = Command::new(compiler)
+        .args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-I"])
+        .arg(manifest_dir.join("include"))
+        .arg(manifest_dir.join("tests/c/xstream_test.c"))
+        .arg(&library)
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&executable)
+        .status()
+        .unwrap();
+    assert!(status.success(), "failed to compile the C tests");
+
+    let output = Command::new(&executable).output().unwrap();
+    let _ = std::fs::remove_file(&executable);
+
+    assert!(
+        output.status.success(),
+        "C tests failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(output.stdout, b"ok\n");
+}
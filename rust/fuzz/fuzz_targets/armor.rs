@@ -0,0 +1,54 @@
+//! Fuzz `ArmorReader` with arbitrary armored or binary input. Containers
+//! which read successfully must survive being armored and read again.
+
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate xstream;
+
+use xstream::armor::{ArmorReader, ArmorWriter};
+
+/// Longest segment read, as in the `xstream` tool
+const MAX_LEN: usize = 65_536 + 16;
+
+fuzz_target!(|data: &[u8]| {
+    let mut reader = match ArmorReader::new(data) {
+        Ok(reader) => reader,
+        Err(_) => return,
+    };
+
+    let mut segments = Vec::new();
+
+    loop {
+        match reader.read_segment(MAX_LEN) {
+            Ok((last, ciphertext)) => {
+                segments.push(ciphertext);
+
+                if last {
+                    break;
+                }
+            }
+            Err(_) => return,
+        }
+    }
+
+    let (last, rest) = segments.split_last().unwrap();
+    let mut writer = ArmorWriter::new(Vec::new(), reader.header()).unwrap();
+
+    for segment in rest {
+        writer.write_next(segment).unwrap();
+    }
+
+    writer.write_last(last).unwrap();
+    let armor = writer.into_inner();
+
+    let mut reader = ArmorReader::new(&armor[..]).expect("armor we wrote");
+    assert!(reader.is_armored());
+
+    for (i, segment) in segments.iter().enumerate() {
+        let (last, ciphertext) = reader.read_segment(MAX_LEN).expect("segment we wrote");
+        assert_eq!(last, i + 1 == segments.len());
+        assert_eq!(&ciphertext, segment);
+    }
+});
@@ -0,0 +1,43 @@
+//! Fuzz the container parser: `container::inspect`, and reading the header
+//! and frames incrementally, which must agree with each other.
+
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate xstream;
+
+use xstream::container::{self, Header};
+
+/// Longest segment read, as in the `xstream` tool
+const MAX_LEN: usize = 65_536 + 16;
+
+fuzz_target!(|data: &[u8]| {
+    let inspection = match container::inspect(data) {
+        Ok(inspection) => inspection,
+        Err(_) => return,
+    };
+
+    let _ = inspection.to_json();
+
+    let mut reader = data;
+    let header = Header::read_from(&mut reader).expect("header parsed by inspect");
+    assert_eq!(header, inspection.header);
+    assert_eq!(header.to_bytes(), &data[..inspection.header_len]);
+
+    for segment in &inspection.segments {
+        assert!(segment.offset + segment.len <= data.len());
+
+        if segment.len > MAX_LEN {
+            return;
+        }
+
+        let (last, ciphertext) = container::read_frame(&mut reader, MAX_LEN).expect("frame");
+        assert_eq!(last, segment.last);
+        assert_eq!(ciphertext, &data[segment.offset..segment.offset + segment.len]);
+    }
+
+    if inspection.truncated {
+        assert!(container::read_frame(&mut reader, MAX_LEN).is_err());
+    }
+});
@@ -0,0 +1,62 @@
+//! Fuzz `open_next` and `open_last` with arbitrary keys and sequences of
+//! segments, for every algorithm.
+//!
+//! The input is an algorithm index (1 byte), the recipient's private key
+//! (32 bytes), the ephemeral public key (32 bytes), the salt length plus one
+//! (1 byte, 0 meaning no salt) and the salt, followed by segments. Each
+//! segment is a flag byte (bit 0 set for the last segment), the associated
+//! data's length (1 byte) and the associated data, then the ciphertext's
+//! length (16-bit big endian) and the ciphertext.
+
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate xstream;
+
+use xstream::{Algorithm, DynDecryptor, PrivateKey, PublicKey};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = open(data);
+});
+
+fn open(mut data: &[u8]) -> Option<()> {
+    let algorithms = Algorithm::all();
+    let algorithm = algorithms[take(&mut data, 1)?[0] as usize % algorithms.len()];
+    let private_key = PrivateKey::new(take(&mut data, 32)?);
+    let ephemeral_key = PublicKey::new(take(&mut data, 32)?);
+
+    let salt = match take(&mut data, 1)?[0] as usize {
+        0 => None,
+        len => Some(take(&mut data, len - 1)?),
+    };
+
+    let mut decryptor = DynDecryptor::new(algorithm, &private_key, &ephemeral_key, salt).ok()?;
+
+    loop {
+        let last = take(&mut data, 1)?[0] & 1 != 0;
+        let ad_len = take(&mut data, 1)?[0] as usize;
+        let ad = take(&mut data, ad_len)?;
+        let len = take(&mut data, 2)?;
+        let ciphertext = take(&mut data, (len[0] as usize) << 8 | len[1] as usize)?;
+
+        if last {
+            let _ = decryptor.open_last(ad, ciphertext);
+            return Some(());
+        }
+
+        // A failed segment doesn't advance the stream, so keep going
+        let _ = decryptor.open_next(ad, ciphertext);
+    }
+}
+
+/// Split `n` bytes from the front of the input
+fn take<'a>(data: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    if data.len() < n {
+        return None;
+    }
+
+    let (head, tail) = data.split_at(n);
+    *data = tail;
+    Some(head)
+}
@@ -0,0 +1,27 @@
+//! Fuzz parsing public keys from arbitrary bytes, and key agreement with
+//! them (which must reject low-order keys rather than panic).
+
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate xstream;
+
+use xstream::{Algorithm, DynDecryptor, PrivateKey, PublicKey};
+
+fuzz_target!(|data: &[u8]| {
+    let public_key = match PublicKey::from_slice(data) {
+        Ok(public_key) => public_key,
+        Err(_) => return,
+    };
+
+    assert_eq!(public_key.as_bytes(), data);
+
+    let private_key = PrivateKey::new(&[0x42; 32]);
+    let _ = DynDecryptor::new(
+        Algorithm::X25519HkdfSha256Aes128Siv,
+        &private_key,
+        &public_key,
+        None,
+    );
+});
@@ -0,0 +1,16 @@
+#!/bin/bash
+# Install the dependencies of the Go, JavaScript, Python and Ruby ports, then
+# run the interop tests against them. Set XSTREAM_INTEROP_REQUIRED to fail
+# (rather than skip) when a port can't be driven. The Go port must be checked
+# out under $GOPATH as github.com/miscreant/xstream.
+
+set -e -x
+
+root=$(cd "$(dirname "$0")/.." && pwd)
+
+(cd "$root/go" && go get -d ./...)
+(cd "$root/js" && yarn install)
+(cd "$root/python" && python3 -m pip install --user -e .)
+(cd "$root/ruby" && bundle install)
+
+cargo test --test interop_test
@@ -0,0 +1,95 @@
+//! `algorithm.rs`: runtime identifiers for the algorithm combinations
+//! supported by `XSTREAM`
+
+use error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// `XSTREAM` algorithm suites, i.e. a combination of a key agreement
+/// function, a KDF, and an AEAD algorithm used with STREAM
+///
+/// HPKE suites (see the `hpke` module) are deliberately not included: HPKE
+/// contexts don't detect truncation, and don't support the exporter labels,
+/// rekeying and checkpoints `DynEncryptor` provides, so they can't be
+/// substituted for an `XSTREAM` suite named in a header.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Algorithm {
+    /// X25519 + HKDF-SHA-256 + AES-128-SIV
+    X25519HkdfSha256Aes128Siv,
+
+    /// X25519 + HKDF-SHA-256 + AES-256-SIV
+    X25519HkdfSha256Aes256Siv,
+
+    /// X25519 + HKDF-SHA-256 + AES-128-PMAC-SIV
+    X25519HkdfSha256Aes128PmacSiv,
+
+    /// X25519 + HKDF-SHA-256 + AES-256-PMAC-SIV
+    X25519HkdfSha256Aes256PmacSiv,
+
+    /// X25519 + HKDF-SHA-256 + AES-128-GCM-SIV
+    X25519HkdfSha256Aes128GcmSiv,
+
+    /// X25519 + HKDF-SHA-256 + AES-256-GCM-SIV
+    X25519HkdfSha256Aes256GcmSiv,
+}
+
+impl Algorithm {
+    /// All supported algorithms, in the order their test vectors appear
+    pub fn all() -> &'static [Algorithm] {
+        &[
+            Algorithm::X25519HkdfSha256Aes128Siv,
+            Algorithm::X25519HkdfSha256Aes128PmacSiv,
+            Algorithm::X25519HkdfSha256Aes256Siv,
+            Algorithm::X25519HkdfSha256Aes256PmacSiv,
+            Algorithm::X25519HkdfSha256Aes128GcmSiv,
+            Algorithm::X25519HkdfSha256Aes256GcmSiv,
+        ]
+    }
+
+    /// Obtain the `XSTREAM_*` identifier string for this algorithm
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Algorithm::X25519HkdfSha256Aes128Siv => "XSTREAM_X25519_HKDF_SHA256_AES128_SIV",
+            Algorithm::X25519HkdfSha256Aes256Siv => "XSTREAM_X25519_HKDF_SHA256_AES256_SIV",
+            Algorithm::X25519HkdfSha256Aes128PmacSiv => {
+                "XSTREAM_X25519_HKDF_SHA256_AES128_PMAC_SIV"
+            }
+            Algorithm::X25519HkdfSha256Aes256PmacSiv => {
+                "XSTREAM_X25519_HKDF_SHA256_AES256_PMAC_SIV"
+            }
+            Algorithm::X25519HkdfSha256Aes128GcmSiv => "XSTREAM_X25519_HKDF_SHA256_AES128_GCM_SIV",
+            Algorithm::X25519HkdfSha256Aes256GcmSiv => "XSTREAM_X25519_HKDF_SHA256_AES256_GCM_SIV",
+        }
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = Error;
+
+    /// Parse an `XSTREAM_*` algorithm identifier string
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "XSTREAM_X25519_HKDF_SHA256_AES128_SIV" => Ok(Algorithm::X25519HkdfSha256Aes128Siv),
+            "XSTREAM_X25519_HKDF_SHA256_AES256_SIV" => Ok(Algorithm::X25519HkdfSha256Aes256Siv),
+            "XSTREAM_X25519_HKDF_SHA256_AES128_PMAC_SIV" => {
+                Ok(Algorithm::X25519HkdfSha256Aes128PmacSiv)
+            }
+            "XSTREAM_X25519_HKDF_SHA256_AES256_PMAC_SIV" => {
+                Ok(Algorithm::X25519HkdfSha256Aes256PmacSiv)
+            }
+            "XSTREAM_X25519_HKDF_SHA256_AES128_GCM_SIV" => {
+                Ok(Algorithm::X25519HkdfSha256Aes128GcmSiv)
+            }
+            "XSTREAM_X25519_HKDF_SHA256_AES256_GCM_SIV" => {
+                Ok(Algorithm::X25519HkdfSha256Aes256GcmSiv)
+            }
+            _ => Err(Error),
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
@@ -0,0 +1,398 @@
+//! `armor.rs`: ASCII armor for `XSTREAM` containers, for pasting
+//! ciphertexts into text such as tickets, YAML, or email.
+//!
+//! An armored container is the binary container (see `container`) encoded
+//! as base64 (RFC 4648, with padding) between BEGIN and END lines:
+//!
+//! ```text
+//! -----BEGIN XSTREAM MESSAGE-----
+//! WFNUUkVBTQElWFNUUkVBTV9YMjU1MTlfSEtERl9TSEEyNTZfQUVTMTI4X1NJVgAA
+//! ...
+//! -----END XSTREAM MESSAGE-----
+//! ```
+//!
+//! Every body line but the last is exactly `ARMOR_LINE_WIDTH` characters
+//! long. Decoding is strict: anything besides whitespace around the lines
+//! and after the END line is rejected, including non-canonical base64.
+
+use container::{self, Header};
+use std::io::{self, BufRead, Read, Write};
+
+/// Line which begins an armored container
+pub const ARMOR_BEGIN: &str = "-----BEGIN XSTREAM MESSAGE-----";
+
+/// Line which ends an armored container
+pub const ARMOR_END: &str = "-----END XSTREAM MESSAGE-----";
+
+/// Number of base64 characters in every body line but the last
+pub const ARMOR_LINE_WIDTH: usize = 64;
+
+/// Number of bytes encoded in a full body line
+const LINE_BYTES: usize = ARMOR_LINE_WIDTH / 4 * 3;
+
+/// Longest line `ArmorReader` will read, so binary garbage without any
+/// newlines can't exhaust memory
+const MAX_LINE_LENGTH: u64 = 1024;
+
+/// Base64 alphabet (RFC 4648 section 4)
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Writes a container as ASCII armor, one sealed segment at a time.
+/// `write_last` must be called to finish the armor.
+pub struct ArmorWriter<W: Write> {
+    inner: W,
+    line: Vec<u8>,
+    finished: bool,
+}
+
+impl<W: Write> ArmorWriter<W> {
+    /// Begin the armor, writing the BEGIN line and the container header
+    pub fn new(mut inner: W, header: &Header) -> io::Result<Self> {
+        writeln!(inner, "{}", ARMOR_BEGIN)?;
+
+        let mut writer = Self {
+            inner,
+            line: Vec::with_capacity(LINE_BYTES),
+            finished: false,
+        };
+
+        writer.write_encoded(&header.to_bytes())?;
+        Ok(writer)
+    }
+
+    /// Write a segment sealed with `seal_next`
+    pub fn write_next(&mut self, ciphertext: &[u8]) -> io::Result<()> {
+        self.write_frame(false, ciphertext)
+    }
+
+    /// Write the segment sealed with `seal_last`, followed by the END line,
+    /// and flush the underlying writer
+    pub fn write_last(&mut self, ciphertext: &[u8]) -> io::Result<()> {
+        self.write_frame(true, ciphertext)?;
+
+        if !self.line.is_empty() {
+            self.inner.write_all(&encode(&self.line))?;
+            self.inner.write_all(b"\n")?;
+            self.line.clear();
+        }
+
+        writeln!(self.inner, "{}", ARMOR_END)?;
+        self.finished = true;
+        self.inner.flush()
+    }
+
+    /// Return the underlying writer
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Frame a sealed segment and encode it
+    fn write_frame(&mut self, last: bool, ciphertext: &[u8]) -> io::Result<()> {
+        if self.finished {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "armor already finished"));
+        }
+
+        let mut frame = Vec::with_capacity(ciphertext.len() + 5);
+        container::write_frame(&mut frame, last, ciphertext)?;
+        self.write_encoded(&frame)
+    }
+
+    /// Encode data, writing out every full line
+    fn write_encoded(&mut self, mut data: &[u8]) -> io::Result<()> {
+        while !data.is_empty() {
+            let n = (LINE_BYTES - self.line.len()).min(data.len());
+            self.line.extend_from_slice(&data[..n]);
+            data = &data[n..];
+
+            if self.line.len() == LINE_BYTES {
+                self.inner.write_all(&encode(&self.line))?;
+                self.inner.write_all(b"\n")?;
+                self.line.clear();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads a container which is either ASCII armored or binary, detecting
+/// which from the start of the input
+pub struct ArmorReader<R: BufRead> {
+    input: Input<R>,
+    header: Header,
+    finished: bool,
+}
+
+impl<R: BufRead> ArmorReader<R> {
+    /// Detect the input's encoding and read the container header.
+    ///
+    /// Returns an error of kind `InvalidData` if the input is neither an
+    /// armored nor a binary container, and `UnexpectedEof` if it's
+    /// truncated.
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let armored = match inner.fill_buf()?.first() {
+            Some(&byte) => byte == b'-' || byte.is_ascii_whitespace(),
+            None => return Err(io::ErrorKind::UnexpectedEof.into()),
+        };
+
+        let mut input = if armored {
+            Input::Armored(Decoder::new(inner)?)
+        } else {
+            Input::Binary(inner)
+        };
+
+        let header = Header::read_from(&mut input)?;
+
+        Ok(Self {
+            input,
+            header,
+            finished: false,
+        })
+    }
+
+    /// Container header
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Whether the input is ASCII armored
+    pub fn is_armored(&self) -> bool {
+        match self.input {
+            Input::Armored(_) => true,
+            Input::Binary(_) => false,
+        }
+    }
+
+    /// Read the next sealed segment, returning whether it's marked as the
+    /// last segment along with its ciphertext. Once the last segment has
+    /// been read, the rest of the input is checked to be empty (or, for
+    /// armor, to be the END line).
+    ///
+    /// Returns an error of kind `InvalidData` if the segment is malformed or
+    /// longer than `max_len`, and `UnexpectedEof` if the input is truncated.
+    pub fn read_segment(&mut self, max_len: usize) -> io::Result<(bool, Vec<u8>)> {
+        if self.finished {
+            return Err(invalid_data("already read the last segment"));
+        }
+
+        let (last, ciphertext) = container::read_frame(&mut self.input, max_len)?;
+
+        if last {
+            self.finished = true;
+            self.input.finish()?;
+        }
+
+        Ok((last, ciphertext))
+    }
+}
+
+/// Container bytes, either read directly or decoded from armor
+enum Input<R: BufRead> {
+    Binary(R),
+    Armored(Decoder<R>),
+}
+
+impl<R: BufRead> Input<R> {
+    /// Check nothing follows the container, besides whitespace after the
+    /// END line of armor
+    fn finish(&mut self) -> io::Result<()> {
+        match *self {
+            Input::Binary(ref mut reader) => {
+                if reader.fill_buf()?.is_empty() {
+                    Ok(())
+                } else {
+                    Err(trailing_data())
+                }
+            }
+            Input::Armored(ref mut decoder) => decoder.finish(),
+        }
+    }
+}
+
+impl<R: BufRead> Read for Input<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match *self {
+            Input::Binary(ref mut reader) => reader.read(buf),
+            Input::Armored(ref mut decoder) => decoder.read(buf),
+        }
+    }
+}
+
+/// Decodes the body of an armored container, one line at a time
+struct Decoder<R: BufRead> {
+    inner: R,
+    decoded: Vec<u8>,
+    pos: usize,
+    last_line: bool,
+    ended: bool,
+}
+
+impl<R: BufRead> Decoder<R> {
+    /// Skip leading blank lines and read the BEGIN line
+    fn new(inner: R) -> io::Result<Self> {
+        let mut decoder = Self {
+            inner,
+            decoded: Vec::with_capacity(LINE_BYTES),
+            pos: 0,
+            last_line: false,
+            ended: false,
+        };
+
+        loop {
+            match decoder.next_line()? {
+                Some(ref line) if line.is_empty() => continue,
+                Some(ref line) if line == ARMOR_BEGIN.as_bytes() => return Ok(decoder),
+                Some(_) => return Err(invalid_data("armor doesn't begin with a BEGIN line")),
+                None => return Err(io::ErrorKind::UnexpectedEof.into()),
+            }
+        }
+    }
+
+    /// Read the next line with surrounding whitespace removed, or `None` at
+    /// the end of the input
+    fn next_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut line = Vec::new();
+        (&mut self.inner).take(MAX_LINE_LENGTH).read_until(b'\n', &mut line)?;
+
+        if line.is_empty() {
+            return Ok(None);
+        }
+
+        if line.len() as u64 == MAX_LINE_LENGTH && line.last() != Some(&b'\n') {
+            return Err(invalid_data("armor line too long"));
+        }
+
+        let start = line.iter().position(|c| !c.is_ascii_whitespace());
+        let end = line.iter().rposition(|c| !c.is_ascii_whitespace());
+
+        Ok(Some(match (start, end) {
+            (Some(start), Some(end)) => line[start..=end].to_vec(),
+            _ => Vec::new(),
+        }))
+    }
+
+    /// Decode the next body line, or note the END line
+    fn decode_line(&mut self) -> io::Result<()> {
+        let line = self
+            .next_line()?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "armor has no END line"))?;
+
+        if line == ARMOR_END.as_bytes() {
+            self.ended = true;
+            return Ok(());
+        }
+
+        if self.last_line {
+            return Err(invalid_data("armor body continues after its last line"));
+        }
+
+        if line.is_empty() || line.len() > ARMOR_LINE_WIDTH || line.len() % 4 != 0 {
+            return Err(invalid_data("armor body line has an invalid length"));
+        }
+
+        self.last_line = line.len() < ARMOR_LINE_WIDTH || line.ends_with(b"=");
+        self.decoded.clear();
+        self.pos = 0;
+        decode(&line, &mut self.decoded)
+    }
+
+    /// Check the END line, and nothing but whitespace, follows the data
+    /// read so far
+    fn finish(&mut self) -> io::Result<()> {
+        if self.pos < self.decoded.len() {
+            return Err(trailing_data());
+        }
+
+        if !self.ended {
+            self.decode_line()?;
+
+            if !self.ended {
+                return Err(trailing_data());
+            }
+        }
+
+        while let Some(line) = self.next_line()? {
+            if !line.is_empty() {
+                return Err(invalid_data("unexpected data after the END line"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.decoded.len() && !self.ended {
+            self.decode_line()?;
+        }
+
+        let n = (self.decoded.len() - self.pos).min(buf.len());
+        buf[..n].copy_from_slice(&self.decoded[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Encode bytes as base64 with padding
+fn encode(bytes: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let mut block = [0u8; 3];
+        block[..chunk.len()].copy_from_slice(chunk);
+        let n = (u32::from(block[0]) << 16) | (u32::from(block[1]) << 8) | u32::from(block[2]);
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize]);
+            } else {
+                encoded.push(b'=');
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Decode a line of base64 whose length is a multiple of 4, rejecting
+/// invalid characters, misplaced padding, and non-zero padding bits
+fn decode(line: &[u8], out: &mut Vec<u8>) -> io::Result<()> {
+    let padding = line.iter().rev().take_while(|&&c| c == b'=').count();
+
+    if padding > 2 {
+        return Err(invalid_data("invalid base64 padding"));
+    }
+
+    let data = &line[..line.len() - padding];
+
+    for chunk in data.chunks(4) {
+        let mut n = 0u32;
+
+        for (i, &c) in chunk.iter().enumerate() {
+            let value = ALPHABET
+                .iter()
+                .position(|&a| a == c)
+                .ok_or_else(|| invalid_data("invalid base64 character"))?;
+            n |= (value as u32) << (18 - 6 * i);
+        }
+
+        let len = chunk.len() - 1;
+
+        if n & (0xff_ffff >> (8 * len)) != 0 {
+            return Err(invalid_data("non-canonical base64"));
+        }
+
+        out.extend_from_slice(&n.to_be_bytes()[1..=len]);
+    }
+
+    Ok(())
+}
+
+fn trailing_data() -> io::Error {
+    invalid_data("unexpected data after the last segment")
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
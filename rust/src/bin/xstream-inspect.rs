@@ -0,0 +1,101 @@
+//! `xstream-inspect`: reports the metadata of an `XSTREAM` container
+//! (algorithm, ephemeral key, salt and segment layout) without decrypting
+//! it, as text or as JSON for tooling.
+//!
+//! Exits with status 1 if the input isn't a container, and 2 on usage
+//! errors. Incomplete containers are reported rather than rejected.
+
+extern crate xstream;
+
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::process;
+use xstream::container::{self, Inspection};
+
+const USAGE: &str = "Usage: xstream-inspect [--json] [file]
+
+Reads the container from stdin if no file (or `-`) is given.";
+
+fn main() {
+    let mut json = false;
+    let mut path = None;
+
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') && arg != "-" => usage(&format!("unknown option `{}`", arg)),
+            _ if path.is_some() => usage("too many arguments"),
+            _ => path = Some(arg),
+        }
+    }
+
+    let input = match path.as_deref() {
+        None | Some("-") => {
+            let mut input = Vec::new();
+            io::stdin().read_to_end(&mut input).map(|_| input)
+        }
+        Some(path) => fs::read(path),
+    };
+
+    let input = input.unwrap_or_else(|err| fail(&format!("couldn't read input: {}", err)));
+    let inspection = container::inspect(&input)
+        .unwrap_or_else(|_| fail("not an XSTREAM container, or its header is malformed"));
+
+    if json {
+        println!("{}", inspection.to_json());
+    } else {
+        print_report(&inspection);
+    }
+}
+
+fn print_report(inspection: &Inspection) {
+    let header = &inspection.header;
+    let supported = if header.algorithm().is_ok() { "" } else { " (unsupported)" };
+    let ephemeral_key: String = header
+        .ephemeral_key
+        .as_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    println!("algorithm:      {}{}", header.algorithm, supported);
+    println!("ephemeral key:  {}", ephemeral_key);
+    println!("salt length:    {}", header.salt.len());
+    println!("header length:  {}", inspection.header_len);
+    println!("segments:       {}", inspection.segments.len());
+
+    for (i, segment) in inspection.segments.iter().enumerate() {
+        println!(
+            "  {:>6}: offset {}, {} bytes{}",
+            i,
+            segment.offset,
+            segment.len,
+            if segment.last { ", last" } else { "" }
+        );
+    }
+
+    println!("last segment:   {}", if inspection.finished { "present" } else { "missing" });
+
+    if inspection.truncated {
+        println!("warning: container ends partway through a segment");
+    }
+
+    if inspection.trailing_bytes > 0 {
+        println!("warning: {} bytes follow the last segment", inspection.trailing_bytes);
+    }
+}
+
+fn usage(msg: &str) -> ! {
+    eprintln!("xstream-inspect: {}\n\n{}", msg, USAGE);
+    process::exit(2);
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("xstream-inspect: {}", msg);
+    process::exit(1);
+}
@@ -0,0 +1,369 @@
+//! `xstream`: command-line tool for generating keys, and encrypting and
+//! decrypting files with `XSTREAM`.
+//!
+//! Streams are encrypted with X25519 + HKDF-SHA-256 + AES-128-SIV, and
+//! written in the format described in `xstream::container`, optionally
+//! ASCII armored (see `xstream::armor`). Decryption accepts either form.
+//!
+//! Keys are stored and passed as hex. Exits with status 1 on errors
+//! (including authentication failures), and 2 on usage errors.
+
+extern crate miscreant;
+extern crate rand;
+extern crate xstream;
+
+use miscreant::aead::Aes128Siv;
+use rand::OsRng;
+use std::env;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::process;
+use xstream::armor::{ArmorReader, ArmorWriter};
+use xstream::container::{self, Header};
+use xstream::{Algorithm, Decryptor, Encryptor, PrivateKey, PublicKey, X25519HkdfSha256Decryptor,
+              X25519HkdfSha256Encryptor};
+
+/// Algorithm used to encrypt files
+const ALGORITHM: Algorithm = Algorithm::X25519HkdfSha256Aes128Siv;
+
+/// Size of X25519 keys in bytes
+const KEY_SIZE: usize = 32;
+
+/// Amount of plaintext sealed in each segment
+const SEGMENT_SIZE: usize = 65_536;
+
+/// Size of the authentication tag added to every segment
+const TAG_SIZE: usize = 16;
+
+const USAGE: &str = "Usage:
+    xstream keygen <keyfile>
+    xstream encrypt [--armor] -r <pubkey> [in] [out]
+    xstream decrypt -i <keyfile> [in] [out]
+
+keygen writes a new private key to <keyfile> and prints its public key.
+--armor writes the ciphertext as text, which decrypt detects.
+Input and output default to stdin and stdout, or may be given as `-`.";
+
+/// Errors reported by the tool
+enum CliError {
+    /// Invalid command-line arguments
+    Usage(String),
+
+    /// Any other failure
+    Failed(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CliError::Usage(ref msg) => write!(f, "{}\n\n{}", msg, USAGE),
+            CliError::Failed(ref msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(err: io::Error) -> Self {
+        CliError::Failed(format!("I/O error: {}", err))
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if let Err(err) = run(&args) {
+        eprintln!("xstream: {}", err);
+
+        process::exit(match err {
+            CliError::Usage(_) => 2,
+            CliError::Failed(_) => 1,
+        });
+    }
+}
+
+fn run(args: &[String]) -> Result<(), CliError> {
+    let (command, args) = match args.split_first() {
+        Some((command, args)) => (command.as_str(), args),
+        None => return Err(CliError::Usage("no command given".to_owned())),
+    };
+
+    match command {
+        "keygen" => match args {
+            [keyfile] => keygen(keyfile),
+            _ => Err(CliError::Usage("keygen takes exactly one keyfile".to_owned())),
+        },
+        "encrypt" => {
+            let armor = args.iter().any(|arg| arg == "--armor");
+            let args: Vec<String> = args.iter().filter(|arg| *arg != "--armor").cloned().collect();
+            let (recipient, paths) = parse_flag(&args, "-r")?;
+            let public_key = parse_key(&recipient).map(PublicKey::from).ok_or_else(|| {
+                CliError::Failed("invalid public key (expected 64 hex digits)".to_owned())
+            })?;
+
+            with_files(paths, |input, output| encrypt(&public_key, armor, input, output))
+        }
+        "decrypt" => {
+            let (keyfile, paths) = parse_flag(args, "-i")?;
+            let private_key = read_private_key(&keyfile)?;
+
+            with_files(paths, |input, output| decrypt(&private_key, input, output))
+        }
+        "-h" | "--help" | "help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(CliError::Usage(format!("unknown command `{}`", command))),
+    }
+}
+
+/// Generate a private key, writing it to a new file readable only by its
+/// owner, and print the public key
+fn keygen(keyfile: &str) -> Result<(), CliError> {
+    let mut csprng = OsRng::new()?;
+    let private_key = PrivateKey::generate(&mut csprng);
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    restrict_permissions(&mut options);
+
+    let mut file = options.open(keyfile).map_err(|err| {
+        CliError::Failed(format!("couldn't create {}: {}", keyfile, err))
+    })?;
+    writeln!(file, "{}", to_hex(private_key.as_bytes()))?;
+
+    println!("{}", to_hex(private_key.public_key().as_bytes()));
+    Ok(())
+}
+
+#[cfg(unix)]
+fn restrict_permissions(options: &mut OpenOptions) {
+    use std::os::unix::fs::OpenOptionsExt;
+    options.mode(0o600);
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_options: &mut OpenOptions) {}
+
+/// Encrypt the input to the given public key, optionally armored
+fn encrypt(
+    public_key: &PublicKey,
+    armor: bool,
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+) -> Result<(), CliError> {
+    let mut csprng = OsRng::new()?;
+    let (encryptor, ephemeral_key) =
+        X25519HkdfSha256Encryptor::<Aes128Siv>::new(&mut csprng, public_key, None)
+            .map_err(|_| CliError::Failed("invalid public key".to_owned()))?;
+
+    let header = Header::new(ALGORITHM, &ephemeral_key, None);
+
+    if armor {
+        let mut writer = ArmorWriter::new(&mut *output, &header)?;
+        seal_segments(encryptor, input, |last, ciphertext| {
+            if last {
+                writer.write_last(ciphertext)
+            } else {
+                writer.write_next(ciphertext)
+            }
+        })?;
+    } else {
+        output.write_all(&header.to_bytes())?;
+        seal_segments(encryptor, input, |last, ciphertext| {
+            container::write_frame(output, last, ciphertext)
+        })?;
+    }
+
+    output.flush()?;
+    Ok(())
+}
+
+/// Seal the input, passing each sealed segment to `write_frame` along with
+/// whether it's the last one
+fn seal_segments<F>(
+    mut encryptor: X25519HkdfSha256Encryptor<Aes128Siv>,
+    input: &mut dyn Read,
+    mut write_frame: F,
+) -> Result<(), CliError>
+where
+    F: FnMut(bool, &[u8]) -> io::Result<()>,
+{
+    // A full segment is only sealed once more plaintext arrives, since until
+    // then it may turn out to be the last one
+    let mut segment = read_segment(input)?;
+
+    loop {
+        let next = read_segment(input)?;
+
+        if next.is_empty() {
+            write_frame(true, &encryptor.seal_last(b"", &segment))?;
+            return Ok(());
+        }
+
+        let ciphertext = encryptor
+            .try_seal_next(b"", &segment)
+            .map_err(|_| CliError::Failed("input too long".to_owned()))?;
+        write_frame(false, &ciphertext)?;
+        segment = next;
+    }
+}
+
+/// Decrypt the input (armored or binary) with the given private key. Each
+/// segment is written as soon as it has been authenticated.
+fn decrypt(
+    private_key: &PrivateKey,
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+) -> Result<(), CliError> {
+    let mut reader = ArmorReader::new(BufReader::new(input)).map_err(read_failed)?;
+    let header = reader.header().clone();
+
+    if header.algorithm != ALGORITHM.as_str() {
+        return Err(CliError::Failed(format!("unsupported algorithm {}", header.algorithm)));
+    }
+
+    let mut decryptor = X25519HkdfSha256Decryptor::<Aes128Siv>::new(
+        private_key,
+        &header.ephemeral_key,
+        header.salt(),
+    ).map_err(|_| CliError::Failed("invalid ephemeral key".to_owned()))?;
+
+    loop {
+        let (last, ciphertext) =
+            reader.read_segment(SEGMENT_SIZE + TAG_SIZE).map_err(read_failed)?;
+
+        if last {
+            let plaintext = decryptor.open_last(b"", &ciphertext).map_err(|_| auth_failed())?;
+            output.write_all(&plaintext)?;
+            break;
+        }
+
+        let plaintext = decryptor.open_next(b"", &ciphertext).map_err(|_| auth_failed())?;
+        output.write_all(&plaintext)?;
+    }
+
+    output.flush()?;
+    Ok(())
+}
+
+fn auth_failed() -> CliError {
+    CliError::Failed(
+        "authentication failed: wrong key, or the file is corrupt or has been tampered with"
+            .to_owned(),
+    )
+}
+
+/// Split a required flag and its value from the positional arguments
+fn parse_flag<'a>(args: &'a [String], flag: &str) -> Result<(String, Vec<&'a str>), CliError> {
+    let mut value = None;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            match args.next() {
+                Some(arg) if value.is_none() => value = Some(arg.clone()),
+                Some(_) => return Err(CliError::Usage(format!("{} given twice", flag))),
+                None => return Err(CliError::Usage(format!("{} requires a value", flag))),
+            }
+        } else if arg.starts_with('-') && arg != "-" {
+            return Err(CliError::Usage(format!("unknown option `{}`", arg)));
+        } else {
+            paths.push(arg.as_str());
+        }
+    }
+
+    if paths.len() > 2 {
+        return Err(CliError::Usage("too many arguments".to_owned()));
+    }
+
+    let value = value.ok_or_else(|| CliError::Usage(format!("{} is required", flag)))?;
+    Ok((value, paths))
+}
+
+/// Open the input and output (stdin and stdout by default) and run the
+/// given operation on them. A partially written output file is removed if
+/// the operation fails.
+fn with_files<F>(paths: Vec<&str>, operation: F) -> Result<(), CliError>
+where
+    F: FnOnce(&mut dyn Read, &mut dyn Write) -> Result<(), CliError>,
+{
+    let input_path = paths.first().cloned().unwrap_or("-");
+    let output_path = paths.get(1).cloned().unwrap_or("-");
+
+    let stdin = io::stdin();
+    let mut input: Box<dyn Read> = if input_path == "-" {
+        Box::new(stdin.lock())
+    } else {
+        let file = File::open(input_path).map_err(|err| {
+            CliError::Failed(format!("couldn't open {}: {}", input_path, err))
+        })?;
+        Box::new(BufReader::new(file))
+    };
+
+    if output_path == "-" {
+        let stdout = io::stdout();
+        let mut output = stdout.lock();
+        return operation(&mut input, &mut output);
+    }
+
+    let file = File::create(output_path).map_err(|err| {
+        CliError::Failed(format!("couldn't create {}: {}", output_path, err))
+    })?;
+    let result = operation(&mut input, &mut BufWriter::new(file));
+
+    if result.is_err() {
+        let _ = fs::remove_file(output_path);
+    }
+
+    result
+}
+
+/// Read a private key from a keyfile written by `keygen`
+fn read_private_key(keyfile: &str) -> Result<PrivateKey, CliError> {
+    let contents = fs::read_to_string(keyfile).map_err(|err| {
+        CliError::Failed(format!("couldn't read {}: {}", keyfile, err))
+    })?;
+
+    parse_key(contents.trim())
+        .map(PrivateKey::from)
+        .ok_or_else(|| CliError::Failed(format!("{} doesn't contain a valid key", keyfile)))
+}
+
+/// Parse a 32-byte key from hex
+fn parse_key(hex: &str) -> Option<[u8; KEY_SIZE]> {
+    if hex.len() != KEY_SIZE * 2 || !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let mut key = [0u8; KEY_SIZE];
+
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+
+    Some(key)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Read up to one segment of plaintext, which is shorter than a segment
+/// only at the end of the input
+fn read_segment(input: &mut dyn Read) -> io::Result<Vec<u8>> {
+    let mut segment = Vec::with_capacity(SEGMENT_SIZE);
+    input.take(SEGMENT_SIZE as u64).read_to_end(&mut segment)?;
+    Ok(segment)
+}
+
+/// Describe an error reading the input, reporting a truncated file at the
+/// end of the input
+fn read_failed(err: io::Error) -> CliError {
+    match err.kind() {
+        io::ErrorKind::UnexpectedEof => CliError::Failed("file is truncated".to_owned()),
+        io::ErrorKind::InvalidData => CliError::Failed(format!("malformed file: {}", err)),
+        _ => CliError::from(err),
+    }
+}
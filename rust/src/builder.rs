@@ -0,0 +1,741 @@
+//! `builder.rs`: builder for configuring `XSTREAM` sealers and openers
+//! without positional constructor arguments
+
+use algorithm::Algorithm;
+use compression::{self, Compression};
+use container::Header;
+use dynamic::{DynDecryptor, DynEncryptor};
+use error::Error;
+use keys::{PrivateKey, PublicKey};
+use padding::{self, Padding};
+use rand::Rng;
+use ratchet::Rekey;
+use signature::{SIGNATURE_SIZE, SigningKey, Transcript, VerifyingKey};
+use std::cmp;
+use std::mem;
+
+/// Size of the authentication tag appended to every sealed segment
+const TAG_SIZE: usize = 16;
+
+/// Builder for `XSTREAM` sealers (encryptors) and openers (decryptors).
+///
+/// All options have defaults except for the keys: a `Sealer` requires the
+/// recipient's public key, and an `Opener` requires the recipient's private
+/// key along with the ephemeral public key produced by the `Sealer`. Missing
+/// or inconsistent options are reported by `sealer` and `opener`, before any
+/// keys are derived.
+pub struct XStreamBuilder {
+    algorithm: Algorithm,
+    salt: Option<Vec<u8>>,
+    context: Vec<u8>,
+    recipient_key: Option<PublicKey>,
+    private_key: Option<PrivateKey>,
+    ephemeral_key: Option<PublicKey>,
+    segment_size: Option<usize>,
+    padding: Option<Padding>,
+    compression: Option<Compression>,
+    signing_key: Option<SigningKey>,
+    verifying_key: Option<VerifyingKey>,
+    rekey: Option<Rekey>,
+}
+
+impl Default for XStreamBuilder {
+    fn default() -> Self {
+        Self {
+            algorithm: Algorithm::X25519HkdfSha256Aes128Siv,
+            salt: None,
+            context: Vec::new(),
+            recipient_key: None,
+            private_key: None,
+            ephemeral_key: None,
+            segment_size: None,
+            padding: None,
+            compression: None,
+            signing_key: None,
+            verifying_key: None,
+            rekey: None,
+        }
+    }
+}
+
+impl XStreamBuilder {
+    /// Create a new builder using `XSTREAM_X25519_HKDF_SHA256_AES128_SIV`
+    /// and no salt, context, or framing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Algorithm suite to use, which selects the AEAD algorithm and the
+    /// digest used with HKDF
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Salt to use as an input to HKDF. See `Encryptor::new`.
+    pub fn salt(mut self, salt: &[u8]) -> Self {
+        self.salt = Some(Vec::from(salt));
+        self
+    }
+
+    /// Application context to bind into the derived key. See
+    /// `X25519HkdfEncryptor::new_with_context`.
+    pub fn context(mut self, context: &[u8]) -> Self {
+        self.context = Vec::from(context);
+        self
+    }
+
+    /// Public key of the recipient (required to build a `Sealer`)
+    pub fn recipient_key(mut self, public_key: &PublicKey) -> Self {
+        self.recipient_key = Some(PublicKey::from(*public_key.as_ref()));
+        self
+    }
+
+    /// Private key of the recipient (required to build an `Opener`)
+    pub fn private_key(mut self, private_key: &PrivateKey) -> Self {
+        self.private_key = Some(PrivateKey::new(private_key.as_bytes()));
+        self
+    }
+
+    /// Ephemeral public key produced when the stream was sealed (required to
+    /// build an `Opener`)
+    pub fn ephemeral_key(mut self, ephemeral_key: &PublicKey) -> Self {
+        self.ephemeral_key = Some(PublicKey::from(*ephemeral_key.as_ref()));
+        self
+    }
+
+    /// Use fixed-size framing: every segment but the last must contain
+    /// exactly this many bytes of plaintext, and the last segment at most
+    /// this many. Must be non-zero.
+    pub fn segment_size(mut self, segment_size: usize) -> Self {
+        self.segment_size = Some(segment_size);
+        self
+    }
+
+    /// Pad the plaintext to hide its exact length, using the given scheme.
+    /// See `Padding`.
+    ///
+    /// The opener must be built with padding too, but may use any scheme
+    /// with the same parameters.
+    pub fn padding(mut self, padding: Padding) -> Self {
+        self.padding = Some(padding);
+        self
+    }
+
+    /// Compress each segment before sealing it, using the given algorithm.
+    /// Requires fixed-size framing, and can't be combined with padding.
+    ///
+    /// **Warning**: compression leaks information about the plaintext
+    /// through the ciphertext length. See the `Compression` documentation.
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Ed25519 key of the publisher, used to sign the stream's transcript.
+    /// The signature is appended to the last segment.
+    pub fn signing_key(mut self, signing_key: &SigningKey) -> Self {
+        self.signing_key = Some(signing_key.clone());
+        self
+    }
+
+    /// Ed25519 key of the expected publisher. When given, the last segment
+    /// must carry a valid signature over the stream's transcript, and isn't
+    /// decrypted unless it does.
+    pub fn verifying_key(mut self, verifying_key: &VerifyingKey) -> Self {
+        self.verifying_key = Some(*verifying_key);
+        self
+    }
+
+    /// Periodically replace the STREAM key according to the given rekeying
+    /// schedule, which the opener must also be built with. See `Rekey`.
+    ///
+    /// `Rekey::Bytes` can't be combined with a segment size: framed streams
+    /// are read by random access, which needs to know which key each segment
+    /// was sealed under without decrypting the segments before it.
+    pub fn rekey(mut self, rekey: Rekey) -> Self {
+        self.rekey = Some(rekey);
+        self
+    }
+
+    /// Build a `Sealer`, returning it along with the ephemeral public key
+    /// which must be passed to the `Opener`.
+    ///
+    /// Returns an error if no recipient key was given, the options are
+    /// inconsistent, or the recipient key is invalid.
+    pub fn sealer<R: Rng>(&self, csprng: &mut R) -> Result<(Sealer, PublicKey), Error> {
+        self.validate()?;
+
+        // An ephemeral key is only meaningful when opening a stream, and a
+        // stream can't be signed with a verifying key alone
+        if self.ephemeral_key.is_some() ||
+            (self.verifying_key.is_some() && self.signing_key.is_none())
+        {
+            return Err(Error);
+        }
+
+        let recipient_key = self.recipient_key.as_ref().ok_or(Error)?;
+
+        let (mut encryptor, ephemeral_key) = DynEncryptor::new_with_context(
+            self.algorithm,
+            csprng,
+            recipient_key,
+            self.salt.as_ref().map(|salt| salt.as_ref()),
+            &self.context,
+        )?;
+
+        if let Some(rekey) = self.rekey {
+            encryptor.set_rekey(rekey)?;
+        }
+
+        let signer = self.signing_key.as_ref().map(|signing_key| {
+            (self.transcript(&ephemeral_key), signing_key.clone())
+        });
+
+        let sealer = Sealer {
+            encryptor,
+            signer,
+            header: self.header(&ephemeral_key),
+            segment_size: self.segment_size,
+            padding: self.padding,
+            compression: self.compression,
+            sealed_len: 0,
+        };
+
+        Ok((sealer, ephemeral_key))
+    }
+
+    /// Build an `Opener`.
+    ///
+    /// Returns an error if the private or ephemeral key is missing, the
+    /// options are inconsistent, or the ephemeral key is invalid.
+    pub fn opener(&self) -> Result<Opener, Error> {
+        self.validate()?;
+
+        let private_key = self.private_key.as_ref().ok_or(Error)?;
+        let ephemeral_key = self.ephemeral_key.as_ref().ok_or(Error)?;
+
+        // Signatures are verified with the publisher's verifying key, which
+        // must be given explicitly
+        if self.signing_key.is_some() && self.verifying_key.is_none() {
+            return Err(Error);
+        }
+
+        let mut decryptor = DynDecryptor::new_with_context(
+            self.algorithm,
+            private_key,
+            ephemeral_key,
+            self.salt.as_ref().map(|salt| salt.as_ref()),
+            &self.context,
+        )?;
+
+        if let Some(rekey) = self.rekey {
+            decryptor.set_rekey(rekey)?;
+        }
+
+        let verifier = self.verifying_key.map(|verifying_key| {
+            (self.transcript(ephemeral_key), verifying_key)
+        });
+
+        Ok(Opener {
+            decryptor,
+            verifier,
+            header: self.header(ephemeral_key),
+            segment_size: self.segment_size,
+            padding: self.padding,
+            compression: self.compression,
+            opened_len: 0,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Check options which apply to both sealers and openers
+    fn validate(&self) -> Result<(), Error> {
+        if let Some(size) = self.segment_size {
+            if size == 0 || size.checked_add(TAG_SIZE).is_none() {
+                return Err(Error);
+            }
+
+            // Framed streams begin with a container header, which has a
+            // 16-bit salt length
+            if self.salt.as_ref().map_or(0, |salt| salt.len()) > u16::MAX as usize {
+                return Err(Error);
+            }
+        }
+
+        if let Some(ref padding) = self.padding {
+            padding.validate()?;
+        }
+
+        if let Some(ref rekey) = self.rekey {
+            rekey.validate()?;
+
+            // Keys can only be located by segment index, which `SegmentReader`
+            // and `Opener::open_at` rely on
+            if let (Rekey::Bytes(_), Some(_)) = (rekey, self.segment_size) {
+                return Err(Error);
+            }
+        }
+
+        // Decompression is bounded by the segment size, and compressing
+        // padding would defeat its purpose
+        if let Some(ref compression) = self.compression {
+            if self.segment_size.is_none() || self.padding.is_some() {
+                return Err(Error);
+            }
+
+            compression.validate()?;
+        }
+
+        // The recipient key must belong to the private key if both are given
+        if let (Some(recipient_key), Some(private_key)) = (&self.recipient_key, &self.private_key) {
+            if recipient_key.as_bytes() != private_key.public_key().as_bytes() {
+                return Err(Error);
+            }
+        }
+
+        // Likewise for the verifying and signing keys
+        if let (Some(verifying_key), Some(signing_key)) = (&self.verifying_key, &self.signing_key) {
+            if *verifying_key != signing_key.verifying_key() {
+                return Err(Error);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Container header written before framed streams, if fixed-size framing
+    /// is in use
+    fn header(&self, ephemeral_key: &PublicKey) -> Option<Header> {
+        self.segment_size?;
+
+        Some(Header::new(
+            self.algorithm,
+            ephemeral_key,
+            self.salt.as_ref().map(|salt| salt.as_ref()),
+        ))
+    }
+
+    /// Begin the transcript which stream signatures cover
+    fn transcript(&self, ephemeral_key: &PublicKey) -> Transcript {
+        Transcript::new(
+            self.algorithm,
+            ephemeral_key,
+            self.salt.as_ref().map(|salt| salt.as_ref()),
+            &self.context,
+        )
+    }
+}
+
+/// `XSTREAM` encryptor produced by `XStreamBuilder`, which enforces the
+/// builder's framing options
+pub struct Sealer {
+    encryptor: DynEncryptor,
+    signer: Option<(Transcript, SigningKey)>,
+    header: Option<Header>,
+    segment_size: Option<usize>,
+    padding: Option<Padding>,
+    compression: Option<Compression>,
+    sealed_len: u64,
+}
+
+impl Sealer {
+    /// Algorithm this sealer is using
+    pub fn algorithm(&self) -> Algorithm {
+        self.encryptor.algorithm()
+    }
+
+    /// Plaintext segment size, if fixed-size framing is in use
+    pub fn segment_size(&self) -> Option<usize> {
+        self.segment_size
+    }
+
+    /// Padding scheme, if the plaintext is being padded
+    pub fn padding(&self) -> Option<Padding> {
+        self.padding
+    }
+
+    /// Compression algorithm, if segments are being compressed
+    pub fn compression(&self) -> Option<Compression> {
+        self.compression
+    }
+
+    /// Encrypt the next segment in the stream.
+    ///
+    /// Returns an error if fixed-size framing is in use and the plaintext is
+    /// not exactly one segment long, or the stream can't hold any more
+    /// segments before the last one.
+    pub fn seal_next(&mut self, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        if let Some(size) = self.segment_size {
+            if plaintext.len() != size {
+                return Err(Error);
+            }
+        }
+
+        let sealed_len = self.sealed_len.checked_add(plaintext.len() as u64).ok_or(Error)?;
+        let segment = self.compressed(plaintext)?;
+        let ciphertext = self.seal_segment(ad, &segment)?;
+        self.sealed_len = sealed_len;
+        Ok(ciphertext)
+    }
+
+    /// Encrypt the final segment in the stream, padding it if padding is in
+    /// use.
+    ///
+    /// Returns an error if fixed-size framing is in use and the plaintext
+    /// (including any padding) is longer than one segment. Use `finish` to
+    /// seal padding which spills over into extra segments.
+    pub fn seal_last(self, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        let tail = self.padded_tail(plaintext)?;
+
+        if let Some(size) = self.segment_size {
+            if tail.len() > size {
+                return Err(Error);
+            }
+        }
+
+        let segment = self.compressed(&tail)?;
+        Ok(self.seal_final(ad, &segment))
+    }
+
+    /// Encrypt the remaining plaintext and any padding, returning the final
+    /// segments in the stream. When fixed-size framing and padding are both
+    /// in use the padding may require extra segments, otherwise exactly one
+    /// segment is returned.
+    ///
+    /// Returns an error if fixed-size framing is in use and the plaintext
+    /// (excluding padding) is longer than one segment.
+    pub fn finish(mut self, ad: &[u8], plaintext: &[u8]) -> Result<Vec<Vec<u8>>, Error> {
+        let size = match self.segment_size {
+            Some(size) if plaintext.len() > size => return Err(Error),
+            Some(size) => size,
+            None => return self.seal_last(ad, plaintext).map(|segment| vec![segment]),
+        };
+
+        let tail = self.padded_tail(plaintext)?;
+        let mut segments: Vec<&[u8]> = tail.chunks(size).collect();
+        let last = segments.pop().unwrap_or(&[]);
+
+        let mut ciphertexts = Vec::with_capacity(segments.len() + 1);
+
+        for segment in segments {
+            let segment = self.compressed(segment)?;
+            ciphertexts.push(self.seal_segment(ad, &segment)?);
+        }
+
+        let last = self.compressed(last)?;
+        ciphertexts.push(self.seal_final(ad, &last));
+        Ok(ciphertexts)
+    }
+
+    /// Container header to write before the segments, if fixed-size framing
+    /// is in use
+    pub(crate) fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
+    }
+
+    /// Number of further segments which can be sealed with `seal_next`
+    /// before the stream is exhausted. See `DynEncryptor::remaining_segments`.
+    pub fn remaining_segments(&self) -> Option<u64> {
+        self.encryptor.remaining_segments()
+    }
+
+    /// Derive a secret bound to this stream's key exchange. See
+    /// `Exporter::export`.
+    pub fn export(&self, label: &[u8], context: &[u8], length: usize) -> Result<Vec<u8>, Error> {
+        self.encryptor.export(label, context, length)
+    }
+
+    /// Append padding (if in use) to the final plaintext
+    fn padded_tail(&self, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        match self.padding {
+            Some(ref scheme) => padding::pad(scheme, self.sealed_len, plaintext),
+            None => Ok(Vec::from(plaintext)),
+        }
+    }
+
+    /// Seal a segment which isn't the last one, adding it to the transcript
+    /// if the stream is being signed
+    fn seal_segment(&mut self, ad: &[u8], segment: &[u8]) -> Result<Vec<u8>, Error> {
+        let ciphertext = self.encryptor.try_seal_next(ad, segment)?;
+
+        if let Some((ref mut transcript, _)) = self.signer {
+            transcript.update(ad, &ciphertext, false);
+        }
+
+        Ok(ciphertext)
+    }
+
+    /// Seal the last segment, appending the signature over the completed
+    /// transcript if the stream is being signed
+    fn seal_final(mut self, ad: &[u8], segment: &[u8]) -> Vec<u8> {
+        let signer = self.signer.take();
+        let mut ciphertext = self.encryptor.seal_last(ad, segment);
+
+        if let Some((mut transcript, signing_key)) = signer {
+            transcript.update(ad, &ciphertext, true);
+            ciphertext.extend_from_slice(&transcript.sign(&signing_key));
+        }
+
+        ciphertext
+    }
+
+    /// Compress a segment (if compression is in use)
+    fn compressed(&self, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        match self.compression {
+            Some(ref compression) => compression.compress(plaintext),
+            None => Ok(Vec::from(plaintext)),
+        }
+    }
+}
+
+/// `XSTREAM` decryptor produced by `XStreamBuilder`, which enforces the
+/// builder's framing options
+pub struct Opener {
+    decryptor: DynDecryptor,
+    verifier: Option<(Transcript, VerifyingKey)>,
+    header: Option<Header>,
+    segment_size: Option<usize>,
+    padding: Option<Padding>,
+    compression: Option<Compression>,
+    opened_len: u64,
+    pending: Vec<u8>,
+}
+
+impl Opener {
+    /// Algorithm this opener is using
+    pub fn algorithm(&self) -> Algorithm {
+        self.decryptor.algorithm()
+    }
+
+    /// Plaintext segment size, if fixed-size framing is in use
+    pub fn segment_size(&self) -> Option<usize> {
+        self.segment_size
+    }
+
+    /// Padding scheme, if the plaintext is padded
+    pub fn padding(&self) -> Option<Padding> {
+        self.padding
+    }
+
+    /// Compression algorithm, if segments are compressed. Any supported
+    /// algorithm is accepted when opening, since each segment is flagged
+    /// with the algorithm it was compressed with.
+    pub fn compression(&self) -> Option<Compression> {
+        self.compression
+    }
+
+    /// Decrypt the next segment in the stream.
+    ///
+    /// When padding is in use, any trailing bytes which may turn out to be
+    /// padding are withheld until a later segment shows they aren't, so the
+    /// returned plaintext may be shorter than the segment (or empty). At most
+    /// the largest padding the scheme can produce for the length opened so
+    /// far is withheld.
+    ///
+    /// Returns an error if the ciphertext is not authentic, fixed-size
+    /// framing is in use and the ciphertext is not exactly one segment long,
+    /// or the segment doesn't decompress to exactly one segment.
+    pub fn open_next(&mut self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        self.check_length(ciphertext, false)?;
+        let plaintext = self.decryptor.open_next(ad, ciphertext)?;
+        let plaintext = decompress_segment(self.compression, self.segment_size, plaintext, false)?;
+
+        if let Some((ref mut transcript, _)) = self.verifier {
+            transcript.update(ad, ciphertext, false);
+        }
+
+        let scheme = match self.padding {
+            Some(scheme) => scheme,
+            None => return Ok(plaintext),
+        };
+
+        self.opened_len = self.opened_len.checked_add(plaintext.len() as u64).ok_or(Error)?;
+        self.pending.extend_from_slice(&plaintext);
+
+        // Withhold trailing bytes which resemble padding, but no more than
+        // the padding the scheme could add to a stream of this length, so
+        // long runs of zeros in the plaintext aren't buffered indefinitely
+        let pending_start = self.opened_len - self.pending.len() as u64;
+        let earliest = padding::earliest_padding_start(&scheme, self.opened_len);
+        let certain = cmp::min(earliest.saturating_sub(pending_start), self.pending.len() as u64);
+        let start = cmp::max(padding::padding_start(&self.pending), certain as usize);
+
+        let withheld = self.pending.split_off(start);
+        Ok(mem::replace(&mut self.pending, withheld))
+    }
+
+    /// Decrypt the final segment in the stream, removing the padding if
+    /// padding is in use.
+    ///
+    /// If a verifying key was given, the signature trailer is verified
+    /// before the last segment is decrypted.
+    ///
+    /// Returns an error if the ciphertext is not authentic, the signature is
+    /// missing or invalid, fixed-size framing is in use and the ciphertext is
+    /// longer than one segment, the segment decompresses to more than one
+    /// segment, or the padding is malformed or doesn't match the padding
+    /// scheme.
+    pub fn open_last(mut self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        let (ciphertext, signature) = self.split_signature(ciphertext, true)?;
+        self.check_length(ciphertext, true)?;
+
+        if let Some((mut transcript, verifying_key)) = self.verifier.take() {
+            transcript.update(ad, ciphertext, true);
+            transcript.verify(&verifying_key, signature)?;
+        }
+
+        let plaintext = self.decryptor.open_last(ad, ciphertext)?;
+        let plaintext = decompress_segment(self.compression, self.segment_size, plaintext, true)?;
+
+        let scheme = match self.padding {
+            Some(scheme) => scheme,
+            None => return Ok(plaintext),
+        };
+
+        let padded_len = self.opened_len.checked_add(plaintext.len() as u64).ok_or(Error)?;
+        self.pending.extend_from_slice(&plaintext);
+
+        let len = padding::unpad_len(&self.pending)?;
+        let padding_len = (self.pending.len() - len) as u64;
+
+        if scheme.padded_len(padded_len - padding_len) != Some(padded_len) {
+            return Err(Error);
+        }
+
+        self.pending.truncate(len);
+        Ok(mem::take(&mut self.pending))
+    }
+
+    /// Decrypt the segment with the given index, without decrypting any of
+    /// the segments which precede it. `last` must be set if and only if this
+    /// is the final segment of the stream.
+    ///
+    /// Padding is not removed, since it can't be located without the other
+    /// segments. `SegmentReader` removes padding when reading framed streams.
+    /// Likewise, the last segment of a signed stream can't be opened this
+    /// way, since the signature covers every segment: `SegmentReader`
+    /// verifies it before returning any plaintext.
+    ///
+    /// Returns an error if the ciphertext is not authentic, fixed-size
+    /// framing is in use and the ciphertext or decompressed segment has the
+    /// wrong length, the opener rekeys by bytes (see `XStreamBuilder::rekey`),
+    /// or `last` is set and the opener has a verifying key.
+    pub fn open_at(
+        &mut self,
+        index: u32,
+        last: bool,
+        ad: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        if last && self.verifier.is_some() {
+            return Err(Error);
+        }
+
+        self.open_verified_at(index, last, ad, ciphertext)
+    }
+
+    /// Decrypt the segment with the given index like `open_at`, removing the
+    /// signature trailer (if any) from the last segment without verifying
+    /// it. The caller must already have verified the stream's signature.
+    pub(crate) fn open_verified_at(
+        &mut self,
+        index: u32,
+        last: bool,
+        ad: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let (ciphertext, _) = self.split_signature(ciphertext, last)?;
+        self.check_length(ciphertext, last)?;
+        let plaintext = self.decryptor.open_at(index, last, ad, ciphertext)?;
+        decompress_segment(self.compression, self.segment_size, plaintext, last)
+    }
+
+    /// Derive a secret bound to this stream's key exchange. See
+    /// `Exporter::export`.
+    pub fn export(&self, label: &[u8], context: &[u8], length: usize) -> Result<Vec<u8>, Error> {
+        self.decryptor.export(label, context, length)
+    }
+
+    /// Container header expected before the segments, if fixed-size framing
+    /// is in use
+    pub(crate) fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
+    }
+
+    /// Size of the trailer appended to the last segment in bytes
+    pub(crate) fn trailer_size(&self) -> usize {
+        if self.verifier.is_some() {
+            SIGNATURE_SIZE
+        } else {
+            0
+        }
+    }
+
+    /// Begin verifying the stream's signature independently of this opener,
+    /// if a verifying key was given
+    pub(crate) fn verifier(&self) -> Option<(Transcript, VerifyingKey)> {
+        self.verifier.clone()
+    }
+
+    /// Split the signature trailer (if expected) from the last segment
+    fn split_signature<'a>(
+        &self,
+        ciphertext: &'a [u8],
+        last: bool,
+    ) -> Result<(&'a [u8], &'a [u8]), Error> {
+        if !last || self.verifier.is_none() {
+            return Ok((ciphertext, &[]));
+        }
+
+        if ciphertext.len() < SIGNATURE_SIZE {
+            return Err(Error);
+        }
+
+        Ok(ciphertext.split_at(ciphertext.len() - SIGNATURE_SIZE))
+    }
+
+    /// Check the length of a ciphertext is consistent with fixed-size
+    /// framing (if in use). Compressed segments may be shorter than a full
+    /// segment, and are checked once decompressed instead.
+    fn check_length(&self, ciphertext: &[u8], last: bool) -> Result<(), Error> {
+        if let Some(size) = self.segment_size {
+            let full_len = size + TAG_SIZE;
+
+            let valid = if self.compression.is_some() {
+                ciphertext.len() <= full_len + compression::FLAG_SIZE
+            } else {
+                ciphertext.len() == full_len || (last && ciphertext.len() < full_len)
+            };
+
+            if !valid {
+                return Err(Error);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Decompress a segment (if compression is in use), ensuring it isn't
+/// larger than a segment, and is exactly one segment unless it's the last one
+fn decompress_segment(
+    compression: Option<Compression>,
+    segment_size: Option<usize>,
+    plaintext: Vec<u8>,
+    last: bool,
+) -> Result<Vec<u8>, Error> {
+    if compression.is_none() {
+        return Ok(plaintext);
+    }
+
+    // Compression can only be enabled along with fixed-size framing
+    let size = segment_size.expect("segment size required");
+    let segment = compression::decompress(&plaintext, size)?;
+
+    if !last && segment.len() != size {
+        return Err(Error);
+    }
+
+    Ok(segment)
+}
@@ -0,0 +1,151 @@
+//! `compression.rs`: optional compression of segment plaintexts before they
+//! are sealed.
+//!
+//! Every segment is compressed independently, so segments can still be
+//! opened in any order. A compressed segment's plaintext begins with a flag
+//! byte identifying the compression algorithm, or indicating the segment is
+//! stored uncompressed because compression didn't make it any smaller.
+//! Decompression is bounded by the segment size, so a malicious stream can't
+//! expand into more than one segment's worth of plaintext per segment.
+//!
+//! **Warning**: compression makes the ciphertext length depend on the
+//! content of the plaintext. If an attacker can influence part of the
+//! plaintext which is compressed alongside secrets, they may be able to
+//! recover those secrets by observing ciphertext lengths (as in the CRIME
+//! and BREACH attacks on TLS). Don't compress attacker-influenced data
+//! together with secrets.
+
+use error::Error;
+#[cfg(feature = "deflate")]
+use flate2;
+#[cfg(any(feature = "deflate", feature = "zstd"))]
+use std::io::{Read, Write};
+#[cfg(feature = "zstd")]
+use zstd;
+
+/// Flag for segments which are stored uncompressed
+const FLAG_STORED: u8 = 0;
+
+/// Flag for segments compressed with raw DEFLATE
+#[cfg(feature = "deflate")]
+const FLAG_DEFLATE: u8 = 1;
+
+/// Flag for segments compressed with Zstandard
+#[cfg(feature = "zstd")]
+const FLAG_ZSTD: u8 = 2;
+
+/// Size of the flag prefixed to every segment plaintext in bytes
+pub(crate) const FLAG_SIZE: usize = 1;
+
+/// Compression algorithms which can be applied to segments, each with a
+/// compression level. DEFLATE and Zstandard are enabled with the `deflate`
+/// and `zstd` cargo features respectively.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Compression {
+    /// No compression: segments are flagged as stored uncompressed. Streams
+    /// sealed with any supported algorithm can still be opened.
+    None,
+
+    /// Raw DEFLATE (RFC 1951) with the given level, from 0 to 9
+    #[cfg(feature = "deflate")]
+    Deflate(u32),
+
+    /// Zstandard (RFC 8878) with the given level, from 1 to 22, or 0 for the
+    /// default level. Negative levels, down to the minimum supported by the
+    /// zstd library, trade compression ratio for speed.
+    #[cfg(feature = "zstd")]
+    Zstd(i32),
+}
+
+impl Compression {
+    /// Check the compression level is valid
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        match *self {
+            #[cfg(feature = "deflate")]
+            Compression::Deflate(level) if level > 9 => Err(Error),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd(level) if !zstd::compression_level_range().contains(&level) => {
+                Err(Error)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Compress a segment plaintext, prefixing it with the appropriate flag
+    pub(crate) fn compress(&self, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        match *self {
+            Compression::None => Ok(stored(plaintext)),
+            #[cfg(feature = "deflate")]
+            Compression::Deflate(level) => {
+                let mut encoder = flate2::write::DeflateEncoder::new(
+                    vec![FLAG_DEFLATE],
+                    flate2::Compression::new(level),
+                );
+
+                encoder.write_all(plaintext).or(Err(Error))?;
+                Ok(smallest(encoder.finish().or(Err(Error))?, plaintext))
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd(level) => {
+                let mut encoder = zstd::stream::write::Encoder::new(vec![FLAG_ZSTD], level)
+                    .or(Err(Error))?;
+
+                encoder.write_all(plaintext).or(Err(Error))?;
+                Ok(smallest(encoder.finish().or(Err(Error))?, plaintext))
+            }
+        }
+    }
+}
+
+/// Use a compressed segment unless compression didn't make it any smaller,
+/// in which case the plaintext is stored uncompressed
+#[cfg(any(feature = "deflate", feature = "zstd"))]
+fn smallest(segment: Vec<u8>, plaintext: &[u8]) -> Vec<u8> {
+    if segment.len() > FLAG_SIZE + plaintext.len() {
+        stored(plaintext)
+    } else {
+        segment
+    }
+}
+
+/// Flag a segment plaintext as stored uncompressed
+fn stored(plaintext: &[u8]) -> Vec<u8> {
+    let mut segment = Vec::with_capacity(FLAG_SIZE + plaintext.len());
+    segment.push(FLAG_STORED);
+    segment.extend_from_slice(plaintext);
+    segment
+}
+
+/// Decompress a segment plaintext produced by `Compression::compress`.
+///
+/// Returns an error if the segment is malformed, uses an algorithm which
+/// isn't supported, or would decompress to more than `limit` bytes.
+pub(crate) fn decompress(segment: &[u8], limit: usize) -> Result<Vec<u8>, Error> {
+    let (flag, body) = segment.split_first().ok_or(Error)?;
+
+    match *flag {
+        FLAG_STORED if body.len() <= limit => Ok(Vec::from(body)),
+        #[cfg(feature = "deflate")]
+        FLAG_DEFLATE => read_bounded(flate2::read::DeflateDecoder::new(body), limit),
+        #[cfg(feature = "zstd")]
+        FLAG_ZSTD => read_bounded(zstd::stream::read::Decoder::new(body).or(Err(Error))?, limit),
+        _ => Err(Error),
+    }
+}
+
+/// Read a decompressed segment, stopping as soon as it exceeds the limit
+#[cfg(any(feature = "deflate", feature = "zstd"))]
+fn read_bounded<R: Read>(reader: R, limit: usize) -> Result<Vec<u8>, Error> {
+    let mut plaintext = Vec::new();
+
+    reader
+        .take(limit as u64 + 1)
+        .read_to_end(&mut plaintext)
+        .or(Err(Error))?;
+
+    if plaintext.len() > limit {
+        return Err(Error);
+    }
+
+    Ok(plaintext)
+}
@@ -0,0 +1,352 @@
+//! `container.rs`: self-describing container format for `XSTREAM`
+//! ciphertexts, and inspection of containers without any keys.
+//!
+//! A container begins with a header:
+//!
+//! - the magic string `XSTREAM` and a version byte
+//! - the algorithm identifier, prefixed with its 8-bit length
+//! - the 32-byte ephemeral public key returned by `Encryptor::new`
+//! - the salt (if any), prefixed with its 16-bit big endian length
+//!
+//! The header is followed by the sealed segments, each framed as a marker
+//! byte (1 for the last segment, otherwise 0), the ciphertext length as a
+//! 32-bit big endian integer, and the ciphertext. The marker only tells
+//! readers where the stream should end: STREAM authenticates which segment
+//! is the last one regardless.
+//!
+//! `SegmentWriter` and `SegmentReader` read and write containers too, so
+//! `inspect` works on their output.
+
+use algorithm::Algorithm;
+use error::Error;
+use keys::{KEY_SIZE, PublicKey};
+use std::fmt::Write as FmtWrite;
+use std::io::{self, Read, Write};
+
+/// Magic string which begins every container
+pub const CONTAINER_MAGIC: &[u8] = b"XSTREAM";
+
+/// Current container format version
+pub const CONTAINER_VERSION: u8 = 1;
+
+/// Marker preceding every segment but the last
+const MARKER_NEXT: u8 = 0;
+
+/// Marker preceding the last segment
+const MARKER_LAST: u8 = 1;
+
+/// Size of a segment's marker and length prefix in bytes
+pub(crate) const FRAME_HEADER_SIZE: usize = 5;
+
+/// Container header: everything needed to begin decrypting the segments
+/// besides the recipient's private key
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Header {
+    /// Algorithm identifier, which may not be one this library supports
+    pub algorithm: String,
+
+    /// Ephemeral public key the stream was sealed with
+    pub ephemeral_key: PublicKey,
+
+    /// Salt passed to the KDF (empty if none was used)
+    pub salt: Vec<u8>,
+}
+
+impl Header {
+    /// Create a header for a stream sealed with the given algorithm,
+    /// ephemeral key and salt.
+    ///
+    /// Panics if the salt is longer than 65535 bytes.
+    pub fn new(algorithm: Algorithm, ephemeral_key: &PublicKey, salt: Option<&[u8]>) -> Self {
+        let salt = Vec::from(salt.unwrap_or(b""));
+        assert!(salt.len() <= u16::MAX as usize, "salt too long");
+
+        Self {
+            algorithm: algorithm.as_str().to_owned(),
+            ephemeral_key: *ephemeral_key,
+            salt,
+        }
+    }
+
+    /// Parse the algorithm identifier.
+    ///
+    /// Returns an error if the algorithm isn't supported.
+    pub fn algorithm(&self) -> Result<Algorithm, Error> {
+        self.algorithm.parse()
+    }
+
+    /// Salt passed to the KDF, if any
+    pub fn salt(&self) -> Option<&[u8]> {
+        if self.salt.is_empty() {
+            None
+        } else {
+            Some(&self.salt)
+        }
+    }
+
+    /// Serialize this header.
+    ///
+    /// Panics if the algorithm identifier is longer than 255 bytes, or the
+    /// salt is longer than 65535 bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        assert!(self.algorithm.len() <= u8::MAX as usize, "algorithm identifier too long");
+        assert!(self.salt.len() <= u16::MAX as usize, "salt too long");
+
+        let mut bytes = Vec::from(CONTAINER_MAGIC);
+        bytes.push(CONTAINER_VERSION);
+        bytes.push(self.algorithm.len() as u8);
+        bytes.extend_from_slice(self.algorithm.as_bytes());
+        bytes.extend_from_slice(self.ephemeral_key.as_bytes());
+        bytes.extend_from_slice(&(self.salt.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(&self.salt);
+        bytes
+    }
+
+    /// Read a header from the start of a container.
+    ///
+    /// Returns an error of kind `InvalidData` if the input isn't a container
+    /// or uses an unsupported version, and `UnexpectedEof` if it's truncated.
+    pub fn read_from<R: Read + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        let mut magic = [0u8; 9];
+        reader.read_exact(&mut magic)?;
+
+        if &magic[..7] != CONTAINER_MAGIC {
+            return Err(invalid_data("not an XSTREAM container"));
+        }
+
+        if magic[7] != CONTAINER_VERSION {
+            return Err(invalid_data("unsupported container version"));
+        }
+
+        let mut algorithm = vec![0u8; magic[8] as usize];
+        reader.read_exact(&mut algorithm)?;
+        let algorithm = String::from_utf8(algorithm)
+            .map_err(|_| invalid_data("malformed algorithm identifier"))?;
+
+        let mut ephemeral_key = [0u8; KEY_SIZE];
+        reader.read_exact(&mut ephemeral_key)?;
+
+        let mut salt_len = [0u8; 2];
+        reader.read_exact(&mut salt_len)?;
+        let mut salt = vec![0u8; u16::from_be_bytes(salt_len) as usize];
+        reader.read_exact(&mut salt)?;
+
+        Ok(Self {
+            algorithm,
+            ephemeral_key: PublicKey::from(ephemeral_key),
+            salt,
+        })
+    }
+}
+
+/// Write a sealed segment as a frame
+pub fn write_frame<W>(writer: &mut W, last: bool, ciphertext: &[u8]) -> io::Result<()>
+where
+    W: Write + ?Sized,
+{
+    if ciphertext.len() > u32::MAX as usize {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "segment too large"));
+    }
+
+    let marker = if last { MARKER_LAST } else { MARKER_NEXT };
+    writer.write_all(&[marker])?;
+    writer.write_all(&(ciphertext.len() as u32).to_be_bytes())?;
+    writer.write_all(ciphertext)
+}
+
+/// Read a frame, returning whether it's marked as the last segment along
+/// with its ciphertext.
+///
+/// Returns an error of kind `InvalidData` if the frame is malformed or its
+/// ciphertext is longer than `max_len`, and `UnexpectedEof` if the input
+/// ends before the frame does.
+pub fn read_frame<R>(reader: &mut R, max_len: usize) -> io::Result<(bool, Vec<u8>)>
+where
+    R: Read + ?Sized,
+{
+    let mut header = [0u8; FRAME_HEADER_SIZE];
+    reader.read_exact(&mut header)?;
+
+    let (last, len) = parse_frame_header(&header)?;
+
+    if len > max_len as u64 {
+        return Err(invalid_data("segment too large"));
+    }
+
+    let mut ciphertext = vec![0u8; len as usize];
+    reader.read_exact(&mut ciphertext)?;
+    Ok((last, ciphertext))
+}
+
+/// Metadata about a container, obtained without decrypting it
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Inspection {
+    /// Container header
+    pub header: Header,
+
+    /// Size of the header in bytes
+    pub header_len: usize,
+
+    /// Frames which are present in full, in order
+    pub segments: Vec<SegmentInfo>,
+
+    /// Whether a segment marked as the last one is present
+    pub finished: bool,
+
+    /// Whether the container ends partway through a frame
+    pub truncated: bool,
+
+    /// Number of bytes following the last segment
+    pub trailing_bytes: usize,
+}
+
+/// Location and size of a sealed segment within a container
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SegmentInfo {
+    /// Offset of the segment's ciphertext within the container
+    pub offset: usize,
+
+    /// Length of the segment's ciphertext in bytes
+    pub len: usize,
+
+    /// Whether the segment is marked as the last one
+    pub last: bool,
+}
+
+/// Inspect a container, reporting its header and segment layout. No keys
+/// are needed, and nothing is authenticated: the results describe what the
+/// container claims, which may have been tampered with.
+///
+/// Returns an error if the header is missing or malformed, or a frame has
+/// an invalid marker.
+pub fn inspect(container: &[u8]) -> Result<Inspection, Error> {
+    let mut reader = container;
+    let header = Header::read_from(&mut reader).or(Err(Error))?;
+    let header_len = container.len() - reader.len();
+
+    let mut inspection = Inspection {
+        header,
+        header_len,
+        segments: Vec::new(),
+        finished: false,
+        truncated: false,
+        trailing_bytes: 0,
+    };
+
+    let mut offset = header_len;
+
+    while offset < container.len() {
+        if inspection.finished {
+            inspection.trailing_bytes = container.len() - offset;
+            break;
+        }
+
+        let remaining = container.len() - offset;
+
+        if remaining < FRAME_HEADER_SIZE {
+            inspection.truncated = true;
+            break;
+        }
+
+        let (last, len) = parse_frame_header(&container[offset..offset + FRAME_HEADER_SIZE])
+            .or(Err(Error))?;
+
+        if len > (remaining - FRAME_HEADER_SIZE) as u64 {
+            inspection.truncated = true;
+            break;
+        }
+
+        inspection.segments.push(SegmentInfo {
+            offset: offset + FRAME_HEADER_SIZE,
+            len: len as usize,
+            last,
+        });
+
+        inspection.finished = last;
+        offset += FRAME_HEADER_SIZE + len as usize;
+    }
+
+    Ok(inspection)
+}
+
+impl Inspection {
+    /// Serialize this inspection report as a JSON object
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{");
+        json.push_str("\"version\":");
+        json.push_str(&CONTAINER_VERSION.to_string());
+        json.push_str(",\"algorithm\":");
+        push_json_string(&mut json, &self.header.algorithm);
+        json.push_str(",\"supported\":");
+        json.push_str(if self.header.algorithm().is_ok() { "true" } else { "false" });
+        json.push_str(",\"ephemeral_key\":");
+        push_json_string(&mut json, &to_hex(self.header.ephemeral_key.as_bytes()));
+        let _ = write!(json, ",\"salt_len\":{}", self.header.salt.len());
+        let _ = write!(json, ",\"header_len\":{}", self.header_len);
+        let _ = write!(json, ",\"segment_count\":{}", self.segments.len());
+        json.push_str(",\"segments\":[");
+
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+
+            let _ = write!(
+                json,
+                "{{\"offset\":{},\"len\":{},\"last\":{}}}",
+                segment.offset,
+                segment.len,
+                segment.last
+            );
+        }
+
+        let _ = write!(
+            json,
+            "],\"finished\":{},\"truncated\":{},\"trailing_bytes\":{}}}",
+            self.finished,
+            self.truncated,
+            self.trailing_bytes
+        );
+
+        json
+    }
+}
+
+/// Parse a frame's marker and ciphertext length
+pub(crate) fn parse_frame_header(header: &[u8]) -> io::Result<(bool, u64)> {
+    let last = match header[0] {
+        MARKER_NEXT => false,
+        MARKER_LAST => true,
+        _ => return Err(invalid_data("invalid segment marker")),
+    };
+
+    let mut len = [0u8; 4];
+    len.copy_from_slice(&header[1..FRAME_HEADER_SIZE]);
+    Ok((last, u64::from(u32::from_be_bytes(len))))
+}
+
+/// Append a string to JSON output as a quoted, escaped string
+fn push_json_string(json: &mut String, value: &str) {
+    json.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
@@ -0,0 +1,576 @@
+//! `dynamic.rs`: `XSTREAM` encryptors and decryptors whose algorithms are
+//! selected at runtime, e.g. from an algorithm identifier stored in a header
+
+use algorithm::Algorithm;
+use error::Error;
+use gcmsiv::{Aes128GcmSiv, Aes256GcmSiv};
+use keys::{PrivateKey, PublicKey};
+use miscreant::aead::{self, Aes128PmacSiv, Aes128Siv, Aes256PmacSiv, Aes256Siv};
+use rand::{OsRng, Rng};
+use ratchet::Rekey;
+use state::{STATE_KEY_SIZE, State};
+use traits::{Decryptor, Encryptor, Exporter};
+use x25519hkdf::{X25519HkdfSha256Decryptor, X25519HkdfSha256Encryptor};
+
+/// Object-safe counterpart of the `Encryptor` trait
+trait BoxedEncryptor {
+    fn seal_next_in_place(&mut self, ad: &[u8], buffer: &mut [u8]);
+    fn seal_last_in_place(self: Box<Self>, ad: &[u8], buffer: &mut [u8]);
+    fn seal_next(&mut self, ad: &[u8], plaintext: &[u8]) -> Vec<u8>;
+    fn try_seal_next(&mut self, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error>;
+    fn segments_sealed(&self) -> u64;
+    fn bytes_sealed(&self) -> u64;
+    fn remaining_segments(&self) -> Option<u64>;
+    fn seal_last(self: Box<Self>, ad: &[u8], plaintext: &[u8]) -> Vec<u8>;
+    fn export(&self, label: &[u8], context: &[u8], length: usize) -> Result<Vec<u8>, Error>;
+    fn set_rekey(&mut self, rekey: Rekey) -> Result<(), Error>;
+    fn checkpoint(self: Box<Self>, state_key: &[u8; STATE_KEY_SIZE], algorithm: &str) -> Vec<u8>;
+    #[cfg(feature = "parallel")]
+    fn seal_next_par(&mut self, ad: &[u8], segments: &[&[u8]]) -> Vec<Vec<u8>>;
+    #[cfg(feature = "parallel")]
+    fn seal_last_par(self: Box<Self>, ad: &[u8], segments: &[&[u8]]) -> Vec<Vec<u8>>;
+}
+
+impl<A: aead::Algorithm> BoxedEncryptor for X25519HkdfSha256Encryptor<A> {
+    fn seal_next_in_place(&mut self, ad: &[u8], buffer: &mut [u8]) {
+        Encryptor::seal_next_in_place(self, ad, buffer)
+    }
+
+    fn seal_last_in_place(self: Box<Self>, ad: &[u8], buffer: &mut [u8]) {
+        Encryptor::seal_last_in_place(*self, ad, buffer)
+    }
+
+    fn seal_next(&mut self, ad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        Encryptor::seal_next(self, ad, plaintext)
+    }
+
+    fn try_seal_next(&mut self, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        Encryptor::try_seal_next(self, ad, plaintext)
+    }
+
+    fn segments_sealed(&self) -> u64 {
+        X25519HkdfSha256Encryptor::segments_sealed(self)
+    }
+
+    fn bytes_sealed(&self) -> u64 {
+        X25519HkdfSha256Encryptor::bytes_sealed(self)
+    }
+
+    fn remaining_segments(&self) -> Option<u64> {
+        X25519HkdfSha256Encryptor::remaining_segments(self)
+    }
+
+    fn seal_last(self: Box<Self>, ad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        Encryptor::seal_last(*self, ad, plaintext)
+    }
+
+    fn export(&self, label: &[u8], context: &[u8], length: usize) -> Result<Vec<u8>, Error> {
+        Exporter::export(self, label, context, length)
+    }
+
+    fn set_rekey(&mut self, rekey: Rekey) -> Result<(), Error> {
+        X25519HkdfSha256Encryptor::set_rekey(self, rekey)
+    }
+
+    fn checkpoint(self: Box<Self>, state_key: &[u8; STATE_KEY_SIZE], algorithm: &str) -> Vec<u8> {
+        X25519HkdfSha256Encryptor::checkpoint_tagged(*self, state_key, algorithm)
+    }
+
+    #[cfg(feature = "parallel")]
+    fn seal_next_par(&mut self, ad: &[u8], segments: &[&[u8]]) -> Vec<Vec<u8>> {
+        X25519HkdfSha256Encryptor::seal_next_par(self, ad, segments)
+    }
+
+    #[cfg(feature = "parallel")]
+    fn seal_last_par(self: Box<Self>, ad: &[u8], segments: &[&[u8]]) -> Vec<Vec<u8>> {
+        X25519HkdfSha256Encryptor::seal_last_par(*self, ad, segments)
+    }
+}
+
+/// Object-safe counterpart of the `Decryptor` trait
+trait BoxedDecryptor {
+    fn open_next_in_place<'a>(
+        &mut self,
+        ad: &[u8],
+        buffer: &'a mut [u8],
+    ) -> Result<&'a [u8], Error>;
+    fn open_last_in_place<'a>(
+        self: Box<Self>,
+        ad: &[u8],
+        buffer: &'a mut [u8],
+    ) -> Result<&'a [u8], Error>;
+    fn open_next(&mut self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error>;
+    fn open_last(self: Box<Self>, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error>;
+    fn open_at(
+        &mut self,
+        index: u32,
+        last: bool,
+        ad: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, Error>;
+    fn export(&self, label: &[u8], context: &[u8], length: usize) -> Result<Vec<u8>, Error>;
+    fn set_rekey(&mut self, rekey: Rekey) -> Result<(), Error>;
+    #[cfg(feature = "parallel")]
+    fn open_next_par(&mut self, ad: &[u8], ciphertexts: &[&[u8]]) -> Result<Vec<Vec<u8>>, Error>;
+    #[cfg(feature = "parallel")]
+    fn open_last_par(
+        self: Box<Self>,
+        ad: &[u8],
+        ciphertexts: &[&[u8]],
+    ) -> Result<Vec<Vec<u8>>, Error>;
+}
+
+impl<A: aead::Algorithm> BoxedDecryptor for X25519HkdfSha256Decryptor<A> {
+    fn open_next_in_place<'a>(
+        &mut self,
+        ad: &[u8],
+        buffer: &'a mut [u8],
+    ) -> Result<&'a [u8], Error> {
+        Decryptor::open_next_in_place(self, ad, buffer)
+    }
+
+    fn open_last_in_place<'a>(
+        self: Box<Self>,
+        ad: &[u8],
+        buffer: &'a mut [u8],
+    ) -> Result<&'a [u8], Error> {
+        Decryptor::open_last_in_place(*self, ad, buffer)
+    }
+
+    fn open_next(&mut self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        Decryptor::open_next(self, ad, ciphertext)
+    }
+
+    fn open_last(self: Box<Self>, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        Decryptor::open_last(*self, ad, ciphertext)
+    }
+
+    fn open_at(
+        &mut self,
+        index: u32,
+        last: bool,
+        ad: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        X25519HkdfSha256Decryptor::open_at(self, index, last, ad, ciphertext)
+    }
+
+    fn export(&self, label: &[u8], context: &[u8], length: usize) -> Result<Vec<u8>, Error> {
+        Exporter::export(self, label, context, length)
+    }
+
+    fn set_rekey(&mut self, rekey: Rekey) -> Result<(), Error> {
+        X25519HkdfSha256Decryptor::set_rekey(self, rekey)
+    }
+
+    #[cfg(feature = "parallel")]
+    fn open_next_par(&mut self, ad: &[u8], ciphertexts: &[&[u8]]) -> Result<Vec<Vec<u8>>, Error> {
+        X25519HkdfSha256Decryptor::open_next_par(self, ad, ciphertexts)
+    }
+
+    #[cfg(feature = "parallel")]
+    fn open_last_par(
+        self: Box<Self>,
+        ad: &[u8],
+        ciphertexts: &[&[u8]],
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        X25519HkdfSha256Decryptor::open_last_par(*self, ad, ciphertexts)
+    }
+}
+
+/// `XSTREAM` encryptor for an algorithm selected at runtime
+pub struct DynEncryptor {
+    algorithm: Algorithm,
+    inner: Box<dyn BoxedEncryptor>,
+}
+
+impl DynEncryptor {
+    /// Create a new encryptor for the given algorithm which seals a stream of
+    /// messages under the given public key. See `Encryptor::new`.
+    pub fn new<R: Rng>(
+        algorithm: Algorithm,
+        csprng: &mut R,
+        public_key: &PublicKey,
+        salt: Option<&[u8]>,
+    ) -> Result<(Self, PublicKey), Error> {
+        Self::new_with_context(algorithm, csprng, public_key, salt, b"")
+    }
+
+    /// Create a new encryptor for the given algorithm whose key is bound to
+    /// the given application context. See
+    /// `X25519HkdfEncryptor::new_with_context`.
+    pub fn new_with_context<R: Rng>(
+        algorithm: Algorithm,
+        csprng: &mut R,
+        public_key: &PublicKey,
+        salt: Option<&[u8]>,
+        context: &[u8],
+    ) -> Result<(Self, PublicKey), Error> {
+        let (inner, ephemeral_key) = match algorithm {
+            Algorithm::X25519HkdfSha256Aes128Siv => {
+                boxed_encryptor::<Aes128Siv, R>(csprng, public_key, salt, context)
+            }
+            Algorithm::X25519HkdfSha256Aes256Siv => {
+                boxed_encryptor::<Aes256Siv, R>(csprng, public_key, salt, context)
+            }
+            Algorithm::X25519HkdfSha256Aes128PmacSiv => {
+                boxed_encryptor::<Aes128PmacSiv, R>(csprng, public_key, salt, context)
+            }
+            Algorithm::X25519HkdfSha256Aes256PmacSiv => {
+                boxed_encryptor::<Aes256PmacSiv, R>(csprng, public_key, salt, context)
+            }
+            Algorithm::X25519HkdfSha256Aes128GcmSiv => {
+                boxed_encryptor::<Aes128GcmSiv, R>(csprng, public_key, salt, context)
+            }
+            Algorithm::X25519HkdfSha256Aes256GcmSiv => {
+                boxed_encryptor::<Aes256GcmSiv, R>(csprng, public_key, salt, context)
+            }
+        }?;
+
+        let encryptor = Self {
+            algorithm,
+            inner,
+        };
+
+        Ok((encryptor, ephemeral_key))
+    }
+
+    /// Resume an encryptor from a blob produced by `DynEncryptor::checkpoint`,
+    /// using the algorithm the blob was tagged with. See
+    /// `X25519HkdfEncryptor::resume`, including for the handling of
+    /// `generation`.
+    pub fn resume(
+        state_key: &[u8; STATE_KEY_SIZE],
+        blob: &[u8],
+        generation: &mut u64,
+    ) -> Result<Self, Error> {
+        let algorithm: Algorithm = State::algorithm(blob)?.parse()?;
+        let id = algorithm.as_str();
+
+        let inner = match algorithm {
+            Algorithm::X25519HkdfSha256Aes128Siv => {
+                boxed_resume::<Aes128Siv>(state_key, blob, id, generation)
+            }
+            Algorithm::X25519HkdfSha256Aes256Siv => {
+                boxed_resume::<Aes256Siv>(state_key, blob, id, generation)
+            }
+            Algorithm::X25519HkdfSha256Aes128PmacSiv => {
+                boxed_resume::<Aes128PmacSiv>(state_key, blob, id, generation)
+            }
+            Algorithm::X25519HkdfSha256Aes256PmacSiv => {
+                boxed_resume::<Aes256PmacSiv>(state_key, blob, id, generation)
+            }
+            Algorithm::X25519HkdfSha256Aes128GcmSiv => {
+                boxed_resume::<Aes128GcmSiv>(state_key, blob, id, generation)
+            }
+            Algorithm::X25519HkdfSha256Aes256GcmSiv => {
+                boxed_resume::<Aes256GcmSiv>(state_key, blob, id, generation)
+            }
+        }?;
+
+        Ok(Self {
+            algorithm,
+            inner,
+        })
+    }
+
+    /// Algorithm this encryptor is using
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Encrypt the next message in the stream in-place
+    pub fn seal_next_in_place(&mut self, ad: &[u8], buffer: &mut [u8]) {
+        self.inner.seal_next_in_place(ad, buffer)
+    }
+
+    /// Encrypt the final message in-place, consuming the stream encryptor
+    pub fn seal_last_in_place(self, ad: &[u8], buffer: &mut [u8]) {
+        self.inner.seal_last_in_place(ad, buffer)
+    }
+
+    /// Encrypt the next message in the stream, allocating and returning a
+    /// `Vec<u8>` for the ciphertext
+    pub fn seal_next(&mut self, ad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        self.inner.seal_next(ad, plaintext)
+    }
+
+    /// Encrypt the next message in the stream, or return an error if the
+    /// STREAM counter is exhausted. See `Encryptor::try_seal_next`.
+    pub fn try_seal_next(&mut self, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        self.inner.try_seal_next(ad, plaintext)
+    }
+
+    /// Number of segments sealed so far, not counting the last one
+    pub fn segments_sealed(&self) -> u64 {
+        self.inner.segments_sealed()
+    }
+
+    /// Number of bytes of ciphertext sealed so far, not counting the last
+    /// segment
+    pub fn bytes_sealed(&self) -> u64 {
+        self.inner.bytes_sealed()
+    }
+
+    /// Number of further segments which can be sealed before the STREAM
+    /// counter is exhausted. See `X25519HkdfEncryptor::remaining_segments`.
+    pub fn remaining_segments(&self) -> Option<u64> {
+        self.inner.remaining_segments()
+    }
+
+    /// Encrypt the final message in the stream, allocating and returning a
+    /// `Vec<u8>` for the ciphertext
+    pub fn seal_last(self, ad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        self.inner.seal_last(ad, plaintext)
+    }
+
+    /// Derive a secret bound to this stream's key exchange. See
+    /// `Exporter::export`.
+    pub fn export(&self, label: &[u8], context: &[u8], length: usize) -> Result<Vec<u8>, Error> {
+        self.inner.export(label, context, length)
+    }
+
+    /// Periodically replace the STREAM key according to the given rekeying
+    /// schedule. See `X25519HkdfEncryptor::set_rekey`.
+    pub fn set_rekey(&mut self, rekey: Rekey) -> Result<(), Error> {
+        self.inner.set_rekey(rekey)
+    }
+
+    /// Checkpoint this encryptor into an encrypted and authenticated blob
+    /// tagged with its algorithm. See `X25519HkdfEncryptor::checkpoint`.
+    pub fn checkpoint(self, state_key: &[u8; STATE_KEY_SIZE]) -> Vec<u8> {
+        self.inner.checkpoint(state_key, self.algorithm.as_str())
+    }
+
+    /// Encrypt the next segments in the stream in parallel using `rayon`.
+    /// See `X25519HkdfEncryptor::seal_next_par`.
+    #[cfg(feature = "parallel")]
+    pub fn seal_next_par<S: AsRef<[u8]>>(&mut self, ad: &[u8], segments: &[S]) -> Vec<Vec<u8>> {
+        self.inner.seal_next_par(ad, &as_slices(segments))
+    }
+
+    /// Encrypt the remaining segments in the stream in parallel using
+    /// `rayon`. See `X25519HkdfEncryptor::seal_last_par`.
+    #[cfg(feature = "parallel")]
+    pub fn seal_last_par<S: AsRef<[u8]>>(self, ad: &[u8], segments: &[S]) -> Vec<Vec<u8>> {
+        self.inner.seal_last_par(ad, &as_slices(segments))
+    }
+}
+
+/// `XSTREAM` decryptor for an algorithm selected at runtime
+pub struct DynDecryptor {
+    algorithm: Algorithm,
+    inner: Box<dyn BoxedDecryptor>,
+}
+
+impl DynDecryptor {
+    /// Create a new decryptor for the given algorithm which unseals a stream
+    /// of messages. See `Decryptor::new`.
+    pub fn new(
+        algorithm: Algorithm,
+        private_key: &PrivateKey,
+        ephemeral_key: &PublicKey,
+        salt: Option<&[u8]>,
+    ) -> Result<Self, Error> {
+        Self::new_with_context(algorithm, private_key, ephemeral_key, salt, b"")
+    }
+
+    /// Create a new decryptor for the given algorithm whose key is bound to
+    /// the given application context. See
+    /// `X25519HkdfDecryptor::new_with_context`.
+    pub fn new_with_context(
+        algorithm: Algorithm,
+        private_key: &PrivateKey,
+        ephemeral_key: &PublicKey,
+        salt: Option<&[u8]>,
+        context: &[u8],
+    ) -> Result<Self, Error> {
+        let inner = match algorithm {
+            Algorithm::X25519HkdfSha256Aes128Siv => {
+                boxed_decryptor::<Aes128Siv>(private_key, ephemeral_key, salt, context)
+            }
+            Algorithm::X25519HkdfSha256Aes256Siv => {
+                boxed_decryptor::<Aes256Siv>(private_key, ephemeral_key, salt, context)
+            }
+            Algorithm::X25519HkdfSha256Aes128PmacSiv => {
+                boxed_decryptor::<Aes128PmacSiv>(private_key, ephemeral_key, salt, context)
+            }
+            Algorithm::X25519HkdfSha256Aes256PmacSiv => {
+                boxed_decryptor::<Aes256PmacSiv>(private_key, ephemeral_key, salt, context)
+            }
+            Algorithm::X25519HkdfSha256Aes128GcmSiv => {
+                boxed_decryptor::<Aes128GcmSiv>(private_key, ephemeral_key, salt, context)
+            }
+            Algorithm::X25519HkdfSha256Aes256GcmSiv => {
+                boxed_decryptor::<Aes256GcmSiv>(private_key, ephemeral_key, salt, context)
+            }
+        }?;
+
+        Ok(Self {
+            algorithm,
+            inner,
+        })
+    }
+
+    /// Algorithm this decryptor is using
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Decrypt the next message in the stream in-place
+    pub fn open_next_in_place<'a>(
+        &mut self,
+        ad: &[u8],
+        buffer: &'a mut [u8],
+    ) -> Result<&'a [u8], Error> {
+        self.inner.open_next_in_place(ad, buffer)
+    }
+
+    /// Decrypt the final message in-place, consuming the stream decryptor
+    pub fn open_last_in_place<'a>(self, ad: &[u8], buffer: &'a mut [u8]) -> Result<&'a [u8], Error> {
+        self.inner.open_last_in_place(ad, buffer)
+    }
+
+    /// Decrypt the next message in the stream, allocating and returning a
+    /// `Vec<u8>` for the plaintext
+    pub fn open_next(&mut self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        self.inner.open_next(ad, ciphertext)
+    }
+
+    /// Decrypt the final message in the stream, allocating and returning a
+    /// `Vec<u8>` for the plaintext
+    pub fn open_last(self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        self.inner.open_last(ad, ciphertext)
+    }
+
+    /// Decrypt the segment with the given index, without decrypting any of
+    /// the segments which precede it. See `X25519HkdfDecryptor::open_at`.
+    pub fn open_at(
+        &mut self,
+        index: u32,
+        last: bool,
+        ad: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        self.inner.open_at(index, last, ad, ciphertext)
+    }
+
+    /// Derive a secret bound to this stream's key exchange. See
+    /// `Exporter::export`.
+    pub fn export(&self, label: &[u8], context: &[u8], length: usize) -> Result<Vec<u8>, Error> {
+        self.inner.export(label, context, length)
+    }
+
+    /// Follow the given rekeying schedule. See
+    /// `X25519HkdfDecryptor::set_rekey`.
+    pub fn set_rekey(&mut self, rekey: Rekey) -> Result<(), Error> {
+        self.inner.set_rekey(rekey)
+    }
+
+    /// Decrypt the next segments in the stream in parallel using `rayon`.
+    /// See `X25519HkdfDecryptor::open_next_par`.
+    #[cfg(feature = "parallel")]
+    pub fn open_next_par<C: AsRef<[u8]>>(
+        &mut self,
+        ad: &[u8],
+        ciphertexts: &[C],
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        self.inner.open_next_par(ad, &as_slices(ciphertexts))
+    }
+
+    /// Decrypt the remaining segments in the stream in parallel using
+    /// `rayon`. See `X25519HkdfDecryptor::open_last_par`.
+    #[cfg(feature = "parallel")]
+    pub fn open_last_par<C: AsRef<[u8]>>(
+        self,
+        ad: &[u8],
+        ciphertexts: &[C],
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        self.inner.open_last_par(ad, &as_slices(ciphertexts))
+    }
+}
+
+/// Create an encryptor for the algorithm with the given `XSTREAM_*`
+/// identifier, using the operating system's RNG to generate the ephemeral key.
+///
+/// Returns an error if the algorithm is unknown, the RNG is unavailable, or
+/// the public key is invalid.
+pub fn encryptor_for(
+    algorithm: &str,
+    public_key: &PublicKey,
+    salt: Option<&[u8]>,
+) -> Result<(DynEncryptor, PublicKey), Error> {
+    let algorithm = algorithm.parse()?;
+    let mut csprng = OsRng::new().or(Err(Error))?;
+    DynEncryptor::new(algorithm, &mut csprng, public_key, salt)
+}
+
+/// Create a decryptor for the algorithm with the given `XSTREAM_*` identifier
+///
+/// Returns an error if the algorithm is unknown or the ephemeral key is
+/// invalid.
+pub fn decryptor_for(
+    algorithm: &str,
+    private_key: &PrivateKey,
+    ephemeral_key: &PublicKey,
+    salt: Option<&[u8]>,
+) -> Result<DynDecryptor, Error> {
+    let algorithm = algorithm.parse()?;
+    DynDecryptor::new(algorithm, private_key, ephemeral_key, salt)
+}
+
+/// Create an X25519-HKDF-SHA256 encryptor for the AEAD algorithm `A` and
+/// box it
+fn boxed_encryptor<A, R>(
+    csprng: &mut R,
+    public_key: &PublicKey,
+    salt: Option<&[u8]>,
+    context: &[u8],
+) -> Result<(Box<dyn BoxedEncryptor>, PublicKey), Error>
+where
+    A: aead::Algorithm + 'static,
+    R: Rng,
+{
+    let (encryptor, ephemeral_key) =
+        X25519HkdfSha256Encryptor::<A>::new_with_context(csprng, public_key, salt, context)?;
+    Ok((Box::new(encryptor), ephemeral_key))
+}
+
+/// Resume an X25519-HKDF-SHA256 encryptor for the AEAD algorithm `A` from a
+/// state blob and box it
+fn boxed_resume<A>(
+    state_key: &[u8; STATE_KEY_SIZE],
+    blob: &[u8],
+    algorithm: &str,
+    generation: &mut u64,
+) -> Result<Box<dyn BoxedEncryptor>, Error>
+where
+    A: aead::Algorithm + 'static,
+{
+    let encryptor =
+        X25519HkdfSha256Encryptor::<A>::resume_tagged(state_key, blob, algorithm, generation)?;
+    Ok(Box::new(encryptor))
+}
+
+/// Create an X25519-HKDF-SHA256 decryptor for the AEAD algorithm `A` and
+/// box it
+fn boxed_decryptor<A>(
+    private_key: &PrivateKey,
+    ephemeral_key: &PublicKey,
+    salt: Option<&[u8]>,
+    context: &[u8],
+) -> Result<Box<dyn BoxedDecryptor>, Error>
+where
+    A: aead::Algorithm + 'static,
+{
+    let decryptor =
+        X25519HkdfSha256Decryptor::<A>::new_with_context(private_key, ephemeral_key, salt, context)?;
+    Ok(Box::new(decryptor))
+}
+
+/// Borrow each segment as a byte slice, so segments of any type can be passed
+/// through the object-safe traits
+#[cfg(feature = "parallel")]
+fn as_slices<S: AsRef<[u8]>>(segments: &[S]) -> Vec<&[u8]> {
+    segments.iter().map(|segment| segment.as_ref()).collect()
+}
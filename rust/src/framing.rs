@@ -0,0 +1,425 @@
+//! `framing.rs`: `std::io` adapters which read and write an `XSTREAM` as a
+//! container of framed sealed segments.
+//!
+//! Streams are written in the format described in `container`: a header
+//! naming the algorithm, ephemeral key and salt, followed by each segment as
+//! a marker byte, its ciphertext length (a 32-bit big endian integer) and
+//! the ciphertext. `SegmentReader` checks the header matches its opener.
+//! Fixed-size framing is required: every
+//! segment but the last contains exactly `segment_size` bytes of plaintext,
+//! which lets `SegmentReader` locate any segment without reading the ones
+//! before it. Segments are sealed with empty associated data.
+//!
+//! When the sealer was built with compression, segments are compressed
+//! before they're sealed, so frames vary in size. `SegmentReader` then reads
+//! every length prefix up front to locate the segments.
+//!
+//! When the sealer was built with a signing key, the signature trailer is
+//! part of the last frame. `SegmentReader` verifies it up front, which means
+//! reading the whole stream once before returning any plaintext.
+//!
+//! When the sealer was built with padding, the padding is written as part
+//! of the stream (possibly as extra segments) and `SegmentReader` excludes
+//! it from the plaintext it returns.
+
+use builder::{Opener, Sealer};
+use compression::FLAG_SIZE;
+use container::{self, FRAME_HEADER_SIZE, Header};
+use padding;
+use signature::SIGNATURE_SIZE;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Size of the authentication tag appended to every sealed segment
+const TAG_SIZE: usize = 16;
+
+/// Writes plaintext to an underlying writer as a container of sealed
+/// segments. `finish` must be called to seal the last segment.
+pub struct SegmentWriter<W: Write> {
+    sealer: Sealer,
+    inner: W,
+    segment_size: usize,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> SegmentWriter<W> {
+    /// Create a new writer, writing the container header immediately. The
+    /// sealer must have been built with a segment size (see
+    /// `XStreamBuilder::segment_size`).
+    pub fn new(sealer: Sealer, mut inner: W) -> io::Result<Self> {
+        let segment_size = sealer.segment_size().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "segment size required")
+        })?;
+        check_segment_size(segment_size as u64)?;
+
+        let header = sealer.header().expect("framed sealers have a header");
+        inner.write_all(&header.to_bytes())?;
+
+        Ok(Self {
+            sealer,
+            inner,
+            segment_size,
+            buffer: Vec::with_capacity(segment_size),
+        })
+    }
+
+    /// Seal the remaining buffered plaintext (and padding, if in use) as the
+    /// last segments, returning the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        let ciphertexts = self.sealer.finish(b"", &self.buffer).map_err(
+            |_| invalid_data("segment too large"),
+        )?;
+
+        let (last, rest) = ciphertexts.split_last().expect("last segment sealed");
+
+        for ciphertext in rest {
+            container::write_frame(&mut self.inner, false, ciphertext)?;
+        }
+
+        container::write_frame(&mut self.inner, true, last)?;
+
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for SegmentWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // A full segment is only sealed once more plaintext arrives, since
+        // until then it may turn out to be the last one
+        if self.buffer.len() == self.segment_size && !buf.is_empty() {
+            let ciphertext = self.sealer.seal_next(b"", &self.buffer).map_err(
+                |_| invalid_data("segment has wrong size"),
+            )?;
+
+            container::write_frame(&mut self.inner, false, &ciphertext)?;
+            self.buffer.clear();
+        }
+
+        let len = buf.len().min(self.segment_size - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reads plaintext from an underlying reader containing a container of
+/// sealed segments, supporting random access via `Seek`. Only the segments
+/// which are actually read are decrypted.
+pub struct SegmentReader<R: Read + Seek> {
+    opener: Opener,
+    inner: R,
+    segment_size: u64,
+    start: u64,
+    segment_count: u64,
+    last_frame_size: u64,
+    frames: Option<Vec<(u64, u64)>>,
+    plaintext_len: u64,
+    position: u64,
+    current: Option<(u64, Vec<u8>)>,
+}
+
+impl<R: Read + Seek> SegmentReader<R> {
+    /// Create a new reader for the container beginning at the underlying
+    /// reader's current position. The opener must have been built with the
+    /// algorithm, ephemeral key, salt and segment size used to seal the
+    /// stream: the container header is checked against them.
+    ///
+    /// The last segment is authenticated immediately, so truncated streams
+    /// are detected before any plaintext is returned. If padding is in use,
+    /// the segments containing the padding are authenticated as well, and if
+    /// the opener has a verifying key the stream's signature is verified.
+    pub fn new(opener: Opener, mut inner: R) -> io::Result<Self> {
+        let segment_size = opener.segment_size().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "segment size required")
+        })? as u64;
+        check_segment_size(segment_size)?;
+
+        let header = Header::read_from(&mut inner)?;
+
+        if Some(&header) != opener.header() {
+            return Err(invalid_data("container header doesn't match the opener"));
+        }
+
+        let frame_size = (FRAME_HEADER_SIZE + TAG_SIZE) as u64 + segment_size;
+        let trailer_size = opener.trailer_size() as u64;
+        let start = inner.stream_position()?;
+        let end = inner.seek(SeekFrom::End(0))?;
+        let total = end.checked_sub(start).ok_or_else(
+            || invalid_data("stream truncated"),
+        )?;
+
+        if total < (FRAME_HEADER_SIZE + TAG_SIZE) as u64 + trailer_size {
+            return Err(invalid_data("stream truncated"));
+        }
+
+        let (segment_count, last_frame_size, frames) = if opener.compression().is_some() {
+            let max_len = segment_size + (TAG_SIZE + FLAG_SIZE) as u64 + trailer_size;
+            let frames = index_frames(&mut inner, start, end, max_len)?;
+            (frames.len() as u64, 0, Some(frames))
+        } else {
+            let segment_count = (total - trailer_size).div_ceil(frame_size);
+            let last_frame_size = total - (segment_count - 1) * frame_size;
+
+            if last_frame_size < (FRAME_HEADER_SIZE + TAG_SIZE) as u64 + trailer_size {
+                return Err(invalid_data("invalid stream length"));
+            }
+
+            (segment_count, last_frame_size, None)
+        };
+
+        if segment_count - 1 > u64::from(u32::MAX) {
+            return Err(invalid_data("invalid stream length"));
+        }
+
+        let mut reader = Self {
+            opener,
+            inner,
+            segment_size,
+            start,
+            segment_count,
+            last_frame_size,
+            frames,
+            plaintext_len: 0,
+            position: 0,
+            current: None,
+        };
+
+        reader.verify_signature()?;
+        reader.load_segment(segment_count - 1)?;
+
+        let last_len = reader.current.as_ref().expect("segment loaded").1.len() as u64;
+        reader.plaintext_len = (segment_count - 1) * segment_size + last_len;
+
+        if reader.opener.padding().is_some() {
+            reader.remove_padding()?;
+        }
+
+        Ok(reader)
+    }
+
+    /// Length of the plaintext in bytes
+    pub fn plaintext_len(&self) -> u64 {
+        self.plaintext_len
+    }
+
+    /// Number of segments in the stream
+    pub fn segment_count(&self) -> u64 {
+        self.segment_count
+    }
+
+    /// Obtain the underlying reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Locate the padding marker by reading backwards from the last segment,
+    /// and exclude the padding from the plaintext length
+    fn remove_padding(&mut self) -> io::Result<()> {
+        let scheme = self.opener.padding().expect("padding in use");
+        let padded_len = self.plaintext_len;
+        let mut index = self.segment_count - 1;
+
+        let len = loop {
+            self.load_segment(index)?;
+            let segment = &self.current.as_ref().expect("segment loaded").1;
+
+            if segment.iter().any(|&byte| byte != 0) {
+                let offset = padding::unpad_len(segment).map_err(
+                    |_| invalid_data("malformed padding"),
+                )?;
+
+                break index * self.segment_size + offset as u64;
+            }
+
+            if index == 0 {
+                return Err(invalid_data("malformed padding"));
+            }
+
+            index -= 1;
+        };
+
+        if scheme.padded_len(len) != Some(padded_len) {
+            return Err(invalid_data("malformed padding"));
+        }
+
+        self.plaintext_len = len;
+        Ok(())
+    }
+
+    /// Read every segment to verify the stream's signature, if the opener
+    /// has a verifying key
+    fn verify_signature(&mut self) -> io::Result<()> {
+        let (mut transcript, verifying_key) = match self.opener.verifier() {
+            Some(verifier) => verifier,
+            None => return Ok(()),
+        };
+
+        for index in 0..self.segment_count - 1 {
+            transcript.update(b"", &self.read_frame(index)?, false);
+        }
+
+        let mut ciphertext = self.read_frame(self.segment_count - 1)?;
+        let signature = ciphertext.split_off(ciphertext.len() - SIGNATURE_SIZE);
+        transcript.update(b"", &ciphertext, true);
+
+        transcript.verify(&verifying_key, &signature).map_err(|_| {
+            invalid_data("invalid signature")
+        })
+    }
+
+    /// Read and decrypt the segment with the given index, unless it's
+    /// already the current segment
+    fn load_segment(&mut self, index: u64) -> io::Result<()> {
+        if let Some((current, _)) = self.current {
+            if current == index {
+                return Ok(());
+            }
+        }
+
+        let last = index == self.segment_count - 1;
+        let ciphertext = self.read_frame(index)?;
+
+        // The signature, if any, was verified when the reader was created
+        let plaintext = self.opener
+            .open_verified_at(index as u32, last, b"", &ciphertext)
+            .map_err(|_| invalid_data("segment is not authentic"))?;
+
+        self.current = Some((index, plaintext));
+        Ok(())
+    }
+
+    /// Read the ciphertext of the segment with the given index
+    fn read_frame(&mut self, index: u64) -> io::Result<Vec<u8>> {
+        let last = index == self.segment_count - 1;
+        let frame_size = (FRAME_HEADER_SIZE + TAG_SIZE) as u64 + self.segment_size;
+
+        let (offset, ciphertext_len) = match self.frames {
+            Some(ref frames) => frames[index as usize],
+            None if last => (
+                self.start + index * frame_size,
+                self.last_frame_size - FRAME_HEADER_SIZE as u64,
+            ),
+            None => (
+                self.start + index * frame_size,
+                frame_size - FRAME_HEADER_SIZE as u64,
+            ),
+        };
+
+        self.inner.seek(SeekFrom::Start(offset))?;
+
+        let mut frame_header = [0u8; FRAME_HEADER_SIZE];
+        self.inner.read_exact(&mut frame_header)?;
+
+        if container::parse_frame_header(&frame_header)? != (last, ciphertext_len) {
+            return Err(invalid_data("segment has wrong length or marker"));
+        }
+
+        let mut ciphertext = vec![0u8; ciphertext_len as usize];
+        self.inner.read_exact(&mut ciphertext)?;
+        Ok(ciphertext)
+    }
+}
+
+impl<R: Read + Seek> Read for SegmentReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.position >= self.plaintext_len {
+            return Ok(0);
+        }
+
+        let index = self.position / self.segment_size;
+        self.load_segment(index)?;
+
+        let offset = (self.position - index * self.segment_size) as usize;
+        let segment = &self.current.as_ref().expect("segment loaded").1;
+        let remaining = self.plaintext_len - self.position;
+        let len = (buf.len().min(segment.len() - offset) as u64).min(remaining) as usize;
+
+        buf[..len].copy_from_slice(&segment[offset..offset + len]);
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+impl<R: Read + Seek> Seek for SegmentReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => offset_position(self.plaintext_len, offset),
+            SeekFrom::Current(offset) => offset_position(self.position, offset),
+        };
+
+        self.position = position.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "invalid seek position")
+        })?;
+
+        Ok(self.position)
+    }
+}
+
+/// Locate every frame in a stream of variable-size (i.e. compressed)
+/// segments by reading their frame headers, returning the offset and
+/// ciphertext length of each
+fn index_frames<R: Read + Seek>(
+    reader: &mut R,
+    start: u64,
+    end: u64,
+    max_len: u64,
+) -> io::Result<Vec<(u64, u64)>> {
+    let mut frames = Vec::new();
+    let mut offset = start;
+
+    while offset < end {
+        if end - offset < FRAME_HEADER_SIZE as u64 {
+            return Err(invalid_data("stream truncated"));
+        }
+
+        reader.seek(SeekFrom::Start(offset))?;
+
+        let mut frame_header = [0u8; FRAME_HEADER_SIZE];
+        reader.read_exact(&mut frame_header)?;
+        let (_, ciphertext_len) = container::parse_frame_header(&frame_header)?;
+
+        if ciphertext_len < (TAG_SIZE + FLAG_SIZE) as u64 || ciphertext_len > max_len {
+            return Err(invalid_data("segment has wrong length"));
+        }
+
+        frames.push((offset, ciphertext_len));
+        offset += FRAME_HEADER_SIZE as u64 + ciphertext_len;
+    }
+
+    if offset > end {
+        return Err(invalid_data("stream truncated"));
+    }
+
+    Ok(frames)
+}
+
+/// Ensure sealed segments of the given size fit in the length prefix
+fn check_segment_size(segment_size: u64) -> io::Result<()> {
+    if segment_size + (TAG_SIZE + FLAG_SIZE) as u64 > u64::from(u32::MAX) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "segment size too large",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Apply a signed offset to a position, returning `None` on overflow or if
+/// the result would be negative
+fn offset_position(base: u64, offset: i64) -> Option<u64> {
+    if offset >= 0 {
+        base.checked_add(offset as u64)
+    } else {
+        base.checked_sub(offset.unsigned_abs())
+    }
+}
+
+/// Create an `InvalidData` I/O error
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
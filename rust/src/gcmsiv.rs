@@ -0,0 +1,118 @@
+//! `gcmsiv.rs`: AES-GCM-SIV (RFC 8452) adapted to the
+//! `miscreant::aead::Algorithm` trait, so it can be used as the AEAD
+//! algorithm underneath any `XSTREAM` encryptor or decryptor.
+
+use aes_gcm_siv::{Nonce, Tag};
+use aes_gcm_siv::aead::{AeadInPlace, NewAead};
+use generic_array::typenum::{U16, U32};
+use miscreant::{self, aead};
+
+/// Size of an AES-GCM-SIV nonce in bytes
+const NONCE_SIZE: usize = 12;
+
+/// Size of an AES-GCM-SIV authentication tag in bytes
+const TAG_SIZE: usize = 16;
+
+/// Convert a STREAM nonce into an AES-GCM-SIV nonce.
+///
+/// STREAM nonces are 13 bytes: an 8-byte prefix, a 32-bit big endian counter,
+/// and a 1-byte last block flag. `XSTREAM` always uses an all-zero prefix,
+/// so its first byte can be dropped to obtain a 96-bit AES-GCM-SIV nonce.
+///
+/// Panics if given anything other than a STREAM nonce with a zero first byte.
+fn stream_nonce(nonce: &[u8]) -> &Nonce {
+    assert_eq!(nonce.len(), NONCE_SIZE + 1, "expected a STREAM nonce");
+    assert_eq!(nonce[0], 0, "STREAM nonce prefix must begin with zero");
+    Nonce::from_slice(&nonce[1..])
+}
+
+macro_rules! impl_gcm_siv {
+    ($name:ident, $cipher:ty, $key_size:ty, $doc:expr) => {
+        #[doc = $doc]
+        pub struct $name($cipher);
+
+        impl aead::Algorithm for $name {
+            type KeySize = $key_size;
+
+            /// Create a new AES-GCM-SIV instance with the given key
+            fn new(key: &[u8]) -> Self {
+                $name(<$cipher>::new_from_slice(key).expect("invalid AES-GCM-SIV key size"))
+            }
+
+            /// Encrypt the given plaintext in-place. The last 16 bytes of the
+            /// buffer are reserved for the authentication tag, which follows
+            /// the ciphertext (as specified in RFC 8452).
+            fn seal_in_place(&mut self, nonce: &[u8], ad: &[u8], buffer: &mut [u8]) {
+                let msg_len = buffer
+                    .len()
+                    .checked_sub(TAG_SIZE)
+                    .expect("buffer too small for AES-GCM-SIV tag");
+
+                let tag = self.0
+                    .encrypt_in_place_detached(stream_nonce(nonce), ad, &mut buffer[..msg_len])
+                    .expect("AES-GCM-SIV message too long");
+
+                buffer[msg_len..].copy_from_slice(&tag);
+            }
+
+            /// Decrypt the given ciphertext in-place, returning the plaintext
+            /// portion of the buffer if it is authentic
+            fn open_in_place<'a>(
+                &mut self,
+                nonce: &[u8],
+                ad: &[u8],
+                buffer: &'a mut [u8],
+            ) -> Result<&'a [u8], miscreant::Error> {
+                let msg_len = match buffer.len().checked_sub(TAG_SIZE) {
+                    Some(len) => len,
+                    None => return Err(miscreant::Error),
+                };
+
+                let tag = Tag::clone_from_slice(&buffer[msg_len..]);
+
+                self.0
+                    .decrypt_in_place_detached(stream_nonce(nonce), ad, &mut buffer[..msg_len], &tag)
+                    .or(Err(miscreant::Error))?;
+
+                Ok(&buffer[..msg_len])
+            }
+
+            /// Encrypt the given plaintext, allocating and returning a
+            /// `Vec<u8>` for the ciphertext
+            fn seal(&mut self, nonce: &[u8], ad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+                let mut buffer = vec![0; plaintext.len() + TAG_SIZE];
+                buffer[..plaintext.len()].copy_from_slice(plaintext);
+                self.seal_in_place(nonce, ad, &mut buffer);
+                buffer
+            }
+
+            /// Decrypt the given ciphertext, allocating and returning a
+            /// `Vec<u8>` for the plaintext
+            fn open(
+                &mut self,
+                nonce: &[u8],
+                ad: &[u8],
+                ciphertext: &[u8],
+            ) -> Result<Vec<u8>, miscreant::Error> {
+                let mut buffer = Vec::from(ciphertext);
+                let msg_len = self.open_in_place(nonce, ad, &mut buffer)?.len();
+                buffer.truncate(msg_len);
+                Ok(buffer)
+            }
+        }
+    }
+}
+
+impl_gcm_siv!(
+    Aes128GcmSiv,
+    ::aes_gcm_siv::Aes128GcmSiv,
+    U16,
+    "AES-GCM-SIV with a 128-bit key"
+);
+
+impl_gcm_siv!(
+    Aes256GcmSiv,
+    ::aes_gcm_siv::Aes256GcmSiv,
+    U32,
+    "AES-GCM-SIV with a 256-bit key"
+);
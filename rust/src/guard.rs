@@ -0,0 +1,213 @@
+//! `guard.rs`: guards which catch streams that are never finished.
+//!
+//! A stream whose last segment is never sealed can't be opened: the
+//! recipient's `open_last` fails, often long after the bug was introduced.
+//! `SealGuard` wraps an `Encryptor` and reports being dropped before the
+//! last segment was sealed, by failing a debug assertion or calling a hook.
+//!
+//! Sealing the last segment through the guard also yields a `Finished`
+//! token, which `FinishedWriter` demands before it hands back the
+//! underlying writer, so code which forgets to finish a stream doesn't
+//! compile. The token isn't tied to a particular stream, so the writer
+//! checks it was finished when it's dropped, the same way the guard does.
+
+use error::Error;
+use std::fmt;
+use std::io::{self, Write};
+use std::thread;
+use traits::Encryptor;
+
+/// Proof that the last segment of a stream has been sealed
+#[must_use = "the stream is only complete once its last segment is written"]
+#[derive(Debug)]
+pub struct Finished(());
+
+/// Wraps an `Encryptor`, checking the last segment is sealed before the
+/// encryptor is dropped.
+///
+/// If the guard is dropped without calling `seal_last`, `seal_last_in_place`,
+/// or `abandon`, it calls its hook if one was set with `on_unfinished`, and
+/// otherwise fails a debug assertion. Nothing is checked while unwinding
+/// from a panic.
+#[must_use = "streams must be finished with `seal_last`"]
+pub struct SealGuard<E: Encryptor> {
+    encryptor: Option<E>,
+    check: UnfinishedCheck,
+}
+
+impl<E: Encryptor> SealGuard<E> {
+    /// Guard the given encryptor
+    pub fn new(encryptor: E) -> Self {
+        Self {
+            encryptor: Some(encryptor),
+            check: UnfinishedCheck::new("XSTREAM dropped without sealing the last segment"),
+        }
+    }
+
+    /// Call the given hook instead of failing a debug assertion if the
+    /// guard is dropped without finishing the stream, e.g. to log the bug
+    /// or increment a metric in release builds
+    pub fn on_unfinished<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut() + Send + 'static,
+    {
+        self.check.hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Borrow the guarded encryptor
+    pub fn get_ref(&self) -> &E {
+        self.encryptor.as_ref().expect("encryptor already taken")
+    }
+
+    /// Encrypt the next message in the stream in-place. See
+    /// `Encryptor::seal_next_in_place`.
+    pub fn seal_next_in_place(&mut self, ad: &[u8], buffer: &mut [u8]) {
+        self.get_mut().seal_next_in_place(ad, buffer)
+    }
+
+    /// Encrypt the next message in the stream. See `Encryptor::seal_next`.
+    pub fn seal_next(&mut self, ad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        self.get_mut().seal_next(ad, plaintext)
+    }
+
+    /// Encrypt the next message in the stream, or return an error if the
+    /// stream can't hold any more segments. See `Encryptor::try_seal_next`.
+    pub fn try_seal_next(&mut self, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        self.get_mut().try_seal_next(ad, plaintext)
+    }
+
+    /// Encrypt the final message in-place, returning proof that the stream
+    /// was finished
+    pub fn seal_last_in_place(mut self, ad: &[u8], buffer: &mut [u8]) -> Finished {
+        self.take().seal_last_in_place(ad, buffer);
+        Finished(())
+    }
+
+    /// Encrypt the final message, returning its ciphertext along with proof
+    /// that the stream was finished
+    pub fn seal_last(mut self, ad: &[u8], plaintext: &[u8]) -> (Vec<u8>, Finished) {
+        let ciphertext = self.take().seal_last(ad, plaintext);
+        (ciphertext, Finished(()))
+    }
+
+    /// Deliberately drop the stream without finishing it, e.g. when the
+    /// operation producing it failed
+    pub fn abandon(mut self) {
+        self.take();
+    }
+
+    /// Take the encryptor back without any checks
+    pub fn into_inner(mut self) -> E {
+        self.take()
+    }
+
+    /// Mutably borrow the guarded encryptor
+    fn get_mut(&mut self) -> &mut E {
+        self.encryptor.as_mut().expect("encryptor already taken")
+    }
+
+    /// Take the encryptor out of the guard, disarming it
+    fn take(&mut self) -> E {
+        self.check.armed = false;
+        self.encryptor.take().expect("encryptor already taken")
+    }
+}
+
+impl<E: Encryptor> fmt::Debug for SealGuard<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SealGuard")
+            .field("finished", &self.encryptor.is_none())
+            .finish()
+    }
+}
+
+/// Writes sealed segments to an underlying writer, which can only be
+/// flushed and recovered with proof that the stream was finished.
+///
+/// If the writer is dropped without calling `finish` or `abandon`, it calls
+/// its hook if one was set with `on_unfinished`, and otherwise fails a debug
+/// assertion. Nothing is checked while unwinding from a panic.
+#[must_use = "streams must be finished with `finish`"]
+pub struct FinishedWriter<W: Write> {
+    inner: W,
+    check: UnfinishedCheck,
+}
+
+impl<W: Write> FinishedWriter<W> {
+    /// Wrap the given writer
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            check: UnfinishedCheck::new("XSTREAM writer dropped without finishing the stream"),
+        }
+    }
+
+    /// Call the given hook instead of failing a debug assertion if the
+    /// writer is dropped without finishing the stream. See
+    /// `SealGuard::on_unfinished`.
+    pub fn on_unfinished<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut() + Send + 'static,
+    {
+        self.check.hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Write a sealed segment (or any other data) to the underlying writer
+    pub fn write_all(&mut self, data: &[u8]) -> io::Result<()> {
+        self.inner.write_all(data)
+    }
+
+    /// Flush the underlying writer and return it, given proof that the last
+    /// segment has been sealed
+    pub fn finish(mut self, _proof: Finished) -> io::Result<W> {
+        self.check.armed = false;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    /// Deliberately drop the stream without finishing it, e.g. when the
+    /// operation producing it failed
+    pub fn abandon(mut self) {
+        self.check.armed = false;
+    }
+}
+
+impl<W: Write> fmt::Debug for FinishedWriter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FinishedWriter")
+            .field("finished", &!self.check.armed)
+            .finish()
+    }
+}
+
+/// Calls a hook, or fails a debug assertion, if dropped while armed
+struct UnfinishedCheck {
+    armed: bool,
+    hook: Option<Box<dyn FnMut() + Send>>,
+    message: &'static str,
+}
+
+impl UnfinishedCheck {
+    fn new(message: &'static str) -> Self {
+        Self {
+            armed: true,
+            hook: None,
+            message,
+        }
+    }
+}
+
+impl Drop for UnfinishedCheck {
+    fn drop(&mut self) {
+        if !self.armed || thread::panicking() {
+            return;
+        }
+
+        match self.hook {
+            Some(ref mut hook) => hook(),
+            None => debug_assert!(false, "{}", self.message),
+        }
+    }
+}
@@ -0,0 +1,670 @@
+//! `hpke.rs`: Hybrid Public Key Encryption (HPKE) as described in RFC 9180,
+//! using DHKEM(X25519, HKDF-SHA256) and HKDF-SHA256, and exposed through the
+//! `XSTREAM` `Encryptor` and `Decryptor` traits.
+//!
+//! The `salt` passed to `Encryptor::new` and `Decryptor::new` is used as the
+//! HPKE `info` parameter, and selects the base mode. The PSK and Auth modes
+//! are available through the `new_psk`, `new_auth` and `new_auth_psk`
+//! constructors.
+//!
+//! Messages are sealed using the sequence numbered nonces of an HPKE context,
+//! and are interoperable with other RFC 9180 implementations. Unlike STREAM,
+//! HPKE has no notion of a final message, so `seal_last` and `open_last` do
+//! not detect truncation of the message sequence. Applications which need
+//! this must authenticate the number of messages themselves, e.g. in the
+//! associated data of the last message. For the same reason HPKE suites
+//! aren't registered in `Algorithm`, and can't be selected at runtime with
+//! `DynEncryptor` and `DynDecryptor`.
+
+pub use aes_gcm::{Aes128Gcm, Aes256Gcm};
+pub use chacha20poly1305::ChaCha20Poly1305;
+
+use aes_gcm::aead::{AeadInPlace, NewAead};
+use aes_gcm::aead::generic_array::GenericArray;
+use aes_gcm::aead::generic_array::typenum::Unsigned;
+use clear_on_drop::clear::Clear;
+use error::Error;
+use hmac::{Hmac, Mac};
+use keys::{KEY_SIZE, PrivateKey, PublicKey, shared_secret};
+use rand::Rng;
+use sha2::Sha256;
+use std::marker::PhantomData;
+use traits::{Encryptor, Decryptor};
+
+/// Version label prepended to all HPKE KDF inputs
+const HPKE_VERSION: &[u8] = b"HPKE-v1";
+
+/// Suite ID for DHKEM(X25519, HKDF-SHA256): "KEM" || I2OSP(0x0020, 2)
+const KEM_SUITE_ID: &[u8] = b"KEM\x00\x20";
+
+/// Identifier for DHKEM(X25519, HKDF-SHA256)
+const KEM_ID: u16 = 0x0020;
+
+/// Identifier for HKDF-SHA256
+const KDF_ID: u16 = 0x0001;
+
+/// Output size of HKDF-SHA256 (`Nh`)
+const HASH_SIZE: usize = 32;
+
+/// Size of an AEAD nonce (`Nn`). Identical for all supported AEADs.
+const NONCE_SIZE: usize = 12;
+
+/// Size of an AEAD authentication tag (`Nt`). Identical for all supported
+/// AEADs.
+const TAG_SIZE: usize = 16;
+
+/// HPKE modes, which determine how the sender is authenticated
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Mode {
+    /// No sender authentication
+    Base = 0x00,
+
+    /// Sender authenticated by possession of a pre-shared key
+    Psk = 0x01,
+
+    /// Sender authenticated by possession of an X25519 private key
+    Auth = 0x02,
+
+    /// Sender authenticated by both a pre-shared key and an X25519 private key
+    AuthPsk = 0x03,
+}
+
+/// AEAD algorithms which can be used with HPKE
+pub trait Aead: AeadInPlace + NewAead {
+    /// HPKE identifier for this algorithm
+    const AEAD_ID: u16;
+}
+
+impl Aead for Aes128Gcm {
+    const AEAD_ID: u16 = 0x0001;
+}
+
+impl Aead for Aes256Gcm {
+    const AEAD_ID: u16 = 0x0002;
+}
+
+impl Aead for ChaCha20Poly1305 {
+    const AEAD_ID: u16 = 0x0003;
+}
+
+/// HPKE encryptor object: seals a sequence of messages to a recipient's
+/// X25519 public key
+pub struct HpkeEncryptor<A: Aead> {
+    context: Context<A>,
+}
+
+impl<A: Aead> HpkeEncryptor<A> {
+    /// Create an encryptor in PSK mode, authenticating the sender with a
+    /// pre-shared key and its identifier (both of which must be non-empty)
+    pub fn new_psk<R: Rng>(
+        csprng: &mut R,
+        public_key: &PublicKey,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+    ) -> Result<(Self, PublicKey), Error> {
+        Self::setup(csprng, public_key, info, Some((psk, psk_id)), None)
+    }
+
+    /// Create an encryptor in Auth mode, authenticating the sender with
+    /// their X25519 private key
+    pub fn new_auth<R: Rng>(
+        csprng: &mut R,
+        public_key: &PublicKey,
+        info: &[u8],
+        sender_key: &PrivateKey,
+    ) -> Result<(Self, PublicKey), Error> {
+        Self::setup(csprng, public_key, info, None, Some(sender_key))
+    }
+
+    /// Create an encryptor in AuthPSK mode, authenticating the sender with
+    /// both a pre-shared key and their X25519 private key
+    pub fn new_auth_psk<R: Rng>(
+        csprng: &mut R,
+        public_key: &PublicKey,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+        sender_key: &PrivateKey,
+    ) -> Result<(Self, PublicKey), Error> {
+        Self::setup(csprng, public_key, info, Some((psk, psk_id)), Some(sender_key))
+    }
+
+    /// Derive a secret of the given length from this HPKE context, bound
+    /// to the given exporter context string
+    pub fn export(&self, exporter_context: &[u8], length: usize) -> Result<Vec<u8>, Error> {
+        self.context.export(exporter_context, length)
+    }
+
+    /// Set up an HPKE sender context (RFC 9180 section 5.1)
+    fn setup<R: Rng>(
+        csprng: &mut R,
+        public_key: &PublicKey,
+        info: &[u8],
+        psk: Option<(&[u8], &[u8])>,
+        sender_key: Option<&PrivateKey>,
+    ) -> Result<(Self, PublicKey), Error> {
+        let mode = select_mode(psk, sender_key.is_some())?;
+
+        // Generate an ephemeral key pair by deriving it from random input
+        let mut ikm = [0u8; KEY_SIZE];
+        csprng.fill_bytes(&mut ikm);
+        let (ephemeral_secret, ephemeral_public) = derive_key_pair(&ikm);
+        ikm.clear();
+
+        let mut dh = shared_secret(&ephemeral_secret.0, public_key.as_ref())?.to_vec();
+        let mut kem_context = ephemeral_public.as_bytes().to_vec();
+        kem_context.extend_from_slice(public_key.as_bytes());
+
+        if let Some(sender_key) = sender_key {
+            match shared_secret(&sender_key.0, public_key.as_ref()) {
+                Ok(mut auth_dh) => {
+                    dh.extend_from_slice(&auth_dh);
+                    auth_dh.clear();
+                }
+                Err(e) => {
+                    dh[..].clear();
+                    return Err(e);
+                }
+            }
+
+            kem_context.extend_from_slice(sender_key.public_key().as_bytes());
+        }
+
+        let mut kem_secret = extract_and_expand(&dh, &kem_context);
+        dh[..].clear();
+
+        let context = Context::new(mode, &kem_secret, info, psk);
+        kem_secret.clear();
+
+        Ok((Self { context }, ephemeral_public))
+    }
+}
+
+impl<A: Aead> Encryptor for HpkeEncryptor<A> {
+    type PublicKey = PublicKey;
+
+    /// Create a new encryptor in HPKE base mode, using the salt (if any) as
+    /// the HPKE `info` parameter
+    fn new<R: Rng>(
+        csprng: &mut R,
+        public_key: &PublicKey,
+        salt: Option<&[u8]>,
+    ) -> Result<(Self, PublicKey), Error> {
+        Self::setup(csprng, public_key, salt.unwrap_or(b""), None, None)
+    }
+
+    /// Encrypt the next message in the sequence in-place
+    fn seal_next_in_place(&mut self, ad: &[u8], buffer: &mut [u8]) {
+        self.context.seal_in_place(ad, buffer)
+    }
+
+    /// Encrypt the final message in-place, consuming the encryptor
+    fn seal_last_in_place(mut self, ad: &[u8], buffer: &mut [u8]) {
+        self.context.seal_in_place(ad, buffer)
+    }
+
+    /// Encrypt the next message in the sequence, allocating and returning a
+    /// `Vec<u8>` for the ciphertext
+    fn seal_next(&mut self, ad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        self.context.seal(ad, plaintext)
+    }
+
+    /// Encrypt the next message in the sequence, or return an error if the
+    /// sequence number is exhausted
+    fn try_seal_next(&mut self, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        if self.context.seq == u64::MAX {
+            return Err(Error);
+        }
+
+        Ok(self.context.seal(ad, plaintext))
+    }
+
+    /// Encrypt the final message in the sequence, allocating and returning a
+    /// `Vec<u8>` for the ciphertext
+    fn seal_last(mut self, ad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        self.context.seal(ad, plaintext)
+    }
+}
+
+/// HPKE decryptor object: opens a sequence of messages sealed to the public
+/// key corresponding to the recipient's private key
+pub struct HpkeDecryptor<A: Aead> {
+    context: Context<A>,
+}
+
+impl<A: Aead> HpkeDecryptor<A> {
+    /// Create a decryptor in PSK mode. The pre-shared key and its identifier
+    /// must match those used by the sender.
+    pub fn new_psk(
+        private_key: &PrivateKey,
+        ephemeral_key: &PublicKey,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+    ) -> Result<Self, Error> {
+        Self::setup(private_key, ephemeral_key, info, Some((psk, psk_id)), None)
+    }
+
+    /// Create a decryptor in Auth mode, which only opens messages sealed by
+    /// the holder of the private key for the given sender public key
+    pub fn new_auth(
+        private_key: &PrivateKey,
+        ephemeral_key: &PublicKey,
+        info: &[u8],
+        sender_key: &PublicKey,
+    ) -> Result<Self, Error> {
+        Self::setup(private_key, ephemeral_key, info, None, Some(sender_key))
+    }
+
+    /// Create a decryptor in AuthPSK mode
+    pub fn new_auth_psk(
+        private_key: &PrivateKey,
+        ephemeral_key: &PublicKey,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+        sender_key: &PublicKey,
+    ) -> Result<Self, Error> {
+        Self::setup(private_key, ephemeral_key, info, Some((psk, psk_id)), Some(sender_key))
+    }
+
+    /// Derive a secret of the given length from this HPKE context, bound
+    /// to the given exporter context string
+    pub fn export(&self, exporter_context: &[u8], length: usize) -> Result<Vec<u8>, Error> {
+        self.context.export(exporter_context, length)
+    }
+
+    /// Set up an HPKE recipient context (RFC 9180 section 5.1)
+    fn setup(
+        private_key: &PrivateKey,
+        ephemeral_key: &PublicKey,
+        info: &[u8],
+        psk: Option<(&[u8], &[u8])>,
+        sender_key: Option<&PublicKey>,
+    ) -> Result<Self, Error> {
+        let mode = select_mode(psk, sender_key.is_some())?;
+
+        let mut dh = shared_secret(&private_key.0, ephemeral_key.as_ref())?.to_vec();
+        let mut kem_context = ephemeral_key.as_bytes().to_vec();
+        kem_context.extend_from_slice(private_key.public_key().as_bytes());
+
+        if let Some(sender_key) = sender_key {
+            match shared_secret(&private_key.0, sender_key.as_ref()) {
+                Ok(mut auth_dh) => {
+                    dh.extend_from_slice(&auth_dh);
+                    auth_dh.clear();
+                }
+                Err(e) => {
+                    dh[..].clear();
+                    return Err(e);
+                }
+            }
+
+            kem_context.extend_from_slice(sender_key.as_bytes());
+        }
+
+        let mut kem_secret = extract_and_expand(&dh, &kem_context);
+        dh[..].clear();
+
+        let context = Context::new(mode, &kem_secret, info, psk);
+        kem_secret.clear();
+
+        Ok(Self { context })
+    }
+}
+
+impl<A: Aead> Decryptor for HpkeDecryptor<A> {
+    type PrivateKey = PrivateKey;
+    type PublicKey = PublicKey;
+
+    /// Create a new decryptor in HPKE base mode, using the salt (if any) as
+    /// the HPKE `info` parameter
+    fn new(
+        private_key: &PrivateKey,
+        ephemeral_key: &PublicKey,
+        salt: Option<&[u8]>,
+    ) -> Result<Self, Error> {
+        Self::setup(private_key, ephemeral_key, salt.unwrap_or(b""), None, None)
+    }
+
+    /// Decrypt the next message in the sequence in-place
+    fn open_next_in_place<'a>(
+        &mut self,
+        ad: &[u8],
+        buffer: &'a mut [u8],
+    ) -> Result<&'a [u8], Error> {
+        self.context.open_in_place(ad, buffer)
+    }
+
+    /// Decrypt the final message in-place, consuming the decryptor
+    fn open_last_in_place<'a>(
+        mut self,
+        ad: &[u8],
+        buffer: &'a mut [u8],
+    ) -> Result<&'a [u8], Error> {
+        self.context.open_in_place(ad, buffer)
+    }
+
+    /// Decrypt the next message in the sequence, allocating and returning a
+    /// `Vec<u8>` for the plaintext
+    fn open_next(&mut self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        self.context.open(ad, ciphertext)
+    }
+
+    /// Decrypt the final message in the sequence, allocating and returning a
+    /// `Vec<u8>` for the plaintext
+    fn open_last(mut self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        self.context.open(ad, ciphertext)
+    }
+}
+
+/// Encrypt a single message to the given public key in base mode
+/// (RFC 9180 `SealBase`), returning the encapsulated key and the ciphertext
+pub fn seal<A: Aead, R: Rng>(
+    csprng: &mut R,
+    public_key: &PublicKey,
+    info: &[u8],
+    ad: &[u8],
+    plaintext: &[u8],
+) -> Result<(PublicKey, Vec<u8>), Error> {
+    let (encryptor, ephemeral_key) = HpkeEncryptor::<A>::setup(csprng, public_key, info, None, None)?;
+    Ok((ephemeral_key, encryptor.seal_last(ad, plaintext)))
+}
+
+/// Decrypt a single message sealed in base mode (RFC 9180 `OpenBase`)
+pub fn open<A: Aead>(
+    private_key: &PrivateKey,
+    ephemeral_key: &PublicKey,
+    info: &[u8],
+    ad: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, Error> {
+    HpkeDecryptor::<A>::setup(private_key, ephemeral_key, info, None, None)?.open_last(ad, ciphertext)
+}
+
+/// Deterministically derive an X25519 key pair from the given input keying
+/// material (RFC 9180 `DeriveKeyPair`)
+pub fn derive_key_pair(ikm: &[u8]) -> (PrivateKey, PublicKey) {
+    let mut dkp_prk = labeled_extract(KEM_SUITE_ID, b"", b"dkp_prk", ikm);
+    let mut scalar = labeled_expand(KEM_SUITE_ID, &dkp_prk, b"sk", b"", KEY_SIZE);
+    dkp_prk.clear();
+
+    let private_key = PrivateKey::new(&scalar);
+    scalar[..].clear();
+
+    let public_key = private_key.public_key();
+    (private_key, public_key)
+}
+
+/// Keys and nonce state for an HPKE context (RFC 9180 section 5.2)
+struct Context<A: Aead> {
+    key: GenericArray<u8, <A as NewAead>::KeySize>,
+    suite_id: [u8; 10],
+    base_nonce: [u8; NONCE_SIZE],
+    exporter_secret: [u8; HASH_SIZE],
+    seq: u64,
+    aead: PhantomData<A>,
+}
+
+impl<A: Aead> Context<A> {
+    /// Derive a context from the KEM shared secret (RFC 9180 `KeySchedule`)
+    fn new(mode: Mode, kem_secret: &[u8], info: &[u8], psk: Option<(&[u8], &[u8])>) -> Self {
+        let suite_id = suite_id::<A>();
+        let (psk, psk_id) = psk.unwrap_or((b"", b""));
+
+        let mut key_schedule_context = vec![mode as u8];
+        key_schedule_context.extend_from_slice(&labeled_extract(
+            &suite_id,
+            b"",
+            b"psk_id_hash",
+            psk_id,
+        ));
+        key_schedule_context.extend_from_slice(&labeled_extract(
+            &suite_id,
+            b"",
+            b"info_hash",
+            info,
+        ));
+
+        let mut secret = labeled_extract(&suite_id, kem_secret, b"secret", psk);
+
+        let mut okm = labeled_expand(
+            &suite_id,
+            &secret,
+            b"key",
+            &key_schedule_context,
+            <A as NewAead>::KeySize::to_usize(),
+        );
+
+        let key = GenericArray::clone_from_slice(&okm);
+        okm[..].clear();
+
+        let mut base_nonce = [0u8; NONCE_SIZE];
+        base_nonce.copy_from_slice(&labeled_expand(
+            &suite_id,
+            &secret,
+            b"base_nonce",
+            &key_schedule_context,
+            NONCE_SIZE,
+        ));
+
+        let mut exporter_secret = [0u8; HASH_SIZE];
+        exporter_secret.copy_from_slice(&labeled_expand(
+            &suite_id,
+            &secret,
+            b"exp",
+            &key_schedule_context,
+            HASH_SIZE,
+        ));
+
+        secret.clear();
+
+        Self {
+            key,
+            suite_id,
+            aead: PhantomData,
+            base_nonce,
+            exporter_secret,
+            seq: 0,
+        }
+    }
+
+    /// Encrypt the given buffer in-place. The last `TAG_SIZE` bytes of the
+    /// buffer are reserved for the authentication tag.
+    fn seal_in_place(&mut self, ad: &[u8], buffer: &mut [u8]) {
+        let msg_len = buffer
+            .len()
+            .checked_sub(TAG_SIZE)
+            .expect("buffer too small for HPKE tag");
+
+        let nonce = self.compute_nonce();
+
+        let tag = self.cipher()
+            .encrypt_in_place_detached(GenericArray::from_slice(&nonce), ad, &mut buffer[..msg_len])
+            .expect("HPKE message too long");
+
+        buffer[msg_len..].copy_from_slice(&tag);
+        self.increment_seq();
+    }
+
+    /// Decrypt the given buffer in-place, returning the plaintext portion of
+    /// the buffer if it is authentic
+    fn open_in_place<'a>(&mut self, ad: &[u8], buffer: &'a mut [u8]) -> Result<&'a [u8], Error> {
+        let msg_len = match buffer.len().checked_sub(TAG_SIZE) {
+            Some(len) => len,
+            None => return Err(Error),
+        };
+
+        let nonce = self.compute_nonce();
+        let tag = GenericArray::clone_from_slice(&buffer[msg_len..]);
+
+        self.cipher()
+            .decrypt_in_place_detached(
+                GenericArray::from_slice(&nonce),
+                ad,
+                &mut buffer[..msg_len],
+                &tag,
+            )
+            .or(Err(Error))?;
+
+        self.increment_seq();
+        Ok(&buffer[..msg_len])
+    }
+
+    /// Encrypt a message, allocating a `Vec<u8>` for the ciphertext
+    fn seal(&mut self, ad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let mut buffer = vec![0; plaintext.len() + TAG_SIZE];
+        buffer[..plaintext.len()].copy_from_slice(plaintext);
+        self.seal_in_place(ad, &mut buffer);
+        buffer
+    }
+
+    /// Decrypt a message, allocating a `Vec<u8>` for the plaintext
+    fn open(&mut self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut buffer = Vec::from(ciphertext);
+        let msg_len = self.open_in_place(ad, &mut buffer)?.len();
+        buffer.truncate(msg_len);
+        Ok(buffer)
+    }
+
+    /// Derive an exported secret (RFC 9180 section 5.3)
+    fn export(&self, exporter_context: &[u8], length: usize) -> Result<Vec<u8>, Error> {
+        if length > 255 * HASH_SIZE {
+            return Err(Error);
+        }
+
+        Ok(labeled_expand(
+            &self.suite_id,
+            &self.exporter_secret,
+            b"sec",
+            exporter_context,
+            length,
+        ))
+    }
+
+    /// Instantiate the AEAD. The key is kept in the context rather than the
+    /// cipher, since not every AEAD implementation erases its key schedule
+    /// when dropped.
+    fn cipher(&self) -> A {
+        A::new(&self.key)
+    }
+
+    /// XOR the sequence number into the base nonce
+    fn compute_nonce(&self) -> [u8; NONCE_SIZE] {
+        let mut nonce = self.base_nonce;
+        let seq = self.seq.to_be_bytes();
+
+        for (byte, seq_byte) in nonce[NONCE_SIZE - seq.len()..].iter_mut().zip(seq.iter()) {
+            *byte ^= *seq_byte;
+        }
+
+        nonce
+    }
+
+    /// Advance to the next sequence number
+    fn increment_seq(&mut self) {
+        self.seq = self.seq.checked_add(1).expect(
+            "HPKE sequence number overflowed",
+        );
+    }
+}
+
+/// Ensure secrets are cleared from memory on drop
+impl<A: Aead> Drop for Context<A> {
+    fn drop(&mut self) {
+        self.key.as_mut_slice().clear();
+        self.base_nonce.clear();
+        self.exporter_secret.clear();
+    }
+}
+
+/// Determine the HPKE mode from the sender authentication in use, ensuring
+/// a pre-shared key and its identifier are either both present or absent
+fn select_mode(psk: Option<(&[u8], &[u8])>, auth: bool) -> Result<Mode, Error> {
+    match psk {
+        Some((psk, psk_id)) => {
+            if psk.is_empty() || psk_id.is_empty() {
+                return Err(Error);
+            }
+
+            Ok(if auth { Mode::AuthPsk } else { Mode::Psk })
+        }
+        None => Ok(if auth { Mode::Auth } else { Mode::Base }),
+    }
+}
+
+/// Suite ID for the HPKE key schedule: "HPKE" || kem_id || kdf_id || aead_id
+fn suite_id<A: Aead>() -> [u8; 10] {
+    let mut suite_id = [0u8; 10];
+    suite_id[..4].copy_from_slice(b"HPKE");
+    suite_id[4..6].copy_from_slice(&KEM_ID.to_be_bytes());
+    suite_id[6..8].copy_from_slice(&KDF_ID.to_be_bytes());
+    suite_id[8..].copy_from_slice(&A::AEAD_ID.to_be_bytes());
+    suite_id
+}
+
+/// Compute the DHKEM shared secret from the Diffie-Hellman output(s) and the
+/// KEM context (RFC 9180 `ExtractAndExpand`)
+fn extract_and_expand(dh: &[u8], kem_context: &[u8]) -> [u8; HASH_SIZE] {
+    let mut eae_prk = labeled_extract(KEM_SUITE_ID, b"", b"eae_prk", dh);
+    let mut kem_secret = labeled_expand(KEM_SUITE_ID, &eae_prk, b"shared_secret", kem_context, HASH_SIZE);
+    eae_prk.clear();
+
+    let mut result = [0u8; HASH_SIZE];
+    result.copy_from_slice(&kem_secret);
+    kem_secret[..].clear();
+    result
+}
+
+/// HKDF-Extract with HPKE's domain separation (RFC 9180 `LabeledExtract`)
+fn labeled_extract(suite_id: &[u8], salt: &[u8], label: &[u8], ikm: &[u8]) -> [u8; HASH_SIZE] {
+    let mut hmac = Hmac::<Sha256>::new(salt).expect("HMAC accepts keys of any size");
+    hmac.input(HPKE_VERSION);
+    hmac.input(suite_id);
+    hmac.input(label);
+    hmac.input(ikm);
+
+    let mut prk = [0u8; HASH_SIZE];
+    prk.copy_from_slice(&hmac.result().code());
+    prk
+}
+
+/// HKDF-Expand with HPKE's domain separation (RFC 9180 `LabeledExpand`)
+fn labeled_expand(
+    suite_id: &[u8],
+    prk: &[u8],
+    label: &[u8],
+    info: &[u8],
+    length: usize,
+) -> Vec<u8> {
+    assert!(length <= 255 * HASH_SIZE, "HKDF output length too large");
+
+    let mut okm = Vec::with_capacity(length);
+    let mut block = [0u8; HASH_SIZE];
+    let mut counter = 1u8;
+
+    while okm.len() < length {
+        let mut hmac = Hmac::<Sha256>::new(prk).expect("HMAC accepts keys of any size");
+
+        if counter > 1 {
+            hmac.input(&block);
+        }
+
+        hmac.input(&(length as u16).to_be_bytes());
+        hmac.input(HPKE_VERSION);
+        hmac.input(suite_id);
+        hmac.input(label);
+        hmac.input(info);
+        hmac.input(&[counter]);
+
+        block.copy_from_slice(&hmac.result().code());
+
+        let remaining = length - okm.len();
+        okm.extend_from_slice(&block[..remaining.min(HASH_SIZE)]);
+        counter = counter.wrapping_add(1);
+    }
+
+    block.clear();
+    okm
+}
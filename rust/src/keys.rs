@@ -1,8 +1,9 @@
 //! `keys.rs`: Public and private keys for X25519
 
 use clear_on_drop::clear::Clear;
+use error::Error;
 use rand::Rng;
-use x25519_dalek::{generate_public, generate_secret};
+use x25519_dalek::{diffie_hellman, generate_public, generate_secret};
 
 /// Length of an X25519 key (private or public) in bytes
 pub const KEY_SIZE: usize = 32;
@@ -57,6 +58,7 @@ impl Drop for PrivateKey {
 }
 
 /// X25519 public key (i.e. compressed Montgomery-u coordinate)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct PublicKey(pub(crate) [u8; KEY_SIZE]);
 
 impl PublicKey {
@@ -69,6 +71,17 @@ impl PublicKey {
         PublicKey(key)
     }
 
+    /// Parse a key from untrusted input, e.g. a ciphertext header
+    ///
+    /// Returns an error if the slice is the wrong size. Low-order keys are
+    /// accepted here, but rejected when used for key agreement.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != KEY_SIZE {
+            return Err(Error);
+        }
+
+        Ok(Self::new(bytes))
+    }
 
     /// Obtain this key as a byte slice
     pub fn as_bytes(&self) -> &[u8] {
@@ -93,3 +106,20 @@ impl Into<[u8; KEY_SIZE]> for PublicKey {
         self.0
     }
 }
+
+/// Compute an X25519 Diffie-Hellman shared secret, rejecting the all-zero
+/// output produced by low-order public keys (see RFC 7748 section 6.1)
+pub(crate) fn shared_secret(
+    private_key: &[u8; KEY_SIZE],
+    public_key: &[u8; KEY_SIZE],
+) -> Result<[u8; KEY_SIZE], Error> {
+    let mut shared_secret = diffie_hellman(private_key, public_key);
+
+    // Check for the all-zero value without branching on secret data
+    if shared_secret.iter().fold(0, |acc, byte| acc | byte) == 0 {
+        shared_secret.clear();
+        return Err(Error);
+    }
+
+    Ok(shared_secret)
+}
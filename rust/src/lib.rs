@@ -9,21 +9,64 @@
 #![deny(warnings, missing_docs, trivial_casts, trivial_numeric_casts)]
 #![deny(unsafe_code, unused_import_braces, unused_qualifications)]
 
+extern crate aes_gcm;
+extern crate aes_gcm_siv;
+extern crate chacha20poly1305;
 extern crate clear_on_drop;
 extern crate digest;
+extern crate ed25519_dalek;
+#[cfg(feature = "deflate")]
+extern crate flate2;
 extern crate generic_array;
+#[cfg(feature = "wasm")]
+extern crate getrandom;
 extern crate hkdf;
+extern crate hmac;
 extern crate miscreant;
 extern crate rand;
+#[cfg(feature = "parallel")]
+extern crate rayon;
 extern crate sha2;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
 extern crate x25519_dalek;
+#[cfg(feature = "zstd")]
+extern crate zstd;
 
+mod algorithm;
+pub mod armor;
+mod builder;
+mod compression;
+pub mod container;
+mod dynamic;
 mod error;
+mod framing;
+mod gcmsiv;
+mod guard;
+pub mod hpke;
 mod keys;
+mod padding;
+mod ratchet;
+mod signature;
+mod state;
+mod stream;
 mod traits;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 mod x25519hkdf;
 
+pub use self::algorithm::Algorithm;
+pub use self::builder::{XStreamBuilder, Sealer, Opener};
+pub use self::compression::Compression;
+pub use self::dynamic::{DynEncryptor, DynDecryptor, encryptor_for, decryptor_for};
 pub use self::error::Error;
+pub use self::framing::{SegmentReader, SegmentWriter};
+pub use self::gcmsiv::{Aes128GcmSiv, Aes256GcmSiv};
+pub use self::guard::{Finished, FinishedWriter, SealGuard};
 pub use self::keys::{PublicKey, PrivateKey};
-pub use self::traits::{Encryptor, Decryptor};
+pub use self::padding::Padding;
+pub use self::ratchet::Rekey;
+pub use self::signature::{ED25519_KEY_SIZE, SigningKey, VerifyingKey};
+pub use self::state::STATE_KEY_SIZE;
+pub use self::traits::{Encryptor, Decryptor, Exporter};
 pub use self::x25519hkdf::{X25519HkdfSha256Encryptor, X25519HkdfSha256Decryptor};
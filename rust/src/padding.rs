@@ -0,0 +1,129 @@
+//! `padding.rs`: length-hiding padding for `XSTREAM` plaintexts.
+//!
+//! Padding is applied to the stream as a whole: the plaintext is followed by
+//! a single `0x80` byte and as many zero bytes as are needed to reach the
+//! padded length selected by the padding scheme. The padding is encrypted
+//! along with the plaintext, so it spills over into extra segments when it
+//! doesn't fit in the last one, and is authenticated when it is removed.
+//!
+//! Observers learn only the padded length, i.e. which size class the
+//! plaintext falls into, rather than its exact length.
+
+use error::Error;
+
+/// Marker byte separating the plaintext from the zero padding
+const PADDING_MARKER: u8 = 0x80;
+
+/// Padding schemes used to select the padded length of a stream
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Padding {
+    /// PADMÉ, which leaks O(log log n) bits of the length with at most 12%
+    /// overhead. See "Reducing Metadata Leakage from Encrypted Files and
+    /// Communication with PURBs" (Nikitin et al., 2019).
+    Padme,
+
+    /// Pad to a multiple of the given bucket size in bytes (must be non-zero)
+    Bucket(u64),
+}
+
+impl Padding {
+    /// Compute the padded length of a plaintext with the given length,
+    /// including the padding marker, or `None` if it would overflow
+    pub fn padded_len(&self, len: u64) -> Option<u64> {
+        let len = len.checked_add(1)?;
+
+        match *self {
+            Padding::Padme => padme(len),
+            Padding::Bucket(size) => {
+                if size == 0 {
+                    return None;
+                }
+
+                len.checked_add(size - 1).map(|n| n / size * size)
+            }
+        }
+    }
+
+    /// Check the parameters of this padding scheme are valid
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        match *self {
+            Padding::Bucket(0) => Err(Error),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Round a length up to the nearest PADMÉ size class
+fn padme(len: u64) -> Option<u64> {
+    if len < 2 {
+        return Some(len);
+    }
+
+    let exponent = 63 - u64::from(len.leading_zeros());
+    let exponent_bits = 64 - u64::from(exponent.leading_zeros());
+    let mask = (1u64 << (exponent - exponent_bits)) - 1;
+
+    len.checked_add(mask).map(|n| n & !mask)
+}
+
+/// Append the padding marker and zero bytes to the given tail of a
+/// plaintext, so the whole plaintext (including the `preceding` bytes) is
+/// padded according to the given scheme
+pub(crate) fn pad(padding: &Padding, preceding: u64, tail: &[u8]) -> Result<Vec<u8>, Error> {
+    let len = preceding.checked_add(tail.len() as u64).ok_or(Error)?;
+    let padded_len = padding.padded_len(len).ok_or(Error)?;
+
+    if padded_len - preceding > usize::MAX as u64 {
+        return Err(Error);
+    }
+
+    let mut padded = Vec::with_capacity((padded_len - preceding) as usize);
+    padded.extend_from_slice(tail);
+    padded.push(PADDING_MARKER);
+    padded.resize((padded_len - preceding) as usize, 0);
+
+    Ok(padded)
+}
+
+/// Compute the earliest offset at which padding can start in a stream whose
+/// padded length is at least `len`, i.e. the shortest plaintext length which
+/// the scheme pads to `len` bytes or more. Everything before this offset is
+/// certainly plaintext, regardless of its contents.
+pub(crate) fn earliest_padding_start(padding: &Padding, len: u64) -> u64 {
+    // `padded_len` is monotonic, so binary search for the shortest length
+    // whose padded length (or overflow) reaches `len`
+    let (mut low, mut high) = (0, len);
+
+    while low < high {
+        let mid = low + (high - low) / 2;
+
+        match padding.padded_len(mid) {
+            Some(padded_len) if padded_len < len => low = mid + 1,
+            _ => high = mid,
+        }
+    }
+
+    low
+}
+
+/// Locate the start of what may be padding at the end of a partial
+/// plaintext: either the last `0x80` byte followed only by zeros, or the
+/// trailing zeros. Everything before this offset is certainly plaintext.
+pub(crate) fn padding_start(buffer: &[u8]) -> usize {
+    match buffer.iter().rposition(|&byte| byte != 0) {
+        Some(pos) if buffer[pos] == PADDING_MARKER => pos,
+        Some(pos) => pos + 1,
+        None => 0,
+    }
+}
+
+/// Locate the padding marker at the end of a complete plaintext, returning
+/// the length of the tail with the padding removed.
+///
+/// Returns an error if the padding is malformed.
+pub(crate) fn unpad_len(tail: &[u8]) -> Result<usize, Error> {
+    match tail.iter().rposition(|&byte| byte != 0) {
+        Some(pos) if tail[pos] == PADDING_MARKER => Ok(pos),
+        _ => Err(Error),
+    }
+}
@@ -0,0 +1,316 @@
+//! `ratchet.rs`: STREAM with optional ratcheted rekeying.
+//!
+//! When a rekeying schedule is configured, the STREAM key is replaced once
+//! enough segments or bytes have been processed under it: the next key is
+//! derived from the current one using HKDF, and the current key is erased.
+//! This bounds the amount of data encrypted under any one key, provides
+//! forward secrecy within a stream, and resets the STREAM counter so long
+//! streams don't run into its 32-bit limit.
+//!
+//! The encryptor and decryptor must use the same schedule. Rekeying happens
+//! after a segment which completes the interval is processed, and never
+//! after the last segment.
+
+use clear_on_drop::clear::Clear;
+use digest::Digest;
+use error::Error;
+use hkdf::Hkdf;
+use miscreant::aead;
+use std::marker::PhantomData;
+use stream::Stream;
+
+/// HKDF info used to derive the next STREAM key from the current one
+const REKEY_INFO: &[u8] = b"XSTREAM_REKEY";
+
+/// Rekeying schedules
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Rekey {
+    /// Derive a new key after every `n` segments (must be non-zero)
+    Segments(u32),
+
+    /// Derive a new key after the segment which brings the ciphertext sealed
+    /// under the current key to at least `n` bytes (must be non-zero)
+    Bytes(u64),
+}
+
+impl Rekey {
+    /// Check the interval is valid
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        match *self {
+            Rekey::Segments(0) | Rekey::Bytes(0) => Err(Error),
+            _ => Ok(()),
+        }
+    }
+
+    /// Is a new key due after processing this many segments and bytes?
+    fn is_due(&self, segments: u32, bytes: u64) -> bool {
+        match *self {
+            Rekey::Segments(n) => segments >= n,
+            Rekey::Bytes(n) => bytes >= n,
+        }
+    }
+}
+
+/// STREAM whose key is replaced according to a rekeying schedule. Tracks the
+/// STREAM counter, which restarts at zero under every new key.
+pub(crate) struct Ratchet<A: aead::Algorithm, D: Digest> {
+    stream: Stream<A>,
+    counter: u32,
+    epoch: u64,
+    epoch_bytes: u64,
+    rekey: Option<Rekey>,
+    segments: u64,
+    bytes: u64,
+    digest: PhantomData<D>,
+}
+
+/// Totals processed by a ratchet across all keys: the number of segments,
+/// and bytes of ciphertext
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Totals {
+    pub segments: u64,
+    pub bytes: u64,
+}
+
+impl<A, D> Ratchet<A, D>
+where
+    A: aead::Algorithm,
+    D: Digest,
+{
+    /// Create a new ratchet using the given initial key, without rekeying
+    pub fn new(key: &[u8]) -> Self {
+        Self::from_parts(key, 0, 0, 0, None, Totals::default())
+    }
+
+    /// Recreate a ratchet from its parts (see the corresponding accessors)
+    pub fn from_parts(
+        key: &[u8],
+        counter: u32,
+        epoch: u64,
+        epoch_bytes: u64,
+        rekey: Option<Rekey>,
+        totals: Totals,
+    ) -> Self {
+        Self {
+            stream: Stream::new(key),
+            counter,
+            epoch,
+            epoch_bytes,
+            rekey,
+            segments: totals.segments,
+            bytes: totals.bytes,
+            digest: PhantomData,
+        }
+    }
+
+    /// Configure the rekeying schedule.
+    ///
+    /// Returns an error if the schedule is invalid, or any segments have
+    /// already been processed.
+    pub fn set_rekey(&mut self, rekey: Rekey) -> Result<(), Error> {
+        rekey.validate()?;
+
+        if self.counter != 0 || self.epoch != 0 {
+            return Err(Error);
+        }
+
+        self.rekey = Some(rekey);
+        Ok(())
+    }
+
+    /// Rekeying schedule, if any
+    pub fn rekey(&self) -> Option<Rekey> {
+        self.rekey
+    }
+
+    /// Current STREAM key
+    pub fn key(&self) -> &[u8] {
+        self.stream.key()
+    }
+
+    /// STREAM counter value of the next segment under the current key
+    pub fn counter(&self) -> u32 {
+        self.counter
+    }
+
+    /// Number of times the key has been replaced
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Bytes of ciphertext processed under the current key
+    pub fn epoch_bytes(&self) -> u64 {
+        self.epoch_bytes
+    }
+
+    /// Segments (other than the last one) and bytes of their ciphertext
+    /// processed so far, across all keys
+    pub fn totals(&self) -> Totals {
+        Totals {
+            segments: self.segments,
+            bytes: self.bytes,
+        }
+    }
+
+    /// Number of further segments (other than the last one) which can be
+    /// processed before the STREAM counter is exhausted, or `None` if the
+    /// rekeying schedule resets the counter before that can happen
+    pub fn remaining_segments(&self) -> Option<u64> {
+        match self.rekey {
+            Some(Rekey::Segments(_)) => None,
+            _ => Some(u64::from(u32::MAX - self.counter)),
+        }
+    }
+
+    /// Check another segment (other than the last one) can be processed
+    /// under the current key. The final counter value is reserved for the
+    /// last segment.
+    pub fn check_next(&self) -> Result<(), Error> {
+        if self.counter == u32::MAX {
+            return Err(Error);
+        }
+
+        Ok(())
+    }
+
+    /// STREAM keyed with the current key
+    pub fn stream(&mut self) -> &mut Stream<A> {
+        &mut self.stream
+    }
+
+    /// Advance past a segment (other than the last one) with the given
+    /// ciphertext length, replacing the key if it's due.
+    ///
+    /// Panics if the STREAM counter would overflow.
+    pub fn advance(&mut self, ciphertext_len: usize) {
+        self.counter = self.counter.checked_add(1).expect(
+            "STREAM nonce counter overflowed",
+        );
+        self.epoch_bytes = self.epoch_bytes.saturating_add(ciphertext_len as u64);
+        self.segments += 1;
+        self.bytes = self.bytes.saturating_add(ciphertext_len as u64);
+
+        if let Some(rekey) = self.rekey {
+            if rekey.is_due(self.counter, self.epoch_bytes) {
+                self.ratchet();
+            }
+        }
+    }
+
+    /// Copy the ratchet in its current state
+    pub fn fork(&self) -> Self {
+        Self::from_parts(
+            self.stream.key(),
+            self.counter,
+            self.epoch,
+            self.epoch_bytes,
+            self.rekey,
+            self.totals(),
+        )
+    }
+
+    /// Decrypt the segment with the given index counted from the start of
+    /// the stream in-place, without affecting the state of the ratchet.
+    ///
+    /// Keys which have already been erased can't be recovered, so this
+    /// returns an error for segments sealed under an earlier key, or when
+    /// rekeying by bytes (which doesn't map indexes to keys).
+    pub fn open_at_in_place<'a>(
+        &mut self,
+        index: u32,
+        last: bool,
+        ad: &[u8],
+        buffer: &'a mut [u8],
+    ) -> Result<&'a [u8], Error> {
+        match self.locate(index)? {
+            (counter, Some(mut fork)) => fork.stream.open_in_place(counter, last, ad, buffer),
+            (counter, None) => self.stream.open_in_place(counter, last, ad, buffer),
+        }
+    }
+
+    /// Decrypt the segment with the given index counted from the start of
+    /// the stream, allocating and returning a `Vec<u8>` for the plaintext.
+    /// See `open_at_in_place`.
+    pub fn open_at(
+        &mut self,
+        index: u32,
+        last: bool,
+        ad: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        match self.locate(index)? {
+            (counter, Some(mut fork)) => fork.stream.open(counter, last, ad, ciphertext),
+            (counter, None) => self.stream.open(counter, last, ad, ciphertext),
+        }
+    }
+
+    /// Find the STREAM counter value of the segment with the given index
+    /// counted from the start of the stream, along with a ratchet holding
+    /// its key unless it's the current one
+    fn locate(&self, index: u32) -> Result<(u32, Option<Self>), Error> {
+        let interval = match self.rekey {
+            None => return Ok((index, None)),
+            Some(Rekey::Segments(n)) => n,
+            Some(Rekey::Bytes(_)) => return Err(Error),
+        };
+
+        let epoch = u64::from(index / interval);
+        let counter = index % interval;
+
+        if epoch < self.epoch {
+            return Err(Error);
+        }
+
+        if epoch == self.epoch {
+            return Ok((counter, None));
+        }
+
+        let mut fork = self.fork();
+
+        while fork.epoch < epoch {
+            fork.ratchet();
+        }
+
+        Ok((counter, Some(fork)))
+    }
+
+    /// Count how many of the upcoming segments, with the given ciphertext
+    /// lengths, can be processed under the current key
+    #[cfg(feature = "parallel")]
+    pub fn epoch_segments<I>(&self, ciphertext_lens: I) -> usize
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let mut segments = 0;
+        let mut counter = self.counter;
+        let mut bytes = self.epoch_bytes;
+
+        for len in ciphertext_lens {
+            segments += 1;
+            counter = counter.saturating_add(1);
+            bytes = bytes.saturating_add(len as u64);
+
+            if let Some(rekey) = self.rekey {
+                if rekey.is_due(counter, bytes) {
+                    break;
+                }
+            }
+        }
+
+        segments
+    }
+
+    /// Replace the current key with the next one
+    fn ratchet(&mut self) {
+        let mut hkdf: Hkdf<D> = Hkdf::new(self.stream.key(), &[]);
+        let mut key = hkdf.derive(REKEY_INFO, self.stream.key().len());
+
+        // Dropping the previous STREAM erases the previous key
+        self.stream = Stream::new(&key);
+        key.as_mut_slice().clear();
+
+        self.counter = 0;
+        self.epoch += 1;
+        self.epoch_bytes = 0;
+    }
+}
@@ -0,0 +1,128 @@
+//! `signature.rs`: Ed25519 signatures over an `XSTREAM` transcript, which
+//! let recipients verify the identity of the publisher of a stream.
+//!
+//! The transcript is a SHA-256 hash over the algorithm identifier, the
+//! ephemeral public key, the salt, the application context, and every
+//! sealed segment (along with its associated data) in order. The publisher
+//! signs the transcript hash with Ed25519, and the 64-byte signature is
+//! appended to the ciphertext of the last segment as a trailer.
+
+use algorithm::Algorithm;
+use digest::Digest;
+use ed25519_dalek::{self, Signer};
+use error::Error;
+use keys::PublicKey;
+use sha2::Sha256;
+
+/// Size of an Ed25519 signature trailer in bytes
+pub(crate) const SIGNATURE_SIZE: usize = 64;
+
+/// Size of Ed25519 secret and public keys in bytes
+pub const ED25519_KEY_SIZE: usize = 32;
+
+/// Domain separation string which begins every transcript
+const TRANSCRIPT_LABEL: &[u8] = b"XSTREAM_ED25519_TRANSCRIPT";
+
+/// Running hash of everything a stream signature covers
+#[derive(Clone)]
+pub(crate) struct Transcript(Sha256);
+
+impl Transcript {
+    /// Begin a transcript for a stream with the given parameters
+    pub fn new(
+        algorithm: Algorithm,
+        ephemeral_key: &PublicKey,
+        salt: Option<&[u8]>,
+        context: &[u8],
+    ) -> Self {
+        let mut transcript = Transcript(Sha256::default());
+        transcript.0.input(TRANSCRIPT_LABEL);
+        transcript.absorb(algorithm.as_str().as_bytes());
+        transcript.absorb(ephemeral_key.as_bytes());
+
+        // An absent salt is distinguished from an empty one
+        match salt {
+            Some(salt) => {
+                transcript.0.input(&[1]);
+                transcript.absorb(salt);
+            }
+            None => transcript.0.input(&[0]),
+        }
+
+        transcript.absorb(context);
+        transcript
+    }
+
+    /// Add a sealed segment and its associated data to the transcript
+    pub fn update(&mut self, ad: &[u8], ciphertext: &[u8], last: bool) {
+        self.absorb(ad);
+        self.absorb(ciphertext);
+        self.0.input(&[last as u8]);
+    }
+
+    /// Sign the completed transcript
+    pub fn sign(self, signing_key: &SigningKey) -> [u8; SIGNATURE_SIZE] {
+        signing_key.0.sign(&self.0.result()).to_bytes()
+    }
+
+    /// Verify a signature over the completed transcript
+    pub fn verify(self, verifying_key: &VerifyingKey, signature: &[u8]) -> Result<(), Error> {
+        let signature = ed25519_dalek::Signature::from_bytes(signature).or(Err(Error))?;
+
+        verifying_key
+            .0
+            .verify_strict(&self.0.result(), &signature)
+            .or(Err(Error))
+    }
+
+    /// Add a length-prefixed value to the transcript
+    fn absorb(&mut self, value: &[u8]) {
+        self.0.input(&(value.len() as u64).to_be_bytes());
+        self.0.input(value);
+    }
+}
+
+/// Ed25519 key used by a publisher to sign streams
+pub struct SigningKey(ed25519_dalek::Keypair);
+
+impl SigningKey {
+    /// Create a signing key from a 32-byte Ed25519 secret key
+    pub fn new(secret_key: &[u8; ED25519_KEY_SIZE]) -> Self {
+        let secret = ed25519_dalek::SecretKey::from_bytes(secret_key)
+            .expect("secret key has the correct length");
+        let public = ed25519_dalek::PublicKey::from(&secret);
+
+        SigningKey(ed25519_dalek::Keypair { secret, public })
+    }
+
+    /// Obtain the verifying key which corresponds to this signing key
+    pub fn verifying_key(&self) -> VerifyingKey {
+        VerifyingKey(self.0.public)
+    }
+}
+
+impl Clone for SigningKey {
+    fn clone(&self) -> Self {
+        SigningKey::new(self.0.secret.as_bytes())
+    }
+}
+
+/// Ed25519 public key used by recipients to verify the publisher of a stream
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct VerifyingKey(ed25519_dalek::PublicKey);
+
+impl VerifyingKey {
+    /// Parse a 32-byte Ed25519 public key.
+    ///
+    /// Returns an error if it isn't a valid Ed25519 public key.
+    pub fn from_bytes(bytes: &[u8; ED25519_KEY_SIZE]) -> Result<Self, Error> {
+        ed25519_dalek::PublicKey::from_bytes(bytes)
+            .map(VerifyingKey)
+            .or(Err(Error))
+    }
+
+    /// Serialize this verifying key as bytes
+    pub fn as_bytes(&self) -> &[u8; ED25519_KEY_SIZE] {
+        self.0.as_bytes()
+    }
+}
@@ -0,0 +1,173 @@
+//! `state.rs`: encrypted and authenticated serialization of encryptor state,
+//! used to checkpoint and later resume an interrupted stream.
+//!
+//! A state blob consists of a header (a version byte, followed by a
+//! length-prefixed algorithm identifier which may be empty) and the
+//! AES-128-SIV encryption of the rekeying state, the totals processed so far,
+//! the next STREAM counter, the checkpoint generation, the STREAM key and the
+//! exporter secret. The header is authenticated as associated data.
+//!
+//! Each checkpoint of a stream has a higher generation than the blob it was
+//! resumed from. A blob is only resumed if its generation is higher than the
+//! last one the caller resumed, so a blob can't be resumed twice.
+
+use clear_on_drop::clear::Clear;
+use error::Error;
+use miscreant::aead::{Aes128Siv, Algorithm};
+use ratchet::{Rekey, Totals};
+
+/// Size of the key used to encrypt state blobs in bytes
+pub const STATE_KEY_SIZE: usize = 32;
+
+/// Current state blob format version
+const STATE_VERSION: u8 = 2;
+
+/// Size of the encoded rekeying state: the schedule type and interval, the
+/// number of keys replaced, and the bytes processed under the current key
+const REKEY_STATE_SIZE: usize = 1 + 8 + 8 + 8;
+
+/// Size of the encoded totals: the number of segments and bytes processed
+const TOTALS_SIZE: usize = 8 + 8;
+
+/// Domain separation string passed as the AES-SIV nonce
+const STATE_NONCE: &[u8] = b"XSTREAM_STATE";
+
+/// Decrypted contents of a state blob, cleared on drop
+pub(crate) struct State {
+    pub counter: u32,
+    pub generation: u64,
+    pub epoch: u64,
+    pub epoch_bytes: u64,
+    pub rekey: Option<Rekey>,
+    pub totals: Totals,
+    pub key: Vec<u8>,
+    pub exporter_secret: Vec<u8>,
+}
+
+impl State {
+    /// Encrypt this state under the given key, tagged with the given
+    /// algorithm identifier
+    pub fn seal(&self, state_key: &[u8; STATE_KEY_SIZE], algorithm: &str) -> Vec<u8> {
+        assert!(algorithm.len() <= u8::MAX as usize, "algorithm identifier too long");
+
+        let mut header = vec![STATE_VERSION, algorithm.len() as u8];
+        header.extend_from_slice(algorithm.as_bytes());
+
+        let (rekey_type, rekey_interval) = match self.rekey {
+            None => (0u8, 0),
+            Some(Rekey::Segments(n)) => (1, u64::from(n)),
+            Some(Rekey::Bytes(n)) => (2, n),
+        };
+
+        let mut plaintext = Vec::with_capacity(
+            REKEY_STATE_SIZE + TOTALS_SIZE + 4 + 8 + 2 + self.key.len() +
+                self.exporter_secret.len(),
+        );
+        plaintext.push(rekey_type);
+        plaintext.extend_from_slice(&rekey_interval.to_be_bytes());
+        plaintext.extend_from_slice(&self.epoch.to_be_bytes());
+        plaintext.extend_from_slice(&self.epoch_bytes.to_be_bytes());
+        plaintext.extend_from_slice(&self.totals.segments.to_be_bytes());
+        plaintext.extend_from_slice(&self.totals.bytes.to_be_bytes());
+        plaintext.extend_from_slice(&self.counter.to_be_bytes());
+        plaintext.extend_from_slice(&self.generation.to_be_bytes());
+        plaintext.extend_from_slice(&(self.key.len() as u16).to_be_bytes());
+        plaintext.extend_from_slice(&self.key);
+        plaintext.extend_from_slice(&self.exporter_secret);
+
+        let ciphertext = Aes128Siv::new(state_key).seal(STATE_NONCE, &header, &plaintext);
+        plaintext.as_mut_slice().clear();
+
+        header.extend_from_slice(&ciphertext);
+        header
+    }
+
+    /// Decrypt a state blob, returning the algorithm identifier it was
+    /// tagged with along with the state.
+    ///
+    /// Returns an error if the blob was not produced by `seal` under the
+    /// given key, or is malformed.
+    pub fn open<'a>(
+        state_key: &[u8; STATE_KEY_SIZE],
+        blob: &'a [u8],
+    ) -> Result<(&'a str, Self), Error> {
+        let algorithm = Self::algorithm(blob)?;
+        let (header, ciphertext) = blob.split_at(2 + algorithm.len());
+
+        let mut plaintext = Aes128Siv::new(state_key)
+            .open(STATE_NONCE, header, ciphertext)
+            .or(Err(Error))?;
+
+        let result = Self::decode(&plaintext);
+        plaintext.as_mut_slice().clear();
+        Ok((algorithm, result?))
+    }
+
+    /// Obtain the algorithm identifier from the header of a state blob,
+    /// without authenticating it
+    pub fn algorithm(blob: &[u8]) -> Result<&str, Error> {
+        if blob.len() < 2 || blob[0] != STATE_VERSION || blob.len() < 2 + blob[1] as usize {
+            return Err(Error);
+        }
+
+        ::std::str::from_utf8(&blob[2..2 + blob[1] as usize]).or(Err(Error))
+    }
+
+    /// Decode the plaintext of a state blob
+    fn decode(plaintext: &[u8]) -> Result<Self, Error> {
+        if plaintext.len() < REKEY_STATE_SIZE + TOTALS_SIZE + 14 {
+            return Err(Error);
+        }
+
+        let (rekey_state, plaintext) = plaintext.split_at(REKEY_STATE_SIZE + TOTALS_SIZE);
+        let read_u64 = |offset: usize| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&rekey_state[offset..offset + 8]);
+            u64::from_be_bytes(bytes)
+        };
+
+        let rekey = match rekey_state[0] {
+            0 => None,
+            1 if read_u64(1) <= u64::from(u32::MAX) => Some(Rekey::Segments(read_u64(1) as u32)),
+            2 => Some(Rekey::Bytes(read_u64(1))),
+            _ => return Err(Error),
+        };
+
+        if let Some(ref rekey) = rekey {
+            rekey.validate()?;
+        }
+
+        let mut counter = [0u8; 4];
+        counter.copy_from_slice(&plaintext[..4]);
+
+        let mut generation = [0u8; 8];
+        generation.copy_from_slice(&plaintext[4..12]);
+
+        let key_len = (plaintext[12] as usize) << 8 | plaintext[13] as usize;
+
+        if plaintext.len() < 14 + key_len {
+            return Err(Error);
+        }
+
+        Ok(Self {
+            counter: u32::from_be_bytes(counter),
+            generation: u64::from_be_bytes(generation),
+            epoch: read_u64(9),
+            epoch_bytes: read_u64(17),
+            rekey,
+            totals: Totals {
+                segments: read_u64(REKEY_STATE_SIZE),
+                bytes: read_u64(REKEY_STATE_SIZE + 8),
+            },
+            key: Vec::from(&plaintext[14..14 + key_len]),
+            exporter_secret: Vec::from(&plaintext[14 + key_len..]),
+        })
+    }
+}
+
+impl Drop for State {
+    fn drop(&mut self) {
+        self.key.as_mut_slice().clear();
+        self.exporter_secret.as_mut_slice().clear();
+    }
+}
@@ -0,0 +1,163 @@
+//! `stream.rs`: the STREAM online authenticated encryption construction,
+//! implemented directly over `miscreant::aead::Algorithm` so segments can be
+//! addressed by index rather than only sequentially.
+//!
+//! Nonces are encoded identically to `miscreant::stream`: an 8-byte prefix,
+//! a 32-bit big endian segment counter, and a 1-byte last segment flag.
+//! `XSTREAM` derives a unique key for every STREAM, so the prefix is always
+//! all zeroes.
+
+use clear_on_drop::clear::Clear;
+use error::Error;
+use miscreant::aead;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Size of the nonce prefix in bytes
+const NONCE_PREFIX_SIZE: usize = 8;
+
+/// Size of a complete STREAM nonce in bytes
+const NONCE_SIZE: usize = NONCE_PREFIX_SIZE + 4 + 1;
+
+/// Size of the authentication tag of every supported AEAD algorithm in bytes
+#[cfg(feature = "parallel")]
+pub(crate) const TAG_SIZE: usize = 16;
+
+/// STREAM keyed with a particular AEAD algorithm instance. The key is
+/// retained so additional instances of the algorithm can be created, e.g. one
+/// per thread when processing segments in parallel.
+pub(crate) struct Stream<A: aead::Algorithm> {
+    alg: A,
+    key: Vec<u8>,
+}
+
+impl<A: aead::Algorithm> Stream<A> {
+    /// Create a new STREAM using the given key
+    pub fn new(key: &[u8]) -> Self {
+        Self {
+            alg: A::new(key),
+            key: Vec::from(key),
+        }
+    }
+
+    /// Key this STREAM was created with
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// Encrypt the segment with the given index in-place
+    pub fn seal_in_place(&mut self, index: u32, last: bool, ad: &[u8], buffer: &mut [u8]) {
+        self.alg.seal_in_place(&nonce(index, last), ad, buffer)
+    }
+
+    /// Decrypt the segment with the given index in-place
+    pub fn open_in_place<'a>(
+        &mut self,
+        index: u32,
+        last: bool,
+        ad: &[u8],
+        buffer: &'a mut [u8],
+    ) -> Result<&'a [u8], Error> {
+        self.alg
+            .open_in_place(&nonce(index, last), ad, buffer)
+            .or(Err(Error))
+    }
+
+    /// Encrypt the segment with the given index, allocating and returning a
+    /// `Vec<u8>` for the ciphertext
+    pub fn seal(&mut self, index: u32, last: bool, ad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        self.alg.seal(&nonce(index, last), ad, plaintext)
+    }
+
+    /// Decrypt the segment with the given index, allocating and returning a
+    /// `Vec<u8>` for the plaintext
+    pub fn open(
+        &mut self,
+        index: u32,
+        last: bool,
+        ad: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        self.alg
+            .open(&nonce(index, last), ad, ciphertext)
+            .or(Err(Error))
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<A: aead::Algorithm> Stream<A> {
+    /// Encrypt consecutive segments in parallel, starting at the given index.
+    /// If `last` is set, the final segment is sealed as the last one.
+    ///
+    /// The caller is responsible for ensuring the indexes don't overflow.
+    pub fn seal_par<S>(
+        &self,
+        first_index: u32,
+        last: bool,
+        ad: &[u8],
+        segments: &[S],
+    ) -> Vec<Vec<u8>>
+    where
+        S: AsRef<[u8]> + Sync,
+    {
+        let key = &self.key;
+        let last_offset = segments.len().wrapping_sub(1);
+
+        segments
+            .par_iter()
+            .enumerate()
+            .map_init(
+                || A::new(key),
+                |alg, (offset, segment)| {
+                    let nonce = nonce(first_index + offset as u32, last && offset == last_offset);
+                    alg.seal(&nonce, ad, segment.as_ref())
+                },
+            )
+            .collect()
+    }
+
+    /// Decrypt consecutive segments in parallel, starting at the given index.
+    /// If `last` is set, the final segment must be the last one.
+    ///
+    /// The caller is responsible for ensuring the indexes don't overflow.
+    pub fn open_par<C>(
+        &self,
+        first_index: u32,
+        last: bool,
+        ad: &[u8],
+        ciphertexts: &[C],
+    ) -> Result<Vec<Vec<u8>>, Error>
+    where
+        C: AsRef<[u8]> + Sync,
+    {
+        let key = &self.key;
+        let last_offset = ciphertexts.len().wrapping_sub(1);
+
+        ciphertexts
+            .par_iter()
+            .enumerate()
+            .map_init(
+                || A::new(key),
+                |alg, (offset, ciphertext)| {
+                    let nonce = nonce(first_index + offset as u32, last && offset == last_offset);
+                    alg.open(&nonce, ad, ciphertext.as_ref()).or(Err(Error))
+                },
+            )
+            .collect()
+    }
+}
+
+/// Ensure the key is cleared from memory on drop
+impl<A: aead::Algorithm> Drop for Stream<A> {
+    fn drop(&mut self) {
+        self.key.as_mut_slice().clear();
+    }
+}
+
+/// Compute the STREAM nonce for the segment with the given index
+fn nonce(index: u32, last: bool) -> [u8; NONCE_SIZE] {
+    let mut nonce = [0u8; NONCE_SIZE];
+    nonce[NONCE_PREFIX_SIZE..NONCE_PREFIX_SIZE + 4].copy_from_slice(&index.to_be_bytes());
+    nonce[NONCE_SIZE - 1] = last as u8;
+    nonce
+}
@@ -24,11 +24,13 @@ pub trait Encryptor {
     /// as Encryptor objects will generate a random ECDH keypair as part of
     /// the encryption process, ensuring all message streams are encrypted
     /// under a random key.
+    ///
+    /// Returns an error if the public key is invalid (e.g. a low-order point)
     fn new<T: Rng>(
         csprng: &mut T,
         public_key: &Self::PublicKey,
         salt: Option<&[u8]>,
-    ) -> (Self, Self::PublicKey)
+    ) -> Result<(Self, Self::PublicKey), Error>
     where
         Self: Sized;
 
@@ -40,8 +42,19 @@ pub trait Encryptor {
 
     /// Encrypt the next message in the stream, allocating and returning a
     /// `Vec<u8>` for the ciphertext
+    ///
+    /// Panics if the stream can't hold any more segments before the last
+    /// one. See `try_seal_next`.
     fn seal_next(&mut self, ad: &[u8], plaintext: &[u8]) -> Vec<u8>;
 
+    /// Encrypt the next message in the stream, allocating and returning a
+    /// `Vec<u8>` for the ciphertext.
+    ///
+    /// Returns an error, leaving the encryptor unchanged, if the stream
+    /// can't hold any more segments before the last one (i.e. the nonce
+    /// counter is exhausted). The last segment can still be sealed.
+    fn try_seal_next(&mut self, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error>;
+
     /// Encrypt the final message in the stream, allocating and returning a
     /// `Vec<u8>` for the ciphertext
     fn seal_last(self, ad: &[u8], plaintext: &[u8]) -> Vec<u8>;
@@ -69,11 +82,16 @@ pub trait Decryptor {
     /// input to the ECIES key derivation function. If provided, it MUST be
     /// the same as the salt value used to seal the messages originally,
     /// or decryption will fail.
+    ///
+    /// Returns an error if the ephemeral key is invalid (e.g. a low-order
+    /// point)
     fn new(
         private_key: &Self::PrivateKey,
         ephemeral_key: &Self::PublicKey,
         salt: Option<&[u8]>,
-    ) -> Self;
+    ) -> Result<Self, Error>
+    where
+        Self: Sized;
 
     /// Decrypt the next message in the stream in-place
     fn open_next_in_place<'a>(
@@ -93,3 +111,16 @@ pub trait Decryptor {
     /// `Vec<u8>` for the plaintext
     fn open_last(self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error>;
 }
+
+/// Derives additional secrets bound to the key exchange of an `XSTREAM`,
+/// e.g. a MAC key for a manifest or a channel binding token. Encryptors and
+/// decryptors for the same stream export identical values.
+pub trait Exporter {
+    /// Derive a secret of the given length in bytes. Different labels (and
+    /// contexts) yield independent secrets, none of which can collide with
+    /// the STREAM key.
+    ///
+    /// Returns an error if the label is longer than 65535 bytes, or the
+    /// length exceeds the maximum output of the KDF.
+    fn export(&self, label: &[u8], context: &[u8], length: usize) -> Result<Vec<u8>, Error>;
+}
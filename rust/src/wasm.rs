@@ -0,0 +1,181 @@
+//! `wasm.rs`: JavaScript bindings for `XSTREAM` via `wasm-bindgen`, enabled
+//! with the `wasm` feature.
+//!
+//! Exposes `keygen` and `publicKey` along with `StreamEncryptor` and
+//! `StreamDecryptor` classes wrapping `X25519HkdfSha256Encryptor` and
+//! `X25519HkdfSha256Decryptor`, for the algorithm with the given `XSTREAM_*`
+//! identifier. Randomness comes from `crypto.getRandomValues` in browsers
+//! and `crypto.randomFillSync` in Node.
+
+// Code generated by `#[wasm_bindgen]` contains `unsafe` blocks
+#![allow(unsafe_code)]
+
+use algorithm::Algorithm;
+use dynamic::{DynDecryptor, DynEncryptor};
+use getrandom;
+use keys::{PrivateKey, PublicKey, KEY_SIZE};
+use rand::Rng;
+use wasm_bindgen::prelude::*;
+
+/// Generate a random private key, returning its bytes
+#[wasm_bindgen]
+pub fn keygen() -> Vec<u8> {
+    PrivateKey::generate(&mut JsRng).as_bytes().to_vec()
+}
+
+/// Compute the public key for a private key
+#[wasm_bindgen(js_name = publicKey)]
+pub fn public_key(private_key: &[u8]) -> Result<Vec<u8>, JsError> {
+    Ok(parse_private_key(private_key)?.public_key().as_bytes().to_vec())
+}
+
+/// `XSTREAM` encryptor exported to JavaScript as `StreamEncryptor`
+#[wasm_bindgen(js_name = StreamEncryptor)]
+pub struct WasmEncryptor {
+    /// The underlying encryptor, or `None` once the last segment is sealed
+    inner: Option<DynEncryptor>,
+
+    /// Ephemeral public key the recipient needs to open the stream
+    ephemeral_key: PublicKey,
+}
+
+#[wasm_bindgen(js_class = StreamEncryptor)]
+impl WasmEncryptor {
+    /// Create an encryptor for the algorithm with the given `XSTREAM_*`
+    /// identifier which seals a stream under the given public key
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        algorithm: &str,
+        public_key: &[u8],
+        salt: Option<Vec<u8>>,
+    ) -> Result<WasmEncryptor, JsError> {
+        let algorithm = parse_algorithm(algorithm)?;
+        let public_key = parse_public_key(public_key)?;
+        let (inner, ephemeral_key) =
+            DynEncryptor::new(algorithm, &mut JsRng, &public_key, salt.as_deref())
+                .map_err(|_| JsError::new("invalid public key"))?;
+
+        Ok(Self {
+            inner: Some(inner),
+            ephemeral_key,
+        })
+    }
+
+    /// Ephemeral public key to send along with the stream
+    #[wasm_bindgen(getter, js_name = ephemeralKey)]
+    pub fn ephemeral_key(&self) -> Vec<u8> {
+        self.ephemeral_key.as_bytes().to_vec()
+    }
+
+    /// Encrypt the next message in the stream
+    #[wasm_bindgen(js_name = sealNext)]
+    pub fn seal_next(&mut self, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, JsError> {
+        self.inner
+            .as_mut()
+            .ok_or_else(finished)?
+            .try_seal_next(ad, plaintext)
+            .map_err(|_| JsError::new("stream counter exhausted"))
+    }
+
+    /// Encrypt the final message in the stream. Nothing else can be sealed
+    /// afterwards.
+    #[wasm_bindgen(js_name = sealLast)]
+    pub fn seal_last(&mut self, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, JsError> {
+        Ok(self.inner.take().ok_or_else(finished)?.seal_last(ad, plaintext))
+    }
+}
+
+/// `XSTREAM` decryptor exported to JavaScript as `StreamDecryptor`
+#[wasm_bindgen(js_name = StreamDecryptor)]
+pub struct WasmDecryptor {
+    /// The underlying decryptor, or `None` once the last segment is opened
+    inner: Option<DynDecryptor>,
+}
+
+#[wasm_bindgen(js_class = StreamDecryptor)]
+impl WasmDecryptor {
+    /// Create a decryptor for the algorithm with the given `XSTREAM_*`
+    /// identifier which opens a stream sealed to the given private key
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        algorithm: &str,
+        private_key: &[u8],
+        ephemeral_key: &[u8],
+        salt: Option<Vec<u8>>,
+    ) -> Result<WasmDecryptor, JsError> {
+        let algorithm = parse_algorithm(algorithm)?;
+        let private_key = parse_private_key(private_key)?;
+        let ephemeral_key = parse_public_key(ephemeral_key)?;
+        let inner = DynDecryptor::new(algorithm, &private_key, &ephemeral_key, salt.as_deref())
+            .map_err(|_| JsError::new("invalid ephemeral key"))?;
+
+        Ok(Self { inner: Some(inner) })
+    }
+
+    /// Decrypt the next message in the stream
+    #[wasm_bindgen(js_name = openNext)]
+    pub fn open_next(&mut self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, JsError> {
+        self.inner
+            .as_mut()
+            .ok_or_else(finished)?
+            .open_next(ad, ciphertext)
+            .map_err(|_| authentication_failed())
+    }
+
+    /// Decrypt the final message in the stream. Nothing else can be opened
+    /// afterwards, even if it fails to authenticate.
+    #[wasm_bindgen(js_name = openLast)]
+    pub fn open_last(&mut self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, JsError> {
+        self.inner
+            .take()
+            .ok_or_else(finished)?
+            .open_last(ad, ciphertext)
+            .map_err(|_| authentication_failed())
+    }
+}
+
+/// Random number generator backed by the JavaScript environment's CSPRNG
+struct JsRng;
+
+impl Rng for JsRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, bytes: &mut [u8]) {
+        getrandom::getrandom(bytes).expect("no CSPRNG available");
+    }
+}
+
+/// Parse an `XSTREAM_*` algorithm identifier
+fn parse_algorithm(algorithm: &str) -> Result<Algorithm, JsError> {
+    algorithm
+        .parse()
+        .map_err(|_| JsError::new("unknown algorithm"))
+}
+
+/// Parse a private key, which must be `KEY_SIZE` bytes
+fn parse_private_key(bytes: &[u8]) -> Result<PrivateKey, JsError> {
+    if bytes.len() != KEY_SIZE {
+        return Err(JsError::new("private key must be 32 bytes"));
+    }
+
+    Ok(PrivateKey::new(bytes))
+}
+
+/// Parse a public key, which must be `KEY_SIZE` bytes
+fn parse_public_key(bytes: &[u8]) -> Result<PublicKey, JsError> {
+    PublicKey::from_slice(bytes).map_err(|_| JsError::new("public key must be 32 bytes"))
+}
+
+/// Error for a stream whose last message was already sealed or opened
+fn finished() -> JsError {
+    JsError::new("stream already finished")
+}
+
+/// Error for a message which failed to authenticate
+fn authentication_failed() -> JsError {
+    JsError::new("authentication failed")
+}
@@ -4,108 +4,341 @@
 //! Can be used with any AEAD algorithm which implements the
 //! `miscreant::aead::Algorithm` trait.
 
-use super::{Encryptor, Decryptor};
+use super::{Encryptor, Decryptor, Exporter};
 use clear_on_drop::clear::Clear;
 use digest::Digest;
 use error::Error;
 use generic_array::GenericArray;
 use generic_array::typenum::Unsigned;
 use hkdf::Hkdf;
-use keys::{self, KEY_SIZE};
+use keys::{self, KEY_SIZE, shared_secret};
 use miscreant::aead;
-use miscreant::stream::{self, NONCE_SIZE};
 use rand::Rng;
+use ratchet::{Ratchet, Rekey};
 use sha2::Sha256;
-use std::marker::PhantomData;
-use x25519_dalek::{generate_secret, generate_public, diffie_hellman};
+use state::{STATE_KEY_SIZE, State};
+#[cfg(feature = "parallel")]
+use stream::TAG_SIZE;
+use x25519_dalek::{generate_secret, generate_public};
 
 /// Domain separation string passed as HKDF info
 const HKDF_INFO: &[u8] = b"XSTREAM_X25519_HKDF";
 
-/// Use a prefix of all zeroes for the STREAM nonce prefix, since we derive a
-/// unique key for every STREAM. The STREAM construction handles producing a
-/// unique nonce per message segment.
-const NONCE_PREFIX: &[u8; NONCE_SIZE] = &[0u8; NONCE_SIZE];
+/// Panic message for sealing beyond the end of the STREAM counter
+const COUNTER_EXHAUSTED: &str = "STREAM nonce counter exhausted";
+
+/// HKDF info used to derive the exporter secret. Exported values are derived
+/// from this secret rather than the shared secret, so they are independent of
+/// the STREAM key regardless of the label used. Neither this nor `HKDF_INFO`
+/// is a prefix of the other, so the info strings for the two never coincide,
+/// whatever context is appended.
+const HKDF_EXPORTER_INFO: &[u8] = b"XSTREAM_EXPORTER_X25519_HKDF";
 
 /// Elliptic Curve Integrated Encryption Scheme (ECIES) encryptor object based
 /// on the X25519 Diffie-Hellman function, HKDF, and generic over any AEAD
 /// algorithm supported by Miscreant. Uses the STREAM construction to support
 /// incremental encryption.
 pub struct X25519HkdfEncryptor<A: aead::Algorithm, D: Digest> {
-    stream: stream::Encryptor<A>,
-    digest: PhantomData<D>,
+    ratchet: Ratchet<A, D>,
+    exporter_secret: ExporterSecret,
+
+    /// Generation of the checkpoint this encryptor was resumed from, or 0
+    generation: u64,
 }
 
 /// A `XSTREAM` encryptor using X25519 and HKDF-SHA-256. This is the
 /// recommended set of algorithms to use with `XSTREAM`.
 pub type X25519HkdfSha256Encryptor<A> = X25519HkdfEncryptor<A, Sha256>;
 
-impl<A, D> Encryptor for X25519HkdfEncryptor<A, D>
+impl<A, D> X25519HkdfEncryptor<A, D>
 where
     A: aead::Algorithm,
     D: Digest,
 {
-    type PublicKey = keys::PublicKey;
-
     /// Create a new Encryptor object which seals a stream of messages under
-    /// an X25519 public key.
-    fn new<R: Rng>(
+    /// an X25519 public key, binding the derived key to the given application
+    /// context (e.g. a file path, tenant ID, or protocol version).
+    ///
+    /// Messages can only be decrypted by a `Decryptor` created with the same
+    /// context. An empty context is equivalent to `Encryptor::new`.
+    pub fn new_with_context<R: Rng>(
         csprng: &mut R,
-        public_key: &Self::PublicKey,
+        public_key: &keys::PublicKey,
         salt: Option<&[u8]>,
-    ) -> (Self, Self::PublicKey) {
+        context: &[u8],
+    ) -> Result<(Self, keys::PublicKey), Error> {
         // Create an ephemeral X25519 key
         let mut ephemeral_scalar = generate_secret(csprng);
         let ephemeral_public = generate_public(&ephemeral_scalar);
 
         // Perform an X25519 elliptic curve Diffie-Hellman operation and use
         // the resulting shared secret to derive a symmetric key (using HKDF)
-        let mut symmetric_key = derive_key::<D>(
+        let result = derive_keys::<D>(
             &ephemeral_scalar,
             public_key.as_ref(),
             salt,
+            context,
             A::KeySize::to_usize(),
         );
 
         // Erase the ephemeral private key/scalar now that we've performed the
         // Diffie-Hellman op
         ephemeral_scalar.clear();
+        let (mut symmetric_key, exporter_secret) = result?;
 
         // Create a new STREAM encryptor object using the derived key.
-        let stream = stream::Encryptor::new(&symmetric_key, NONCE_PREFIX);
+        let ratchet = Ratchet::new(&symmetric_key);
         symmetric_key.clear();
 
         let encryptor = Self {
-            stream: stream,
-            digest: PhantomData,
+            ratchet,
+            exporter_secret,
+            generation: 0,
         };
 
-        (
+        Ok((
             encryptor,
-            Self::PublicKey::from(ephemeral_public.to_bytes()),
-        )
+            keys::PublicKey::from(ephemeral_public.to_bytes()),
+        ))
+    }
+
+    /// Periodically replace the STREAM key according to the given rekeying
+    /// schedule. See `Rekey`. The decryptor must use the same schedule.
+    ///
+    /// Returns an error if the schedule is invalid, or segments have already
+    /// been sealed.
+    pub fn set_rekey(&mut self, rekey: Rekey) -> Result<(), Error> {
+        self.ratchet.set_rekey(rekey)
+    }
+
+    /// Number of segments sealed so far, not counting the last one
+    pub fn segments_sealed(&self) -> u64 {
+        self.ratchet.totals().segments
+    }
+
+    /// Number of bytes of ciphertext sealed so far, not counting the last
+    /// segment
+    pub fn bytes_sealed(&self) -> u64 {
+        self.ratchet.totals().bytes
+    }
+
+    /// Number of further segments which can be sealed with `seal_next`
+    /// before the STREAM counter is exhausted, after which only the last
+    /// segment can be sealed. Returns `None` if rekeying by segments, which
+    /// resets the counter before it can be exhausted.
+    pub fn remaining_segments(&self) -> Option<u64> {
+        self.ratchet.remaining_segments()
+    }
+
+    /// Checkpoint this encryptor, serializing its key and the index of the
+    /// next segment into a blob encrypted and authenticated under the given
+    /// state key. The encryptor is consumed, so no segments can be sealed
+    /// under the checkpointed counter value except by resuming the blob.
+    ///
+    /// The blob's generation is one higher than that of the blob this
+    /// encryptor was resumed from (or 1), which `resume` uses to ensure each
+    /// blob is resumed at most once.
+    pub fn checkpoint(self, state_key: &[u8; STATE_KEY_SIZE]) -> Vec<u8> {
+        self.checkpoint_tagged(state_key, "")
+    }
+
+    /// Resume an encryptor from a blob produced by `checkpoint`, which seals
+    /// the next segment under the checkpointed counter value.
+    ///
+    /// `generation` is the generation of the last blob of this stream which
+    /// was resumed (initially 0), and must be kept in durable storage by the
+    /// caller. The blob is only resumed if its generation is higher, in
+    /// which case `generation` is updated to it. Persist the updated value
+    /// before sealing anything with the resumed encryptor: a blob which is
+    /// resumed twice would seal different segments under the same counter
+    /// values. If the encryptor is lost before it is checkpointed again, the
+    /// stream can't be resumed and must be restarted.
+    ///
+    /// Returns an error if the blob is not authentic under the given state
+    /// key, was produced for a different algorithm, or has already been
+    /// resumed (or superseded) according to `generation`.
+    pub fn resume(
+        state_key: &[u8; STATE_KEY_SIZE],
+        blob: &[u8],
+        generation: &mut u64,
+    ) -> Result<Self, Error> {
+        Self::resume_tagged(state_key, blob, "", generation)
+    }
+
+    /// Checkpoint this encryptor, tagging the blob with an algorithm
+    /// identifier
+    pub(crate) fn checkpoint_tagged(
+        self,
+        state_key: &[u8; STATE_KEY_SIZE],
+        algorithm: &str,
+    ) -> Vec<u8> {
+        let state = State {
+            counter: self.ratchet.counter(),
+            generation: self.generation.checked_add(1).expect("checkpoint generation overflowed"),
+            epoch: self.ratchet.epoch(),
+            epoch_bytes: self.ratchet.epoch_bytes(),
+            rekey: self.ratchet.rekey(),
+            totals: self.ratchet.totals(),
+            key: Vec::from(self.ratchet.key()),
+            exporter_secret: self.exporter_secret.0.clone(),
+        };
+
+        state.seal(state_key, algorithm)
+    }
+
+    /// Resume an encryptor from a blob tagged with the given algorithm
+    /// identifier
+    pub(crate) fn resume_tagged(
+        state_key: &[u8; STATE_KEY_SIZE],
+        blob: &[u8],
+        algorithm: &str,
+        generation: &mut u64,
+    ) -> Result<Self, Error> {
+        let (blob_algorithm, state) = State::open(state_key, blob)?;
+
+        if blob_algorithm != algorithm || state.key.len() != A::KeySize::to_usize() ||
+            state.exporter_secret.len() != D::OutputSize::to_usize() ||
+            state.generation <= *generation
+        {
+            return Err(Error);
+        }
+
+        *generation = state.generation;
+
+        let ratchet = Ratchet::from_parts(
+            &state.key,
+            state.counter,
+            state.epoch,
+            state.epoch_bytes,
+            state.rekey,
+            state.totals,
+        );
+
+        Ok(Self {
+            ratchet,
+            exporter_secret: ExporterSecret(state.exporter_secret.clone()),
+            generation: state.generation,
+        })
+    }
+}
+
+impl<A, D> Encryptor for X25519HkdfEncryptor<A, D>
+where
+    A: aead::Algorithm,
+    D: Digest,
+{
+    type PublicKey = keys::PublicKey;
+
+    /// Create a new Encryptor object which seals a stream of messages under
+    /// an X25519 public key.
+    fn new<R: Rng>(
+        csprng: &mut R,
+        public_key: &Self::PublicKey,
+        salt: Option<&[u8]>,
+    ) -> Result<(Self, Self::PublicKey), Error> {
+        Self::new_with_context(csprng, public_key, salt, b"")
     }
 
     /// Encrypt the next message in the stream in-place
     fn seal_next_in_place(&mut self, ad: &[u8], buffer: &mut [u8]) {
-        self.stream.seal_next_in_place(ad, buffer);
+        self.ratchet.check_next().expect(COUNTER_EXHAUSTED);
+        let index = self.ratchet.counter();
+        self.ratchet.stream().seal_in_place(index, false, ad, buffer);
+        self.ratchet.advance(buffer.len());
     }
 
     /// Encrypt the final message in-place, consuming the stream encryptor
-    fn seal_last_in_place(self, ad: &[u8], buffer: &mut [u8]) {
-        self.stream.seal_last_in_place(ad, buffer);
+    fn seal_last_in_place(mut self, ad: &[u8], buffer: &mut [u8]) {
+        let index = self.ratchet.counter();
+        self.ratchet.stream().seal_in_place(index, true, ad, buffer);
     }
 
     /// Encrypt the next message in the stream, allocating and returning a
     /// `Vec<u8>` for the ciphertext
     fn seal_next(&mut self, ad: &[u8], plaintext: &[u8]) -> Vec<u8> {
-        self.stream.seal_next(ad, plaintext)
+        self.try_seal_next(ad, plaintext).expect(COUNTER_EXHAUSTED)
+    }
+
+    /// Encrypt the next message in the stream, or return an error if the
+    /// STREAM counter is exhausted
+    fn try_seal_next(&mut self, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        self.ratchet.check_next()?;
+        let index = self.ratchet.counter();
+        let ciphertext = self.ratchet.stream().seal(index, false, ad, plaintext);
+        self.ratchet.advance(ciphertext.len());
+        Ok(ciphertext)
     }
 
     /// Encrypt the final message in the stream, allocating and returning a
     /// `Vec<u8>` for the ciphertext
-    fn seal_last(self, ad: &[u8], plaintext: &[u8]) -> Vec<u8> {
-        self.stream.seal_last(ad, plaintext)
+    fn seal_last(mut self, ad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let index = self.ratchet.counter();
+        self.ratchet.stream().seal(index, true, ad, plaintext)
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<A, D> X25519HkdfEncryptor<A, D>
+where
+    A: aead::Algorithm,
+    D: Digest,
+{
+    /// Encrypt the next segments in the stream in parallel using `rayon`,
+    /// with the same associated data for each. The output is identical to
+    /// calling `seal_next` on each segment in turn.
+    pub fn seal_next_par<S>(&mut self, ad: &[u8], segments: &[S]) -> Vec<Vec<u8>>
+    where
+        S: AsRef<[u8]> + Sync,
+    {
+        let mut ciphertexts = Vec::with_capacity(segments.len());
+        let mut remaining = segments;
+
+        // Segments are sealed in parallel in batches which share a key
+        while !remaining.is_empty() {
+            let count = self.ratchet.epoch_segments(
+                remaining.iter().map(|segment| segment.as_ref().len() + TAG_SIZE),
+            );
+            let (batch, rest) = remaining.split_at(count);
+
+            let first_index = self.ratchet.counter();
+            check_counter(first_index, batch.len()).expect("STREAM nonce counter overflowed");
+
+            for ciphertext in self.ratchet.stream().seal_par(first_index, false, ad, batch) {
+                self.ratchet.advance(ciphertext.len());
+                ciphertexts.push(ciphertext);
+            }
+
+            remaining = rest;
+        }
+
+        ciphertexts
+    }
+
+    /// Encrypt the remaining segments in the stream in parallel using
+    /// `rayon`, sealing the final one as the last segment. The output is
+    /// identical to calling `seal_next` on each segment followed by
+    /// `seal_last` on the final one.
+    ///
+    /// Panics if no segments are given.
+    pub fn seal_last_par<S>(mut self, ad: &[u8], segments: &[S]) -> Vec<Vec<u8>>
+    where
+        S: AsRef<[u8]> + Sync,
+    {
+        let (last, segments) = segments.split_last().expect("no segments to seal");
+        let mut ciphertexts = self.seal_next_par(ad, segments);
+        ciphertexts.push(self.seal_last(ad, last.as_ref()));
+        ciphertexts
+    }
+}
+
+impl<A, D> Exporter for X25519HkdfEncryptor<A, D>
+where
+    A: aead::Algorithm,
+    D: Digest,
+{
+    /// Derive a secret bound to this stream's key exchange
+    fn export(&self, label: &[u8], context: &[u8], length: usize) -> Result<Vec<u8>, Error> {
+        self.exporter_secret.export::<D>(label, context, length)
     }
 }
 
@@ -114,47 +347,104 @@ where
 /// algorithm supported by Miscreant. Uses the STREAM construction to support
 /// incremental decryption.
 pub struct X25519HkdfDecryptor<A: aead::Algorithm, D: Digest> {
-    stream: stream::Decryptor<A>,
-    digest: PhantomData<D>,
+    ratchet: Ratchet<A, D>,
+    exporter_secret: ExporterSecret,
 }
 
 /// A `XSTREAM` decryptor using X25519 and HKDF-SHA-256. This is the
 /// recommended set of algorithms to use with `XSTREAM`.
 pub type X25519HkdfSha256Decryptor<A> = X25519HkdfDecryptor<A, Sha256>;
 
-impl<A, D> Decryptor for X25519HkdfDecryptor<A, D>
+impl<A, D> X25519HkdfDecryptor<A, D>
 where
     A: aead::Algorithm,
     D: Digest,
 {
-    type PrivateKey = keys::PrivateKey;
-    type PublicKey = keys::PublicKey;
-
     /// Create a new Decryptor object which unseals a stream of messages
-    /// which were previously encrypted using the public key that cooresponds
-    /// to the given private key.
-    fn new(
-        private_key: &Self::PrivateKey,
-        ephemeral_key: &Self::PublicKey,
+    /// which were encrypted by an `Encryptor` created with the same
+    /// application context. See `X25519HkdfEncryptor::new_with_context`.
+    pub fn new_with_context(
+        private_key: &keys::PrivateKey,
+        ephemeral_key: &keys::PublicKey,
         salt: Option<&[u8]>,
-    ) -> Self {
+        context: &[u8],
+    ) -> Result<Self, Error> {
         // Perform an X25519 elliptic curve Diffie-Hellman operation and use
         // the resulting shared secret to derive a symmetric key (using HKDF)
-        let mut symmetric_key = derive_key::<D>(
+        let (mut symmetric_key, exporter_secret) = derive_keys::<D>(
             &private_key.0,
             ephemeral_key.as_ref(),
             salt,
+            context,
             A::KeySize::to_usize(),
-        );
+        )?;
 
         // Create a new STREAM decryptor object using the derived key.
-        let stream = stream::Decryptor::new(&symmetric_key, NONCE_PREFIX);
+        let ratchet = Ratchet::new(&symmetric_key);
         symmetric_key.clear();
 
-        Self {
-            stream: stream,
-            digest: PhantomData,
-        }
+        Ok(Self {
+            ratchet,
+            exporter_secret,
+        })
+    }
+
+    /// Follow the given rekeying schedule, which must match the one used by
+    /// the encryptor. See `X25519HkdfEncryptor::set_rekey`.
+    ///
+    /// Returns an error if the schedule is invalid, or segments have already
+    /// been opened.
+    pub fn set_rekey(&mut self, rekey: Rekey) -> Result<(), Error> {
+        self.ratchet.set_rekey(rekey)
+    }
+
+    /// Decrypt the segment with the given index in-place, without decrypting
+    /// any of the segments which precede it. `last` must be set if and only
+    /// if this is the final segment of the stream.
+    ///
+    /// Does not affect the position of `open_next`. When rekeying, only
+    /// segments sealed under the current key or a later one can be opened,
+    /// and only if rekeying by segments.
+    pub fn open_at_in_place<'a>(
+        &mut self,
+        index: u32,
+        last: bool,
+        ad: &[u8],
+        buffer: &'a mut [u8],
+    ) -> Result<&'a [u8], Error> {
+        self.ratchet.open_at_in_place(index, last, ad, buffer)
+    }
+
+    /// Decrypt the segment with the given index, allocating and returning a
+    /// `Vec<u8>` for the plaintext. See `open_at_in_place`.
+    pub fn open_at(
+        &mut self,
+        index: u32,
+        last: bool,
+        ad: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        self.ratchet.open_at(index, last, ad, ciphertext)
+    }
+}
+
+impl<A, D> Decryptor for X25519HkdfDecryptor<A, D>
+where
+    A: aead::Algorithm,
+    D: Digest,
+{
+    type PrivateKey = keys::PrivateKey;
+    type PublicKey = keys::PublicKey;
+
+    /// Create a new Decryptor object which unseals a stream of messages
+    /// which were previously encrypted using the public key that cooresponds
+    /// to the given private key.
+    fn new(
+        private_key: &Self::PrivateKey,
+        ephemeral_key: &Self::PublicKey,
+        salt: Option<&[u8]>,
+    ) -> Result<Self, Error> {
+        Self::new_with_context(private_key, ephemeral_key, salt, b"")
     }
 
     /// Decrypt the next message in the stream in-place
@@ -163,37 +453,191 @@ where
         ad: &[u8],
         buffer: &'a mut [u8],
     ) -> Result<&'a [u8], Error> {
-        self.stream.open_next_in_place(ad, buffer).or(Err(Error))
+        self.ratchet.check_next()?;
+        let ciphertext_len = buffer.len();
+        let index = self.ratchet.counter();
+        let plaintext = self.ratchet.stream().open_in_place(index, false, ad, buffer)?;
+        self.ratchet.advance(ciphertext_len);
+        Ok(plaintext)
     }
 
     /// Decrypt the final message in-place, consuming the stream decryptor
-    fn open_last_in_place<'a>(self, ad: &[u8], buffer: &'a mut [u8]) -> Result<&'a [u8], Error> {
-        self.stream.open_last_in_place(ad, buffer).or(Err(Error))
+    fn open_last_in_place<'a>(
+        mut self,
+        ad: &[u8],
+        buffer: &'a mut [u8],
+    ) -> Result<&'a [u8], Error> {
+        let index = self.ratchet.counter();
+        self.ratchet.stream().open_in_place(index, true, ad, buffer)
     }
 
     /// Decrypt the next message in the stream, allocating and returning a
     /// `Vec<u8>` for the plaintext
     fn open_next(&mut self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
-        self.stream.open_next(ad, ciphertext).or(Err(Error))
+        self.ratchet.check_next()?;
+        let index = self.ratchet.counter();
+        let plaintext = self.ratchet.stream().open(index, false, ad, ciphertext)?;
+        self.ratchet.advance(ciphertext.len());
+        Ok(plaintext)
     }
 
     /// Decrypt the next message in the stream, allocating and returning a
     /// `Vec<u8>` for the plaintext
-    fn open_last(self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
-        self.stream.open_last(ad, ciphertext).or(Err(Error))
+    fn open_last(mut self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        let index = self.ratchet.counter();
+        self.ratchet.stream().open(index, true, ad, ciphertext)
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<A, D> X25519HkdfDecryptor<A, D>
+where
+    A: aead::Algorithm,
+    D: Digest,
+{
+    /// Decrypt the next segments in the stream in parallel using `rayon`.
+    /// The stream only advances if every segment is authentic.
+    ///
+    /// Returns an error if any segment is not authentic, or there are more
+    /// segments than the STREAM counter can hold.
+    pub fn open_next_par<C>(
+        &mut self,
+        ad: &[u8],
+        ciphertexts: &[C],
+    ) -> Result<Vec<Vec<u8>>, Error>
+    where
+        C: AsRef<[u8]> + Sync,
+    {
+        // Work on a copy of the ratchet, so neither the counter nor the key
+        // advance unless every segment is authentic
+        let mut ratchet = self.ratchet.fork();
+        let mut plaintexts = Vec::with_capacity(ciphertexts.len());
+        let mut remaining = ciphertexts;
+
+        while !remaining.is_empty() {
+            let count = ratchet.epoch_segments(
+                remaining.iter().map(|ciphertext| ciphertext.as_ref().len()),
+            );
+            let (batch, rest) = remaining.split_at(count);
+
+            let first_index = ratchet.counter();
+            check_counter(first_index, batch.len())?;
+            plaintexts.extend(ratchet.stream().open_par(first_index, false, ad, batch)?);
+
+            for ciphertext in batch {
+                ratchet.advance(ciphertext.as_ref().len());
+            }
+
+            remaining = rest;
+        }
+
+        self.ratchet = ratchet;
+        Ok(plaintexts)
+    }
+
+    /// Decrypt the remaining segments in the stream in parallel using
+    /// `rayon`, the final one of which must be the last segment.
+    ///
+    /// Returns an error if no segments are given.
+    pub fn open_last_par<C>(mut self, ad: &[u8], ciphertexts: &[C]) -> Result<Vec<Vec<u8>>, Error>
+    where
+        C: AsRef<[u8]> + Sync,
+    {
+        let (last, ciphertexts) = ciphertexts.split_last().ok_or(Error)?;
+        let mut plaintexts = self.open_next_par(ad, ciphertexts)?;
+        plaintexts.push(self.open_last(ad, last.as_ref())?);
+        Ok(plaintexts)
+    }
+}
+
+impl<A, D> Exporter for X25519HkdfDecryptor<A, D>
+where
+    A: aead::Algorithm,
+    D: Digest,
+{
+    /// Derive a secret bound to this stream's key exchange
+    fn export(&self, label: &[u8], context: &[u8], length: usize) -> Result<Vec<u8>, Error> {
+        self.exporter_secret.export::<D>(label, context, length)
+    }
+}
+
+/// Ensure a STREAM counter can be advanced by the given number of segments,
+/// returning an error if it would overflow
+#[cfg(feature = "parallel")]
+fn check_counter(counter: u32, segments: usize) -> Result<(), Error> {
+    if segments > u32::MAX as usize {
+        return Err(Error);
     }
+
+    counter.checked_add(segments as u32).ok_or(Error)?;
+    Ok(())
 }
 
-/// Derive a symmetric encryption key from the combination of a public and
-/// private key and salt using X25519 D-H and HKDF
-fn derive_key<D: Digest>(
+/// Secret from which exported values are derived, cleared on drop
+struct ExporterSecret(Vec<u8>);
+
+impl ExporterSecret {
+    /// Derive an exported value using HKDF with the exporter secret as input
+    /// keying material, and the length-prefixed label followed by the context
+    /// as info
+    fn export<D: Digest>(
+        &self,
+        label: &[u8],
+        context: &[u8],
+        length: usize,
+    ) -> Result<Vec<u8>, Error> {
+        if label.len() > u16::MAX as usize ||
+            length > 255 * D::OutputSize::to_usize()
+        {
+            return Err(Error);
+        }
+
+        let mut info = Vec::with_capacity(2 + label.len() + context.len());
+        info.extend_from_slice(&(label.len() as u16).to_be_bytes());
+        info.extend_from_slice(label);
+        info.extend_from_slice(context);
+
+        let mut hkdf: Hkdf<D> = Hkdf::new(&self.0, &[]);
+        Ok(hkdf.derive(&info, length))
+    }
+}
+
+impl Drop for ExporterSecret {
+    fn drop(&mut self) {
+        self.0.as_mut_slice().clear();
+    }
+}
+
+/// Derive a symmetric encryption key and an exporter secret from the
+/// combination of a public and private key, salt, and application context
+/// using X25519 D-H and HKDF
+fn derive_keys<D: Digest>(
     private_key: &[u8; KEY_SIZE],
     public_key: &[u8; KEY_SIZE],
     salt: Option<&[u8]>,
+    context: &[u8],
     length: usize,
-) -> Vec<u8> {
+) -> Result<(Vec<u8>, ExporterSecret), Error> {
+    // Context is encoded as a 32-bit big endian length followed by its
+    // contents, making the info string unambiguous. It's omitted entirely
+    // when empty, for compatibility with streams sealed without a context.
+    if context.len() > u32::MAX as usize {
+        return Err(Error);
+    }
+
+    let encode_info = |prefix: &[u8]| {
+        let mut info = Vec::from(prefix);
+
+        if !context.is_empty() {
+            info.extend_from_slice(&(context.len() as u32).to_be_bytes());
+            info.extend_from_slice(context);
+        }
+
+        info
+    };
+
     // Compute the ECDH shared secret
-    let mut shared_secret = diffie_hellman(private_key, public_key);
+    let mut shared_secret = shared_secret(private_key, public_key)?;
 
     // Use HKDF to derive a symmetric encryption key from the shared secret
     let mut hkdf: Hkdf<D> = Hkdf::new(
@@ -201,9 +645,64 @@ fn derive_key<D: Digest>(
         salt.unwrap_or(&GenericArray::<u8, D::OutputSize>::default()),
     );
 
-    let symmetric_key = hkdf.derive(HKDF_INFO, length);
+    let symmetric_key = hkdf.derive(&encode_info(HKDF_INFO), length);
+    let exporter_secret = hkdf.derive(
+        &encode_info(HKDF_EXPORTER_INFO),
+        D::OutputSize::to_usize(),
+    );
     shared_secret.clear();
 
     // TODO: avoid allocating a Vec when the hkdf crate adds no_std support
-    symmetric_key
+    Ok((symmetric_key, ExporterSecret(exporter_secret)))
+}
+
+#[cfg(all(test, feature = "parallel"))]
+mod tests {
+    use super::{X25519HkdfSha256Decryptor, X25519HkdfSha256Encryptor};
+    use gcmsiv::Aes128GcmSiv;
+    use keys::PrivateKey;
+    use rand::OsRng;
+    use ratchet::{Ratchet, Totals};
+    use sha2::Sha256;
+    use traits::{Decryptor, Encryptor};
+
+    /// Fast-forward a ratchet's STREAM counter, which would otherwise take
+    /// billions of segments
+    fn fast_forward(ratchet: &mut Ratchet<Aes128GcmSiv, Sha256>, counter: u32) {
+        *ratchet = Ratchet::from_parts(ratchet.key(), counter, 0, 0, None, Totals::default());
+    }
+
+    #[test]
+    fn open_next_par_near_counter_limit() {
+        let mut rng = OsRng::new().unwrap();
+        let sealing_sk = PrivateKey::generate(&mut rng);
+        let (mut encryptor, ephemeral_pk) =
+            X25519HkdfSha256Encryptor::<Aes128GcmSiv>::new(&mut rng, &sealing_sk.public_key(), None)
+                .unwrap();
+        fast_forward(&mut encryptor.ratchet, u32::MAX - 2);
+
+        // The final counter value is reserved for the last segment
+        let ciphertexts = encryptor.seal_next_par(b"", &[b"one", b"two"]);
+        let last = encryptor.seal_last(b"", b"last");
+
+        let decryptor = || {
+            let mut decryptor =
+                X25519HkdfSha256Decryptor::<Aes128GcmSiv>::new(&sealing_sk, &ephemeral_pk, None)
+                    .unwrap();
+            fast_forward(&mut decryptor.ratchet, u32::MAX - 2);
+            decryptor
+        };
+
+        let mut valid = decryptor();
+        assert_eq!(valid.open_next_par(b"", &ciphertexts).unwrap(), [b"one", b"two"]);
+        assert_eq!(valid.open_last(b"", &last).unwrap(), b"last");
+
+        // More segments than the counter can hold are rejected, not a panic
+        let mut too_many = ciphertexts.clone();
+        too_many.push(last);
+
+        let mut invalid = decryptor();
+        assert!(invalid.open_next_par(b"", &too_many).is_err());
+        assert_eq!(invalid.ratchet.counter(), u32::MAX - 2);
+    }
 }
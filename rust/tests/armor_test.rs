@@ -0,0 +1,149 @@
+extern crate rand;
+extern crate xstream;
+
+mod test_rng;
+
+use std::io::{BufReader, ErrorKind};
+use test_rng::TestRng;
+use xstream::armor::{ArmorReader, ArmorWriter, ARMOR_BEGIN, ARMOR_END, ARMOR_LINE_WIDTH};
+use xstream::container::{self, Header};
+use xstream::{Aes128GcmSiv, Algorithm, Decryptor, Encryptor, PrivateKey,
+              X25519HkdfSha256Decryptor, X25519HkdfSha256Encryptor};
+
+const ALGORITHM: Algorithm = Algorithm::X25519HkdfSha256Aes128GcmSiv;
+const MAX_LEN: usize = 1024;
+
+/// Seal the given segments, returning the recipient's key and the container
+/// both armored and in binary
+fn seal(segments: &[&[u8]]) -> (PrivateKey, String, Vec<u8>) {
+    let sealing_sk = PrivateKey::new(&[1u8; 32]);
+    let mut rng = TestRng::new(&[2u8; 32]);
+
+    let (mut encryptor, ephemeral_pk) =
+        X25519HkdfSha256Encryptor::<Aes128GcmSiv>::new(&mut rng, &sealing_sk.public_key(), None)
+            .unwrap();
+
+    let header = Header::new(ALGORITHM, &ephemeral_pk, None);
+    let mut armor = ArmorWriter::new(Vec::new(), &header).unwrap();
+    let mut binary = header.to_bytes();
+
+    let (last, rest) = segments.split_last().unwrap();
+
+    for segment in rest {
+        let ciphertext = encryptor.seal_next(b"", segment);
+        armor.write_next(&ciphertext).unwrap();
+        container::write_frame(&mut binary, false, &ciphertext).unwrap();
+    }
+
+    let ciphertext = encryptor.seal_last(b"", last);
+    armor.write_last(&ciphertext).unwrap();
+    container::write_frame(&mut binary, true, &ciphertext).unwrap();
+
+    let armor = String::from_utf8(armor.into_inner()).unwrap();
+    (sealing_sk, armor, binary)
+}
+
+/// Read and open every segment of a container
+fn open(sealing_sk: &PrivateKey, input: &[u8]) -> std::io::Result<Vec<Vec<u8>>> {
+    let mut reader = ArmorReader::new(BufReader::with_capacity(7, input))?;
+    let mut decryptor = X25519HkdfSha256Decryptor::<Aes128GcmSiv>::new(
+        sealing_sk,
+        &reader.header().ephemeral_key,
+        None,
+    ).unwrap();
+
+    let mut plaintexts = Vec::new();
+
+    loop {
+        let (last, ciphertext) = reader.read_segment(MAX_LEN)?;
+
+        if last {
+            plaintexts.push(decryptor.open_last(b"", &ciphertext).unwrap());
+            return Ok(plaintexts);
+        }
+
+        plaintexts.push(decryptor.open_next(b"", &ciphertext).unwrap());
+    }
+}
+
+#[test]
+fn armor_round_trip() {
+    let segments: [&[u8]; 3] = [&[0x11; 100], b"second", b""];
+    let (sealing_sk, armor, binary) = seal(&segments);
+    let lines: Vec<&str> = armor.lines().collect();
+
+    assert_eq!(lines[0], ARMOR_BEGIN);
+    assert_eq!(*lines.last().unwrap(), ARMOR_END);
+
+    let body = &lines[1..lines.len() - 1];
+    let (last_line, full_lines) = body.split_last().unwrap();
+    assert!(full_lines.iter().all(|line| line.len() == ARMOR_LINE_WIDTH));
+    assert!(last_line.len() <= ARMOR_LINE_WIDTH);
+
+    assert_eq!(open(&sealing_sk, armor.as_bytes()).unwrap(), segments);
+    assert_eq!(open(&sealing_sk, &binary).unwrap(), segments);
+}
+
+#[test]
+fn armor_detection() {
+    let (sealing_sk, armor, binary) = seal(&[b"only"]);
+
+    assert!(ArmorReader::new(armor.as_bytes()).unwrap().is_armored());
+    assert!(!ArmorReader::new(&binary[..]).unwrap().is_armored());
+
+    // Surrounding whitespace and CRLF line endings are accepted
+    let pasted = format!("\n\n  {}\n  ", armor.replace('\n', "\r\n"));
+    assert_eq!(open(&sealing_sk, pasted.as_bytes()).unwrap(), vec![b"only".to_vec()]);
+
+    let err = ArmorReader::new(&b"plaintext"[..]).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    let err = ArmorReader::new(&b"-----BEGIN PGP MESSAGE-----\n"[..]).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    let err = ArmorReader::new(&b""[..]).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn armor_rejects_garbage() {
+    let (sealing_sk, armor, binary) = seal(&[&[0x22; 200], b"last"]);
+    let invalid = |input: &str| open(&sealing_sk, input.as_bytes()).unwrap_err().kind();
+
+    // Invalid characters, and lines of the wrong length
+    assert_eq!(invalid(&armor.replacen('A', "*", 1)), ErrorKind::InvalidData);
+    assert_eq!(invalid(&armor.replacen('\n', "\nAAAA", 2)), ErrorKind::InvalidData);
+    assert_eq!(invalid(&armor.replacen('\n', "\n\n", 2)), ErrorKind::InvalidData);
+
+    // Anything after the END line
+    assert_eq!(invalid(&format!("{}garbage\n", armor)), ErrorKind::InvalidData);
+
+    // Missing END line
+    let truncated = armor.replace(&format!("{}\n", ARMOR_END), "");
+    assert_eq!(invalid(&truncated), ErrorKind::UnexpectedEof);
+
+    // Data after the last segment
+    let mut trailing = binary.clone();
+    trailing.push(0);
+    assert_eq!(open(&sealing_sk, &trailing).unwrap_err().kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn armor_rejects_non_canonical_base64() {
+    let (sealing_sk, armor, _) = seal(&[b"only"]);
+    let lines: Vec<&str> = armor.lines().collect();
+    let last_line = lines[lines.len() - 2];
+
+    // The last body line ends in padding, so its last data character has
+    // unused bits which must be zero
+    let data = last_line.trim_end_matches('=');
+    let padding = &last_line[data.len()..];
+    assert!(!padding.is_empty());
+
+    let alphabet = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let value = alphabet.find(data.chars().last().unwrap()).unwrap();
+    let altered = alphabet.chars().nth(value | 1).unwrap();
+    let line = format!("{}{}{}", &data[..data.len() - 1], altered, padding);
+
+    let non_canonical = armor.replace(last_line, &line);
+    let err = open(&sealing_sk, non_canonical.as_bytes()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
@@ -0,0 +1,98 @@
+extern crate rand;
+extern crate xstream;
+
+mod test_rng;
+
+use test_rng::TestRng;
+use xstream::{Algorithm, PrivateKey, XStreamBuilder};
+
+#[test]
+fn seal_and_open() {
+    let sealing_sk = PrivateKey::new(&[1u8; 32]);
+    let builder = XStreamBuilder::new()
+        .algorithm(Algorithm::X25519HkdfSha256Aes256PmacSiv)
+        .salt(b"salt")
+        .context(b"archive v1")
+        .segment_size(4);
+
+    let mut rng = TestRng::new(&[2u8; 32]);
+    let (mut sealer, ephemeral_pk) = builder
+        .recipient_key(&sealing_sk.public_key())
+        .sealer(&mut rng)
+        .unwrap();
+
+    assert_eq!(sealer.algorithm(), Algorithm::X25519HkdfSha256Aes256PmacSiv);
+    let first = sealer.seal_next(b"", b"abcd").unwrap();
+    let export = sealer.export(b"manifest", b"", 32).unwrap();
+    let last = sealer.seal_last(b"", b"ef").unwrap();
+
+    let mut opener = XStreamBuilder::new()
+        .algorithm(Algorithm::X25519HkdfSha256Aes256PmacSiv)
+        .salt(b"salt")
+        .context(b"archive v1")
+        .segment_size(4)
+        .private_key(&sealing_sk)
+        .ephemeral_key(&ephemeral_pk)
+        .opener()
+        .unwrap();
+
+    assert_eq!(opener.export(b"manifest", b"", 32).unwrap(), export);
+    assert_eq!(opener.open_next(b"", &first).unwrap(), b"abcd");
+    assert_eq!(opener.open_last(b"", &last).unwrap(), b"ef");
+}
+
+#[test]
+fn framing_enforced() {
+    let sealing_sk = PrivateKey::new(&[1u8; 32]);
+    let mut rng = TestRng::new(&[2u8; 32]);
+
+    let (mut sealer, _) = XStreamBuilder::new()
+        .segment_size(4)
+        .recipient_key(&sealing_sk.public_key())
+        .sealer(&mut rng)
+        .unwrap();
+
+    assert!(sealer.seal_next(b"", b"abc").is_err());
+    assert!(sealer.seal_next(b"", b"abcde").is_err());
+    assert!(sealer.seal_last(b"", b"abcde").is_err());
+}
+
+#[test]
+fn inconsistent_options_rejected() {
+    let sealing_sk = PrivateKey::new(&[1u8; 32]);
+    let other_pk = PrivateKey::new(&[3u8; 32]).public_key();
+    let mut rng = TestRng::new(&[2u8; 32]);
+
+    // Missing keys
+    assert!(XStreamBuilder::new().sealer(&mut rng).is_err());
+    assert!(XStreamBuilder::new().private_key(&sealing_sk).opener().is_err());
+    assert!(XStreamBuilder::new().ephemeral_key(&other_pk).opener().is_err());
+
+    // Zero-sized segments
+    assert!(
+        XStreamBuilder::new()
+            .segment_size(0)
+            .recipient_key(&other_pk)
+            .sealer(&mut rng)
+            .is_err()
+    );
+
+    // Recipient key which doesn't belong to the private key
+    assert!(
+        XStreamBuilder::new()
+            .recipient_key(&other_pk)
+            .private_key(&sealing_sk)
+            .ephemeral_key(&other_pk)
+            .opener()
+            .is_err()
+    );
+
+    // Ephemeral keys are generated when sealing, not supplied
+    assert!(
+        XStreamBuilder::new()
+            .recipient_key(&other_pk)
+            .ephemeral_key(&other_pk)
+            .sealer(&mut rng)
+            .is_err()
+    );
+}
@@ -0,0 +1,152 @@
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::thread;
+
+fn xstream(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_xstream"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Write stdin from another thread, so the child can't block on a full
+    // stdout pipe while we're still writing
+    let mut pipe = child.stdin.take().unwrap();
+    let stdin = Vec::from(stdin);
+    let writer = thread::spawn(move || {
+        let _ = pipe.write_all(&stdin);
+    });
+
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    output
+}
+
+/// Generate a keypair in a fresh temporary directory, returning the
+/// directory, the keyfile and the public key
+fn keygen(name: &str) -> (PathBuf, String, String) {
+    let dir = env::temp_dir().join(format!("xstream-cli-test-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let keyfile = dir.join("key").to_str().unwrap().to_owned();
+    let output = xstream(&["keygen", &keyfile], b"");
+    assert!(output.status.success());
+
+    let public_key = String::from_utf8(output.stdout).unwrap().trim().to_owned();
+    assert_eq!(public_key.len(), 64);
+
+    // Existing keys aren't overwritten
+    assert!(!xstream(&["keygen", &keyfile], b"").status.success());
+
+    (dir, keyfile, public_key)
+}
+
+#[test]
+fn encrypt_and_decrypt_pipes() {
+    let (dir, keyfile, public_key) = keygen("pipes");
+    let plaintext: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+
+    let encrypted = xstream(&["encrypt", "-r", &public_key], &plaintext);
+    assert!(encrypted.status.success());
+    assert!(encrypted.stdout.starts_with(b"XSTREAM"));
+
+    let decrypted = xstream(&["decrypt", "-i", &keyfile], &encrypted.stdout);
+    assert!(decrypted.status.success());
+    assert_eq!(decrypted.stdout, plaintext);
+
+    let empty = xstream(&["encrypt", "-r", &public_key], b"");
+    let decrypted = xstream(&["decrypt", "-i", &keyfile, "-"], &empty.stdout);
+    assert!(decrypted.status.success());
+    assert!(decrypted.stdout.is_empty());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn encrypt_and_decrypt_files() {
+    let (dir, keyfile, public_key) = keygen("files");
+    let input = dir.join("plain").to_str().unwrap().to_owned();
+    let encrypted = dir.join("encrypted").to_str().unwrap().to_owned();
+    let decrypted = dir.join("decrypted").to_str().unwrap().to_owned();
+    fs::write(&input, b"hello, world").unwrap();
+
+    assert!(xstream(&["encrypt", "-r", &public_key, &input, &encrypted], b"").status.success());
+    assert!(xstream(&["decrypt", &encrypted, &decrypted, "-i", &keyfile], b"").status.success());
+    assert_eq!(fs::read(&decrypted).unwrap(), b"hello, world");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn encrypt_and_decrypt_armored() {
+    let (dir, keyfile, public_key) = keygen("armor");
+    let plaintext: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+
+    let encrypted = xstream(&["encrypt", "--armor", "-r", &public_key], &plaintext);
+    assert!(encrypted.status.success());
+    let armor = String::from_utf8(encrypted.stdout).unwrap();
+    assert!(armor.starts_with("-----BEGIN XSTREAM MESSAGE-----\n"));
+    assert!(armor.ends_with("-----END XSTREAM MESSAGE-----\n"));
+
+    let decrypted = xstream(&["decrypt", "-i", &keyfile], armor.as_bytes());
+    assert!(decrypted.status.success());
+    assert_eq!(decrypted.stdout, plaintext);
+
+    let garbled = armor.replacen("A", "*", 1);
+    let output = xstream(&["decrypt", "-i", &keyfile], garbled.as_bytes());
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("malformed file"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn authentication_failure() {
+    let (dir, keyfile, public_key) = keygen("auth");
+    let plaintext = vec![0x42u8; 100_000];
+    let encrypted = xstream(&["encrypt", "-r", &public_key], &plaintext).stdout;
+
+    let mut tampered = encrypted.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    let output = xstream(&["decrypt", "-i", &keyfile], &tampered);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("authentication failed"));
+
+    // Truncation at a segment boundary, with the marker forged to claim the
+    // truncated stream is complete
+    let second_frame = encrypted.len() - (plaintext.len() - 65_536 + 16) - 5;
+    let mut truncated = Vec::from(&encrypted[..second_frame]);
+    let first_frame = second_frame - (65_536 + 16) - 5;
+    truncated[first_frame] = 1;
+    let output = xstream(&["decrypt", "-i", &keyfile], &truncated);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("authentication failed"));
+
+    let output = xstream(&["decrypt", "-i", &keyfile], &encrypted[..second_frame]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("truncated"));
+
+    // Wrong key
+    let (other_dir, other_keyfile, _) = keygen("auth-other");
+    let output = xstream(&["decrypt", "-i", &other_keyfile], &encrypted);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+
+    fs::remove_dir_all(dir).unwrap();
+    fs::remove_dir_all(other_dir).unwrap();
+}
+
+#[test]
+fn usage_errors() {
+    assert_eq!(xstream(&[], b"").status.code(), Some(2));
+    assert_eq!(xstream(&["frobnicate"], b"").status.code(), Some(2));
+    assert_eq!(xstream(&["encrypt"], b"").status.code(), Some(2));
+    assert_eq!(xstream(&["encrypt", "-r", "abcd", "-x"], b"").status.code(), Some(2));
+    assert_eq!(xstream(&["encrypt", "-r", "not hex"], b"").status.code(), Some(1));
+}
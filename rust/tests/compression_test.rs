@@ -0,0 +1,154 @@
+extern crate rand;
+extern crate xstream;
+
+mod fixture;
+mod test_rng;
+
+#[cfg(any(feature = "deflate", feature = "zstd"))]
+use std::io::{Seek, SeekFrom};
+use fixture::{seal_framed, segment_reader};
+use std::io::Read;
+#[cfg(any(feature = "deflate", feature = "zstd"))]
+use xstream::Padding;
+use xstream::container;
+use xstream::{Compression, XStreamBuilder};
+
+const SEGMENT_SIZE: usize = 256;
+
+fn compressible_plaintext() -> Vec<u8> {
+    let mut plaintext = Vec::new();
+
+    for i in 0..100 {
+        plaintext.extend_from_slice(format!("{{\"level\":\"info\",\"seq\":{}}}\n", i).as_bytes());
+    }
+
+    plaintext
+}
+
+fn builder(compression: Compression, segment_size: usize) -> XStreamBuilder {
+    XStreamBuilder::new().segment_size(segment_size).compression(compression)
+}
+
+#[cfg(any(feature = "deflate", feature = "zstd"))]
+fn round_trip(compression: Compression) {
+    let plaintext = compressible_plaintext();
+    let (ephemeral_pk, framed) = seal_framed(builder(compression, SEGMENT_SIZE), &plaintext);
+
+    assert!(framed.len() < plaintext.len() / 2);
+
+    let mut reader =
+        segment_reader(builder(compression, SEGMENT_SIZE), &ephemeral_pk, framed).unwrap();
+    assert_eq!(reader.plaintext_len(), plaintext.len() as u64);
+
+    let mut decrypted = Vec::new();
+    reader.read_to_end(&mut decrypted).unwrap();
+    assert_eq!(decrypted, plaintext);
+
+    // Segments can still be read in any order
+    let mut middle = [0u8; 64];
+    reader.seek(SeekFrom::Start(1000)).unwrap();
+    reader.read_exact(&mut middle).unwrap();
+    assert_eq!(&middle[..], &plaintext[1000..1064]);
+}
+
+#[cfg(feature = "deflate")]
+#[test]
+fn deflate_round_trip() {
+    round_trip(Compression::Deflate(6));
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd_round_trip() {
+    round_trip(Compression::Zstd(3));
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd_levels_validated() {
+    let accepted = |level| fixture::sealer(builder(Compression::Zstd(level), SEGMENT_SIZE)).is_ok();
+
+    // Negative levels are accepted, down to the library's minimum
+    assert!(accepted(-5));
+    assert!(accepted(0));
+    assert!(accepted(22));
+    assert!(!accepted(23));
+}
+
+#[test]
+fn stored_round_trip() {
+    let plaintext = compressible_plaintext();
+    let (ephemeral_pk, framed) = seal_framed(builder(Compression::None, SEGMENT_SIZE), &plaintext);
+
+    // Segments are flagged, but not compressed
+    let segments = (plaintext.len() + SEGMENT_SIZE) / SEGMENT_SIZE;
+    let header_len = container::inspect(&framed).unwrap().header_len;
+    assert_eq!(framed.len(), header_len + segments * (5 + 1 + 16) + plaintext.len());
+
+    let mut reader =
+        segment_reader(builder(Compression::None, SEGMENT_SIZE), &ephemeral_pk, framed)
+            .unwrap();
+    let mut decrypted = Vec::new();
+    reader.read_to_end(&mut decrypted).unwrap();
+    assert_eq!(decrypted, plaintext);
+}
+
+#[cfg(any(feature = "deflate", feature = "zstd"))]
+#[test]
+fn incompressible_segments_stored() {
+    #[cfg(feature = "deflate")]
+    let compression = Compression::Deflate(9);
+    #[cfg(not(feature = "deflate"))]
+    let compression = Compression::Zstd(19);
+
+    // xorshift output, which doesn't compress
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let plaintext: Vec<u8> = (0..3 * SEGMENT_SIZE)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 32) as u8
+        })
+        .collect();
+    let (ephemeral_pk, framed) = seal_framed(builder(compression, SEGMENT_SIZE), &plaintext);
+
+    // Each segment grows by at most the compression flag
+    let header_len = container::inspect(&framed).unwrap().header_len;
+    assert!(framed.len() <= header_len + 4 * (5 + 1 + 16) + plaintext.len());
+
+    let mut reader =
+        segment_reader(builder(compression, SEGMENT_SIZE), &ephemeral_pk, framed).unwrap();
+    let mut decrypted = Vec::new();
+    reader.read_to_end(&mut decrypted).unwrap();
+    assert_eq!(decrypted, plaintext);
+}
+
+#[cfg(any(feature = "deflate", feature = "zstd"))]
+#[test]
+fn decompression_bomb_rejected() {
+    #[cfg(feature = "deflate")]
+    let compression = Compression::Deflate(9);
+    #[cfg(not(feature = "deflate"))]
+    let compression = Compression::Zstd(19);
+
+    // Segments of zeroes which decompress to far more than the opener's
+    // segment size
+    let (ephemeral_pk, framed) = seal_framed(builder(compression, 4096), &[0u8; 8192]);
+
+    assert!(segment_reader(builder(compression, 64), &ephemeral_pk, framed).is_err());
+}
+
+#[cfg(any(feature = "deflate", feature = "zstd"))]
+#[test]
+fn compression_options_validated() {
+    #[cfg(feature = "deflate")]
+    let compression = Compression::Deflate(6);
+    #[cfg(not(feature = "deflate"))]
+    let compression = Compression::Zstd(3);
+
+    // Compression requires framing, and isn't compatible with padding
+    assert!(fixture::sealer(XStreamBuilder::new().compression(compression)).is_err());
+    assert!(fixture::sealer(builder(compression, SEGMENT_SIZE)).is_ok());
+    assert!(fixture::sealer(builder(compression, SEGMENT_SIZE).padding(Padding::Padme)).is_err());
+}
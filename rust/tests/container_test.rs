@@ -0,0 +1,191 @@
+extern crate rand;
+extern crate xstream;
+
+mod test_rng;
+
+use std::process::Command;
+use test_rng::TestRng;
+use xstream::container::{self, Header, SegmentInfo, CONTAINER_MAGIC};
+use xstream::{Aes128GcmSiv, Algorithm, Decryptor, Encryptor, PrivateKey,
+              X25519HkdfSha256Decryptor, X25519HkdfSha256Encryptor};
+
+const ALGORITHM: Algorithm = Algorithm::X25519HkdfSha256Aes128GcmSiv;
+const SALT: &[u8] = b"container salt";
+
+/// Seal the given segments into a container, returning the recipient's key
+fn seal(segments: &[&[u8]]) -> (PrivateKey, Vec<u8>) {
+    let sealing_sk = PrivateKey::new(&[1u8; 32]);
+    let mut rng = TestRng::new(&[2u8; 32]);
+
+    let (mut encryptor, ephemeral_pk) = X25519HkdfSha256Encryptor::<Aes128GcmSiv>::new(
+        &mut rng,
+        &sealing_sk.public_key(),
+        Some(SALT),
+    ).unwrap();
+
+    let mut out = Header::new(ALGORITHM, &ephemeral_pk, Some(SALT)).to_bytes();
+    let (last, rest) = segments.split_last().unwrap();
+
+    for segment in rest {
+        container::write_frame(&mut out, false, &encryptor.seal_next(b"", segment)).unwrap();
+    }
+
+    container::write_frame(&mut out, true, &encryptor.seal_last(b"", last)).unwrap();
+    (sealing_sk, out)
+}
+
+#[test]
+fn header_round_trip() {
+    let ephemeral_pk = PrivateKey::new(&[3u8; 32]).public_key();
+    let header = Header::new(ALGORITHM, &ephemeral_pk, Some(SALT));
+    let bytes = header.to_bytes();
+
+    assert!(bytes.starts_with(CONTAINER_MAGIC));
+    assert_eq!(Header::read_from(&mut &bytes[..]).unwrap(), header);
+    assert_eq!(header.algorithm().unwrap(), ALGORITHM);
+    assert_eq!(header.salt(), Some(SALT));
+
+    assert_eq!(Header::new(ALGORITHM, &ephemeral_pk, None).salt(), None);
+}
+
+#[test]
+fn header_errors() {
+    let ephemeral_pk = PrivateKey::new(&[3u8; 32]).public_key();
+    let bytes = Header::new(ALGORITHM, &ephemeral_pk, Some(SALT)).to_bytes();
+
+    let err = Header::read_from(&mut &bytes[..bytes.len() - 1]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+
+    let mut bad_magic = bytes.clone();
+    bad_magic[0] ^= 1;
+    let err = Header::read_from(&mut &bad_magic[..]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    let mut bad_version = bytes.clone();
+    bad_version[CONTAINER_MAGIC.len()] = 0xff;
+    let err = Header::read_from(&mut &bad_version[..]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn inspect_and_open() {
+    let segments: [&[u8]; 3] = [b"first segment", b"second", b"last"];
+    let (sealing_sk, bytes) = seal(&segments);
+    let inspection = container::inspect(&bytes).unwrap();
+
+    assert_eq!(inspection.header.algorithm().unwrap(), ALGORITHM);
+    assert_eq!(inspection.header.salt(), Some(SALT));
+    assert!(inspection.finished);
+    assert!(!inspection.truncated);
+    assert_eq!(inspection.trailing_bytes, 0);
+
+    let lens: Vec<usize> = inspection.segments.iter().map(|s| s.len).collect();
+    assert_eq!(lens, vec![29, 22, 20]);
+    assert_eq!(
+        inspection.segments.iter().map(|s| s.last).collect::<Vec<_>>(),
+        vec![false, false, true]
+    );
+
+    // The reported layout is enough to open the stream
+    let mut decryptor = X25519HkdfSha256Decryptor::<Aes128GcmSiv>::new(
+        &sealing_sk,
+        &inspection.header.ephemeral_key,
+        inspection.header.salt(),
+    ).unwrap();
+
+    let open = |info: &SegmentInfo| &bytes[info.offset..info.offset + info.len];
+    let (last, rest) = inspection.segments.split_last().unwrap();
+
+    for (info, expected) in rest.iter().zip(&segments) {
+        assert_eq!(decryptor.open_next(b"", open(info)).unwrap(), *expected);
+    }
+
+    assert_eq!(decryptor.open_last(b"", open(last)).unwrap(), segments[2]);
+}
+
+#[test]
+fn inspect_incomplete_containers() {
+    let (_, bytes) = seal(&[b"first segment", b"last"]);
+    let header_len = container::inspect(&bytes).unwrap().header_len;
+
+    // Cut off partway through the last segment
+    let inspection = container::inspect(&bytes[..bytes.len() - 3]).unwrap();
+    assert_eq!(inspection.segments.len(), 1);
+    assert!(!inspection.finished);
+    assert!(inspection.truncated);
+
+    // Cut off cleanly after the first segment
+    let inspection = container::inspect(&bytes[..header_len + 5 + 29]).unwrap();
+    assert_eq!(
+        inspection.segments,
+        vec![SegmentInfo {
+            offset: header_len + 5,
+            len: 29,
+            last: false,
+        }]
+    );
+    assert!(!inspection.finished);
+    assert!(!inspection.truncated);
+
+    let mut trailing = bytes.clone();
+    trailing.extend_from_slice(b"junk");
+    assert_eq!(container::inspect(&trailing).unwrap().trailing_bytes, 4);
+
+    let mut bad_marker = bytes.clone();
+    bad_marker[header_len] = 7;
+    assert!(container::inspect(&bad_marker).is_err());
+
+    assert!(container::inspect(b"not a container").is_err());
+}
+
+#[test]
+fn inspect_json() {
+    let (_, bytes) = seal(&[b"only"]);
+    let inspection = container::inspect(&bytes).unwrap();
+    let json = inspection.to_json();
+
+    assert!(json.starts_with("{\"version\":1,"));
+    assert!(json.contains("\"algorithm\":\"XSTREAM_X25519_HKDF_SHA256_AES128_GCM_SIV\""));
+    assert!(json.contains("\"supported\":true"));
+    assert!(json.contains(&format!("\"salt_len\":{}", SALT.len())));
+    assert!(json.contains(&format!(
+        "\"segments\":[{{\"offset\":{},\"len\":20,\"last\":true}}]",
+        inspection.header_len + 5
+    )));
+    assert!(json.ends_with("\"finished\":true,\"truncated\":false,\"trailing_bytes\":0}"));
+}
+
+#[test]
+fn inspect_tool() {
+    let (_, bytes) = seal(&[b"first segment", b"last"]);
+    let path = std::env::temp_dir().join(format!("xstream-inspect-{}", std::process::id()));
+    std::fs::write(&path, &bytes).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_xstream-inspect"))
+        .args(["--json", path.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().trim(),
+        container::inspect(&bytes).unwrap().to_json()
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_xstream-inspect"))
+        .arg(path.to_str().unwrap())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(report.contains("segments:       2"));
+    assert!(report.contains("last segment:   present"));
+
+    std::fs::write(&path, b"plaintext").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_xstream-inspect"))
+        .arg(path.to_str().unwrap())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,47 @@
+//! Keys and helpers for sealing and opening streams in tests
+
+// Each suite uses a different subset of these
+#![allow(dead_code)]
+
+use std::io::{self, Cursor, Write};
+use test_rng::TestRng;
+use xstream::{Error, Opener, PrivateKey, PublicKey, Sealer, SegmentReader, SegmentWriter,
+              XStreamBuilder};
+
+/// The recipient's private key
+pub fn sealing_key() -> PrivateKey {
+    PrivateKey::new(&[1u8; 32])
+}
+
+/// RNG supplying the sender's ephemeral key
+pub fn rng() -> TestRng {
+    TestRng::new(&[2u8; 32])
+}
+
+/// Build a sealer for the recipient with the given options
+pub fn sealer(builder: XStreamBuilder) -> Result<(Sealer, PublicKey), Error> {
+    builder.recipient_key(&sealing_key().public_key()).sealer(&mut rng())
+}
+
+/// Build the recipient's opener with the given options
+pub fn opener(builder: XStreamBuilder, ephemeral_pk: &PublicKey) -> Result<Opener, Error> {
+    builder.private_key(&sealing_key()).ephemeral_key(ephemeral_pk).opener()
+}
+
+/// Seal plaintext with a `SegmentWriter`, returning the ephemeral key and
+/// the container
+pub fn seal_framed(builder: XStreamBuilder, plaintext: &[u8]) -> (PublicKey, Vec<u8>) {
+    let (sealer, ephemeral_pk) = sealer(builder).unwrap();
+    let mut writer = SegmentWriter::new(sealer, Vec::new()).unwrap();
+    writer.write_all(plaintext).unwrap();
+    (ephemeral_pk, writer.finish().unwrap())
+}
+
+/// Create a `SegmentReader` for a container sealed by `seal_framed`
+pub fn segment_reader(
+    builder: XStreamBuilder,
+    ephemeral_pk: &PublicKey,
+    framed: Vec<u8>,
+) -> io::Result<SegmentReader<Cursor<Vec<u8>>>> {
+    SegmentReader::new(opener(builder, ephemeral_pk).unwrap(), Cursor::new(framed))
+}
@@ -0,0 +1,147 @@
+extern crate rand;
+extern crate xstream;
+
+mod fixture;
+mod test_rng;
+
+use fixture::{rng, sealing_key, seal_framed, segment_reader};
+use std::io::{Read, Seek, SeekFrom};
+use xstream::container;
+use xstream::{Algorithm, Decryptor, Encryptor, PrivateKey, X25519HkdfSha256Decryptor,
+              X25519HkdfSha256Encryptor, XStreamBuilder};
+use xstream::Aes128GcmSiv;
+
+const SEGMENT_SIZE: usize = 16;
+
+fn builder() -> XStreamBuilder {
+    XStreamBuilder::new()
+        .algorithm(Algorithm::X25519HkdfSha256Aes128GcmSiv)
+        .segment_size(SEGMENT_SIZE)
+}
+
+#[test]
+fn open_at_matches_sequential() {
+    let sealing_sk = sealing_key();
+
+    let (mut encryptor, ephemeral_pk) =
+        X25519HkdfSha256Encryptor::<Aes128GcmSiv>::new(&mut rng(), &sealing_sk.public_key(), None)
+            .unwrap();
+
+    let segments: Vec<Vec<u8>> = (0..10u8).map(|i| vec![i; 8]).collect();
+    let mut ciphertexts: Vec<Vec<u8>> = segments[..9]
+        .iter()
+        .map(|segment| encryptor.seal_next(b"ad", segment))
+        .collect();
+    ciphertexts.push(encryptor.seal_last(b"ad", &segments[9]));
+
+    let mut decryptor =
+        X25519HkdfSha256Decryptor::<Aes128GcmSiv>::new(&sealing_sk, &ephemeral_pk, None).unwrap();
+
+    assert_eq!(
+        decryptor.open_at(9, true, b"ad", &ciphertexts[9]).unwrap(),
+        segments[9]
+    );
+    assert_eq!(
+        decryptor.open_at(4, false, b"ad", &ciphertexts[4]).unwrap(),
+        segments[4]
+    );
+
+    // Wrong index or last flag
+    assert!(decryptor.open_at(5, false, b"ad", &ciphertexts[4]).is_err());
+    assert!(decryptor.open_at(4, true, b"ad", &ciphertexts[4]).is_err());
+    assert!(decryptor.open_at(9, false, b"ad", &ciphertexts[9]).is_err());
+
+    // Random access doesn't disturb sequential decryption
+    assert_eq!(decryptor.open_next(b"ad", &ciphertexts[0]).unwrap(), segments[0]);
+}
+
+#[test]
+fn seekable_reader() {
+    let plaintext: Vec<u8> = (0..100u8).collect();
+    let (ephemeral_pk, framed) = seal_framed(builder(), &plaintext);
+
+    let mut reader = segment_reader(builder(), &ephemeral_pk, framed).unwrap();
+    assert_eq!(reader.plaintext_len(), 100);
+    assert_eq!(reader.segment_count(), 7);
+
+    let mut buf = [0u8; 10];
+    reader.seek(SeekFrom::Start(75)).unwrap();
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf[..], &plaintext[75..85]);
+
+    reader.seek(SeekFrom::End(-5)).unwrap();
+    let mut tail = Vec::new();
+    reader.read_to_end(&mut tail).unwrap();
+    assert_eq!(tail, &plaintext[95..]);
+
+    reader.seek(SeekFrom::Start(0)).unwrap();
+    let mut all = Vec::new();
+    reader.read_to_end(&mut all).unwrap();
+    assert_eq!(all, plaintext);
+}
+
+#[test]
+fn exact_multiple_of_segment_size() {
+    for len in &[0, SEGMENT_SIZE, SEGMENT_SIZE * 3] {
+        let plaintext = vec![0x42u8; *len];
+        let (ephemeral_pk, framed) = seal_framed(builder(), &plaintext);
+
+        let mut reader = segment_reader(builder(), &ephemeral_pk, framed).unwrap();
+        let mut result = Vec::new();
+        reader.read_to_end(&mut result).unwrap();
+        assert_eq!(result, plaintext);
+    }
+}
+
+#[test]
+fn truncation_and_tampering_detected() {
+    let plaintext = vec![0x42u8; 64];
+    let (ephemeral_pk, framed) = seal_framed(builder(), &plaintext);
+    let header_len = container::inspect(&framed).unwrap().header_len;
+    let frame_size = 5 + SEGMENT_SIZE + 16;
+
+    // Dropping the last segment leaves a non-final segment at the end
+    let truncated = framed[..framed.len() - frame_size].to_vec();
+    assert!(segment_reader(builder(), &ephemeral_pk, truncated).is_err());
+
+    // Corrupting a middle segment is only detected when it's read
+    let mut tampered = framed.clone();
+    tampered[header_len + frame_size + 10] ^= 1;
+    let mut reader = segment_reader(builder(), &ephemeral_pk, tampered).unwrap();
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf).unwrap();
+    reader.seek(SeekFrom::Start(SEGMENT_SIZE as u64)).unwrap();
+    assert!(reader.read_exact(&mut buf).is_err());
+}
+
+#[test]
+fn output_is_a_container() {
+    let plaintext = vec![0x42u8; 40];
+    let (ephemeral_pk, framed) = seal_framed(builder(), &plaintext);
+
+    let inspection = container::inspect(&framed).unwrap();
+    assert_eq!(
+        inspection.header.algorithm().unwrap(),
+        Algorithm::X25519HkdfSha256Aes128GcmSiv
+    );
+    assert_eq!(inspection.header.ephemeral_key, ephemeral_pk);
+    assert!(inspection.finished);
+    assert!(!inspection.truncated);
+    assert_eq!(inspection.trailing_bytes, 0);
+
+    let lens: Vec<usize> = inspection.segments.iter().map(|segment| segment.len).collect();
+    assert_eq!(lens, vec![SEGMENT_SIZE + 16, SEGMENT_SIZE + 16, 8 + 16]);
+
+    let mut reader = segment_reader(builder(), &ephemeral_pk, framed).unwrap();
+    let mut result = Vec::new();
+    reader.read_to_end(&mut result).unwrap();
+    assert_eq!(result, plaintext);
+}
+
+#[test]
+fn header_mismatch_rejected() {
+    let (_, framed) = seal_framed(builder(), &[0x42u8; 40]);
+    let other_pk = PrivateKey::new(&[3u8; 32]).public_key();
+
+    assert!(segment_reader(builder(), &other_pk, framed).is_err());
+}
@@ -0,0 +1,118 @@
+extern crate rand;
+extern crate xstream;
+
+mod fixture;
+mod test_rng;
+
+use fixture::{rng, sealing_key};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use xstream::{Aes128GcmSiv, Decryptor, Encryptor, FinishedWriter, PublicKey,
+              SealGuard, X25519HkdfSha256Decryptor, X25519HkdfSha256Encryptor};
+
+type Guard = SealGuard<X25519HkdfSha256Encryptor<Aes128GcmSiv>>;
+
+fn new_guard() -> (PublicKey, Guard) {
+    let (encryptor, ephemeral_pk) = X25519HkdfSha256Encryptor::<Aes128GcmSiv>::new(
+        &mut rng(),
+        &sealing_key().public_key(),
+        None,
+    ).unwrap();
+
+    (ephemeral_pk, SealGuard::new(encryptor))
+}
+
+/// Guard whose hook counts how many times it was called
+fn counting_guard() -> (Arc<AtomicUsize>, Guard) {
+    let count = Arc::new(AtomicUsize::new(0));
+    let hook_count = count.clone();
+    let (_, guard) = new_guard();
+    let guard = guard.on_unfinished(move || {
+        hook_count.fetch_add(1, Ordering::SeqCst);
+    });
+
+    (count, guard)
+}
+
+#[test]
+fn finished_stream() {
+    let (ephemeral_pk, mut guard) = new_guard();
+    let mut writer = FinishedWriter::new(Vec::new());
+
+    let first = guard.seal_next(b"", b"first");
+    writer.write_all(&first).unwrap();
+    let (last, finished) = guard.seal_last(b"", b"last");
+    writer.write_all(&last).unwrap();
+
+    let output = writer.finish(finished).unwrap();
+    assert_eq!(output.len(), first.len() + last.len());
+
+    let mut decryptor =
+        X25519HkdfSha256Decryptor::<Aes128GcmSiv>::new(&sealing_key(), &ephemeral_pk, None)
+            .unwrap();
+    assert_eq!(decryptor.open_next(b"", &output[..first.len()]).unwrap(), b"first");
+    assert_eq!(decryptor.open_last(b"", &output[first.len()..]).unwrap(), b"last");
+}
+
+#[test]
+fn unfinished_stream_calls_hook() {
+    let (count, mut guard) = counting_guard();
+    guard.seal_next(b"", b"first");
+    drop(guard);
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+
+    let (count, guard) = counting_guard();
+    let _ = guard.seal_last(b"", b"last");
+    assert_eq!(count.load(Ordering::SeqCst), 0);
+
+    // Abandoning a stream, or taking the encryptor back, is deliberate
+    let (count, guard) = counting_guard();
+    guard.abandon();
+    assert_eq!(count.load(Ordering::SeqCst), 0);
+
+    let (count, guard) = counting_guard();
+    let _encryptor = guard.into_inner();
+    assert_eq!(count.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "without sealing the last segment")]
+fn unfinished_stream_fails_debug_assertion() {
+    let (_, mut guard) = new_guard();
+    guard.seal_next(b"", b"first");
+}
+
+#[test]
+fn unfinished_writer_calls_hook() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let counting_writer = || {
+        let hook_count = count.clone();
+        FinishedWriter::new(Vec::new()).on_unfinished(move || {
+            hook_count.fetch_add(1, Ordering::SeqCst);
+        })
+    };
+
+    // A proof from another stream doesn't excuse dropping this one
+    let (_, guard) = new_guard();
+    let (_, finished) = guard.seal_last(b"", b"last");
+    let mut writer = counting_writer();
+    writer.write_all(b"segment").unwrap();
+    drop(writer);
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+
+    writer = counting_writer();
+    writer.finish(finished).unwrap();
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+
+    counting_writer().abandon();
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "without finishing the stream")]
+fn unfinished_writer_fails_debug_assertion() {
+    let mut writer = FinishedWriter::new(Vec::new());
+    writer.write_all(b"segment").unwrap();
+}
@@ -0,0 +1,139 @@
+extern crate rand;
+extern crate xstream;
+
+mod hpke_vectors;
+mod test_rng;
+
+use hpke_vectors::HpkeExample;
+use test_rng::TestRng;
+use xstream::{Encryptor, Decryptor, PrivateKey, PublicKey};
+use xstream::hpke::{self, Aead, Aes128Gcm, ChaCha20Poly1305, HpkeEncryptor, HpkeDecryptor};
+
+#[test]
+fn hpke_examples() {
+    for ex in HpkeExample::load_all() {
+        match ex.aead_id {
+            1 => test_example::<Aes128Gcm>(&ex),
+            3 => test_example::<ChaCha20Poly1305>(&ex),
+            _ => panic!("unexpected AEAD: {}", ex.aead_id),
+        }
+    }
+}
+
+fn test_example<A: Aead>(ex: &HpkeExample) {
+    let (sk_r, pk_r) = hpke::derive_key_pair(&ex.ikm_r);
+    assert_eq!(sk_r.as_bytes(), &ex.sk_rm[..], "{}", ex.name);
+    assert_eq!(pk_r.as_bytes(), &ex.pk_rm[..], "{}", ex.name);
+
+    let sender = ex.ikm_s.as_ref().map(|ikm| hpke::derive_key_pair(ikm));
+
+    if let Some((ref sk_s, ref pk_s)) = sender {
+        assert_eq!(sk_s.as_bytes(), &ex.sk_sm.as_ref().unwrap()[..], "{}", ex.name);
+        assert_eq!(pk_s.as_bytes(), &ex.pk_sm.as_ref().unwrap()[..], "{}", ex.name);
+    }
+
+    let psk = ex.psk.as_ref().map(|psk| (&psk[..], &ex.psk_id.as_ref().unwrap()[..]));
+    let mut rng = TestRng::new(&ex.ikm_e);
+
+    let (mut encryptor, enc) = match (ex.mode, psk, &sender) {
+        (0, None, &None) => HpkeEncryptor::<A>::new(&mut rng, &pk_r, Some(&ex.info)),
+        (1, Some((psk, psk_id)), &None) => {
+            HpkeEncryptor::<A>::new_psk(&mut rng, &pk_r, &ex.info, psk, psk_id)
+        }
+        (2, None, &Some((ref sk_s, _))) => {
+            HpkeEncryptor::<A>::new_auth(&mut rng, &pk_r, &ex.info, sk_s)
+        }
+        (3, Some((psk, psk_id)), &Some((ref sk_s, _))) => {
+            HpkeEncryptor::<A>::new_auth_psk(&mut rng, &pk_r, &ex.info, psk, psk_id, sk_s)
+        }
+        _ => panic!("unexpected mode: {}", ex.mode),
+    }.expect("valid public key");
+
+    assert_eq!(enc.as_bytes(), &ex.enc[..], "{}", ex.name);
+
+    let mut decryptor = match (ex.mode, psk, &sender) {
+        (0, None, &None) => HpkeDecryptor::<A>::new(&sk_r, &enc, Some(&ex.info)),
+        (1, Some((psk, psk_id)), &None) => {
+            HpkeDecryptor::<A>::new_psk(&sk_r, &enc, &ex.info, psk, psk_id)
+        }
+        (2, None, &Some((_, ref pk_s))) => HpkeDecryptor::<A>::new_auth(&sk_r, &enc, &ex.info, pk_s),
+        (3, Some((psk, psk_id)), &Some((_, ref pk_s))) => {
+            HpkeDecryptor::<A>::new_auth_psk(&sk_r, &enc, &ex.info, psk, psk_id, pk_s)
+        }
+        _ => panic!("unexpected mode: {}", ex.mode),
+    }.expect("valid ephemeral key");
+
+    // Vectors only list selected sequence numbers, so seal every message in
+    // between to advance the context
+    let last = ex.encryptions.last().expect("encryptions").sequence_number;
+    let plaintext = &ex.encryptions[0].plaintext;
+
+    for seq in 0..(last + 1) {
+        let aad = format!("Count-{}", seq).into_bytes();
+        let ciphertext = encryptor.seal_next(&aad, plaintext);
+
+        if let Some(expected) = ex.encryptions.iter().find(|e| e.sequence_number == seq) {
+            assert_eq!(aad, expected.aad, "{}", ex.name);
+            assert_eq!(ciphertext, expected.ciphertext, "{} seq {}", ex.name, seq);
+        }
+
+        let opened = decryptor.open_next(&aad, &ciphertext).expect(
+            "decrypt failure",
+        );
+
+        assert_eq!(&opened, plaintext);
+    }
+
+    for export in &ex.exports {
+        let sender_value = encryptor
+            .export(&export.exporter_context, export.length)
+            .unwrap();
+        let receiver_value = decryptor
+            .export(&export.exporter_context, export.length)
+            .unwrap();
+
+        assert_eq!(sender_value, export.exported_value, "{}", ex.name);
+        assert_eq!(receiver_value, export.exported_value, "{}", ex.name);
+    }
+}
+
+#[test]
+fn single_shot_round_trip() {
+    let mut rng = TestRng::new(&[0x42; 32]);
+    let (sk_r, pk_r) = hpke::derive_key_pair(b"recipient");
+
+    let (enc, ciphertext) =
+        hpke::seal::<ChaCha20Poly1305, _>(&mut rng, &pk_r, b"info", b"ad", b"plaintext").unwrap();
+
+    assert_eq!(
+        hpke::open::<ChaCha20Poly1305>(&sk_r, &enc, b"info", b"ad", &ciphertext).unwrap(),
+        b"plaintext"
+    );
+
+    assert!(hpke::open::<ChaCha20Poly1305>(&sk_r, &enc, b"other", b"ad", &ciphertext).is_err());
+}
+
+#[test]
+fn psk_mismatch_rejected() {
+    let mut rng = TestRng::new(&[0x42; 32]);
+    let (sk_r, pk_r) = hpke::derive_key_pair(b"recipient");
+
+    let (encryptor, enc) =
+        HpkeEncryptor::<Aes128Gcm>::new_psk(&mut rng, &pk_r, b"", b"secret", b"id").unwrap();
+    let ciphertext = encryptor.seal_last(b"", b"plaintext");
+
+    let decryptor =
+        HpkeDecryptor::<Aes128Gcm>::new_psk(&sk_r, &enc, b"", b"wrong", b"id").unwrap();
+    assert!(decryptor.open_last(b"", &ciphertext).is_err());
+
+    // A PSK must be accompanied by a PSK ID and vice versa
+    assert!(HpkeDecryptor::<Aes128Gcm>::new_psk(&sk_r, &enc, b"", b"secret", b"").is_err());
+    assert!(HpkeDecryptor::<Aes128Gcm>::new_psk(&sk_r, &enc, b"", b"", b"id").is_err());
+}
+
+#[test]
+fn low_order_ephemeral_key_rejected() {
+    let sk_r = PrivateKey::new(&[1u8; 32]);
+    let low_order_pk = PublicKey::from([0u8; 32]);
+    assert!(HpkeDecryptor::<Aes128Gcm>::new(&sk_r, &low_order_pk, None).is_err());
+}
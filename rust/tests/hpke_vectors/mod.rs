@@ -0,0 +1,135 @@
+extern crate data_encoding;
+extern crate serde_json;
+
+use self::data_encoding::HEXLOWER;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// HPKE (RFC 9180) test vectors
+#[derive(Debug)]
+pub struct HpkeExample {
+    pub name: String,
+    pub mode: u8,
+    pub aead_id: u16,
+    pub info: Vec<u8>,
+    pub ikm_r: Vec<u8>,
+    pub ikm_s: Option<Vec<u8>>,
+    pub ikm_e: Vec<u8>,
+    pub sk_rm: Vec<u8>,
+    pub sk_sm: Option<Vec<u8>>,
+    pub pk_rm: Vec<u8>,
+    pub pk_sm: Option<Vec<u8>>,
+    pub psk: Option<Vec<u8>>,
+    pub psk_id: Option<Vec<u8>>,
+    pub enc: Vec<u8>,
+    pub encryptions: Vec<Encryption>,
+    pub exports: Vec<Export>,
+}
+
+#[derive(Debug)]
+pub struct Encryption {
+    pub sequence_number: u64,
+    pub aad: Vec<u8>,
+    pub plaintext: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct Export {
+    pub exporter_context: Vec<u8>,
+    pub length: usize,
+    pub exported_value: Vec<u8>,
+}
+
+impl HpkeExample {
+    /// Load examples from hpke.tjson
+    pub fn load_all() -> Vec<Self> {
+        Self::load_from_file(Path::new("../vectors/hpke.tjson"))
+    }
+
+    /// Load examples from a file at the given path
+    pub fn load_from_file(path: &Path) -> Vec<Self> {
+        let mut file = File::open(path).expect("valid hpke.tjson");
+        let mut tjson_string = String::new();
+
+        file.read_to_string(&mut tjson_string).expect(
+            "hpke.tjson read successfully",
+        );
+
+        let tjson: serde_json::Value =
+            serde_json::from_str(&tjson_string).expect("hpke.tjson parses successfully");
+
+        let examples = &tjson["examples:A<O>"].as_array().expect(
+            "hpke.tjson examples array",
+        );
+
+        examples
+            .iter()
+            .map(|ex| {
+                Self {
+                    name: ex["name:s"].as_str().expect("example name").to_owned(),
+                    mode: uint(&ex["mode:u"]) as u8,
+                    aead_id: uint(&ex["aead_id:u"]) as u16,
+                    info: hex(&ex["info:d16"]),
+                    ikm_r: hex(&ex["ikmR:d16"]),
+                    ikm_s: optional_hex(&ex["ikmS:d16"]),
+                    ikm_e: hex(&ex["ikmE:d16"]),
+                    sk_rm: hex(&ex["skRm:d16"]),
+                    sk_sm: optional_hex(&ex["skSm:d16"]),
+                    pk_rm: hex(&ex["pkRm:d16"]),
+                    pk_sm: optional_hex(&ex["pkSm:d16"]),
+                    psk: optional_hex(&ex["psk:d16"]),
+                    psk_id: optional_hex(&ex["psk_id:d16"]),
+                    enc: hex(&ex["enc:d16"]),
+                    encryptions: ex["encryptions:A<O>"]
+                        .as_array()
+                        .expect("encoded example")
+                        .iter()
+                        .map(|enc| {
+                            Encryption {
+                                sequence_number: uint(&enc["sequence_number:u"]),
+                                aad: hex(&enc["aad:d16"]),
+                                plaintext: hex(&enc["pt:d16"]),
+                                ciphertext: hex(&enc["ct:d16"]),
+                            }
+                        })
+                        .collect(),
+                    exports: ex["exports:A<O>"]
+                        .as_array()
+                        .expect("encoded example")
+                        .iter()
+                        .map(|export| {
+                            Export {
+                                exporter_context: hex(&export["exporter_context:d16"]),
+                                length: uint(&export["L:u"]) as usize,
+                                exported_value: hex(&export["exported_value:d16"]),
+                            }
+                        })
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Decode a TJSON `d16` (hex) value
+fn hex(value: &serde_json::Value) -> Vec<u8> {
+    HEXLOWER
+        .decode(value.as_str().expect("encoded example").as_bytes())
+        .expect("hex encoded")
+}
+
+/// Decode a TJSON `d16` value which may be absent
+fn optional_hex(value: &serde_json::Value) -> Option<Vec<u8>> {
+    value.as_str().map(|s| {
+        HEXLOWER.decode(s.as_bytes()).expect("hex encoded")
+    })
+}
+
+/// Decode a TJSON `u` value (an unsigned integer serialized as a string)
+fn uint(value: &serde_json::Value) -> u64 {
+    value.as_str().expect("encoded example").parse().expect(
+        "unsigned integer",
+    )
+}
@@ -0,0 +1,394 @@
+//! Cross-language interop tests: every implementation encrypts random inputs
+//! and every other implementation decrypts them.
+//!
+//! The Go, JavaScript, Python and Ruby ports are driven through small
+//! programs which read a JSON request on stdin and write a JSON response to
+//! stdout (see README.md). Ports whose toolchain isn't installed are skipped,
+//! unless they're listed in `XSTREAM_INTEROP_REQUIRED` (e.g. `go,python`).
+
+#![cfg(not(target_arch = "wasm32"))]
+
+extern crate data_encoding;
+extern crate rand;
+#[macro_use]
+extern crate serde_json;
+extern crate xstream;
+
+use data_encoding::HEXLOWER;
+use rand::{OsRng, Rng};
+use serde_json::Value;
+use std::env;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use xstream::{Algorithm, DynDecryptor, DynEncryptor, PrivateKey, PublicKey};
+
+/// Algorithms supported by every port
+const ALGORITHMS: &[Algorithm] = &[
+    Algorithm::X25519HkdfSha256Aes128Siv,
+    Algorithm::X25519HkdfSha256Aes128PmacSiv,
+];
+
+/// Number of random streams each implementation encrypts per algorithm
+const ITERATIONS: usize = 4;
+
+/// An implementation of `XSTREAM` which can be sent requests
+enum Implementation {
+    /// This crate, called in-process
+    Rust,
+
+    /// Another port, called through its driver program
+    Port {
+        name: &'static str,
+        dir: PathBuf,
+        program: String,
+        args: Vec<String>,
+    },
+}
+
+impl Implementation {
+    fn name(&self) -> &'static str {
+        match *self {
+            Implementation::Rust => "rust",
+            Implementation::Port { name, .. } => name,
+        }
+    }
+
+    /// Send a request, returning the response
+    fn call(&self, request: &Value) -> Value {
+        let (name, dir, program, args) = match *self {
+            Implementation::Rust => return rust_driver(request),
+            Implementation::Port {
+                name,
+                ref dir,
+                ref program,
+                ref args,
+            } => (name, dir, program, args),
+        };
+
+        let mut child = Command::new(program)
+            .args(args)
+            .current_dir(dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap_or_else(|e| panic!("{}: failed to run driver: {}", name, e));
+
+        let mut pipe = child.stdin.take().unwrap();
+        let input = request.to_string();
+        let writer = thread::spawn(move || {
+            let _ = pipe.write_all(input.as_bytes());
+        });
+
+        let output = child.wait_with_output().unwrap();
+        writer.join().unwrap();
+
+        assert!(
+            output.status.success(),
+            "{} driver failed on {}:\n{}",
+            name,
+            request,
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        serde_json::from_slice(&output.stdout).unwrap_or_else(|e| {
+            panic!(
+                "{} driver wrote invalid JSON ({}): {}",
+                name,
+                e,
+                String::from_utf8_lossy(&output.stdout)
+            )
+        })
+    }
+}
+
+/// Handle a request with this crate, following the same protocol as the
+/// other ports' drivers
+fn rust_driver(request: &Value) -> Value {
+    let algorithm: Algorithm = request["algorithm"].as_str().unwrap().parse().unwrap();
+    let salt = unhex_opt(&request["salt"]);
+    let segments = request["segments"].as_array().unwrap();
+
+    match request["command"].as_str().unwrap() {
+        "encrypt" => {
+            let public_key = PublicKey::from_slice(&unhex(&request["public_key"])).unwrap();
+            let (mut encryptor, ephemeral_key) = DynEncryptor::new(
+                algorithm,
+                &mut OsRng::new().unwrap(),
+                &public_key,
+                salt.as_deref(),
+            )
+            .unwrap();
+
+            let (last, rest) = segments.split_last().unwrap();
+            let mut ciphertexts: Vec<String> = rest
+                .iter()
+                .map(|segment| {
+                    let ciphertext =
+                        encryptor.seal_next(&unhex(&segment["ad"]), &unhex(&segment["plaintext"]));
+                    tohex(&ciphertext)
+                })
+                .collect();
+            ciphertexts.push(tohex(
+                &encryptor.seal_last(&unhex(&last["ad"]), &unhex(&last["plaintext"])),
+            ));
+
+            json!({
+                "ephemeral_key": tohex(ephemeral_key.as_bytes()),
+                "ciphertexts": ciphertexts,
+            })
+        }
+        "decrypt" => {
+            let private_key = PrivateKey::new(&unhex(&request["private_key"]));
+            let ephemeral_key = PublicKey::from_slice(&unhex(&request["ephemeral_key"])).unwrap();
+            let mut decryptor =
+                DynDecryptor::new(algorithm, &private_key, &ephemeral_key, salt.as_deref())
+                    .unwrap();
+
+            let (last, rest) = segments.split_last().unwrap();
+            let mut plaintexts = vec![];
+
+            for segment in rest {
+                let ciphertext = unhex(&segment["ciphertext"]);
+                match decryptor.open_next(&unhex(&segment["ad"]), &ciphertext) {
+                    Ok(plaintext) => plaintexts.push(tohex(&plaintext)),
+                    Err(_) => return json!({ "error": "authentication failed" }),
+                }
+            }
+
+            match decryptor.open_last(&unhex(&last["ad"]), &unhex(&last["ciphertext"])) {
+                Ok(plaintext) => plaintexts.push(tohex(&plaintext)),
+                Err(_) => return json!({ "error": "authentication failed" }),
+            }
+
+            json!({ "plaintexts": plaintexts })
+        }
+        command => panic!("unknown command: {}", command),
+    }
+}
+
+/// Find the ports whose toolchains are installed, panicking if one listed in
+/// `XSTREAM_INTEROP_REQUIRED` is missing
+fn implementations(tmp: &Path) -> Vec<Implementation> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+    let required = env::var("XSTREAM_INTEROP_REQUIRED").unwrap_or_default();
+    let mut implementations = vec![Implementation::Rust];
+
+    let go_driver = tmp.join("xstream-interop");
+    let ports = vec![
+        (
+            "go",
+            root.join("go"),
+            vec![
+                "go",
+                "build",
+                "-o",
+                go_driver.to_str().unwrap(),
+                "./cmd/xstream-interop",
+            ],
+            go_driver.to_str().unwrap().to_owned(),
+            vec![],
+        ),
+        (
+            "js",
+            root.join("js"),
+            vec![
+                "node",
+                "-e",
+                "require.resolve('ts-node'); require('miscreant')",
+            ],
+            "node".to_owned(),
+            vec!["--require", "ts-node/register", "interop/driver.ts"],
+        ),
+        (
+            "python",
+            root.join("python"),
+            vec!["python3", "-c", "import xstream"],
+            "python3".to_owned(),
+            vec!["interop.py"],
+        ),
+        (
+            "ruby",
+            root.join("ruby"),
+            vec!["ruby", "-Ilib", "-e", "require 'xstream'"],
+            "ruby".to_owned(),
+            vec!["bin/xstream-interop"],
+        ),
+    ];
+
+    for (name, dir, probe, program, args) in ports {
+        let available = match Command::new(probe[0])
+            .args(&probe[1..])
+            .current_dir(&dir)
+            .output()
+        {
+            Ok(ref output) if output.status.success() => true,
+            Ok(output) => {
+                eprintln!(
+                    "skipping {}: `{}` failed:\n{}",
+                    name,
+                    probe.join(" "),
+                    String::from_utf8_lossy(&output.stderr)
+                );
+                false
+            }
+            Err(e) => {
+                eprintln!("skipping {}: couldn't run `{}`: {}", name, probe[0], e);
+                false
+            }
+        };
+
+        if !available {
+            assert!(
+                !required.split(',').any(|r| r.trim() == name),
+                "{} is listed in XSTREAM_INTEROP_REQUIRED but unavailable",
+                name
+            );
+            continue;
+        }
+
+        implementations.push(Implementation::Port {
+            name,
+            dir,
+            program,
+            args: args.into_iter().map(String::from).collect(),
+        });
+    }
+
+    implementations
+}
+
+#[test]
+fn every_implementation_decrypts_every_other() {
+    let tmp = env::temp_dir().join(format!("xstream-interop-test-{}", std::process::id()));
+    std::fs::create_dir_all(&tmp).unwrap();
+
+    let implementations = implementations(&tmp);
+    let mut rng = OsRng::new().unwrap();
+
+    for &algorithm in ALGORITHMS {
+        for encryptor in &implementations {
+            for _ in 0..ITERATIONS {
+                let private_key = PrivateKey::generate(&mut rng);
+                let salt = if rng.gen() {
+                    Some(tohex(&random_bytes(&mut rng, 32)))
+                } else {
+                    None
+                };
+
+                let inputs: Vec<(String, Vec<u8>)> = (0..rng.gen_range(1, 5))
+                    .map(|_| {
+                        let ad = random_bytes(&mut rng, 16);
+                        (tohex(&ad), random_bytes(&mut rng, 256))
+                    })
+                    .collect();
+
+                let sealed = encryptor.call(&json!({
+                    "command": "encrypt",
+                    "algorithm": algorithm.as_str(),
+                    "public_key": tohex(private_key.public_key().as_bytes()),
+                    "salt": salt,
+                    "segments": inputs
+                        .iter()
+                        .map(|(ad, plaintext)| json!({
+                            "ad": ad,
+                            "plaintext": tohex(plaintext),
+                        }))
+                        .collect::<Vec<_>>(),
+                }));
+
+                let ciphertexts = sealed["ciphertexts"].as_array().unwrap_or_else(|| {
+                    panic!("{} failed to encrypt: {}", encryptor.name(), sealed)
+                });
+                assert_eq!(ciphertexts.len(), inputs.len());
+
+                let mut segments: Vec<Value> = inputs
+                    .iter()
+                    .zip(ciphertexts)
+                    .map(|((ad, _), ciphertext)| {
+                        json!({
+                            "ad": ad,
+                            "ciphertext": ciphertext,
+                        })
+                    })
+                    .collect();
+
+                let mut request = json!({
+                    "command": "decrypt",
+                    "algorithm": algorithm.as_str(),
+                    "private_key": tohex(private_key.as_bytes()),
+                    "ephemeral_key": sealed["ephemeral_key"],
+                    "salt": salt,
+                    "segments": segments,
+                });
+
+                for decryptor in &implementations {
+                    let context = format!(
+                        "{} encrypted, {} decrypted ({})",
+                        encryptor.name(),
+                        decryptor.name(),
+                        algorithm
+                    );
+
+                    let opened = decryptor.call(&request);
+                    let plaintexts: Vec<Vec<u8>> = opened["plaintexts"]
+                        .as_array()
+                        .unwrap_or_else(|| panic!("{}: {}", context, opened))
+                        .iter()
+                        .map(unhex)
+                        .collect();
+
+                    assert_eq!(plaintexts.len(), inputs.len(), "{}", context);
+                    for (plaintext, (_, expected)) in plaintexts.iter().zip(&inputs) {
+                        assert_eq!(plaintext, expected, "{}", context);
+                    }
+                }
+
+                // Tampering with the last segment must be detected everywhere
+                let mut tampered = unhex(&segments.last().unwrap()["ciphertext"]);
+                tampered[0] ^= 1;
+                segments.last_mut().unwrap()["ciphertext"] = json!(tohex(&tampered));
+                request["segments"] = json!(segments);
+
+                for decryptor in &implementations {
+                    let opened = decryptor.call(&request);
+                    assert!(
+                        opened["error"].is_string(),
+                        "{} encrypted, {} decrypted a tampered stream ({})",
+                        encryptor.name(),
+                        decryptor.name(),
+                        algorithm
+                    );
+                }
+            }
+        }
+    }
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+fn random_bytes<R: Rng>(rng: &mut R, max_len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; rng.gen_range(0, max_len + 1)];
+    rng.fill_bytes(&mut bytes);
+    bytes
+}
+
+fn unhex(value: &Value) -> Vec<u8> {
+    HEXLOWER
+        .decode(value.as_str().expect("hex string").as_bytes())
+        .expect("valid hex")
+}
+
+fn unhex_opt(value: &Value) -> Option<Vec<u8>> {
+    if value.is_null() {
+        None
+    } else {
+        Some(unhex(value))
+    }
+}
+
+fn tohex(bytes: &[u8]) -> String {
+    HEXLOWER.encode(bytes)
+}
@@ -0,0 +1,143 @@
+extern crate rand;
+extern crate xstream;
+
+mod fixture;
+mod test_rng;
+
+use fixture::{opener, sealer, seal_framed, segment_reader};
+use std::io::{Read, Seek, SeekFrom};
+use xstream::container;
+use xstream::{Padding, XStreamBuilder};
+
+const SEGMENT_SIZE: usize = 16;
+
+fn builder(padding: Padding) -> XStreamBuilder {
+    XStreamBuilder::new().segment_size(SEGMENT_SIZE).padding(padding)
+}
+
+#[test]
+fn padded_lengths() {
+    let padme: Vec<u64> = [0, 1, 8, 9, 100, 1000, 1_000_000]
+        .iter()
+        .map(|&len| Padding::Padme.padded_len(len).unwrap())
+        .collect();
+
+    assert_eq!(padme, [1, 2, 10, 10, 104, 1024, 1_015_808]);
+    assert_eq!(Padding::Bucket(64).padded_len(0), Some(64));
+    assert_eq!(Padding::Bucket(64).padded_len(63), Some(64));
+    assert_eq!(Padding::Bucket(64).padded_len(64), Some(128));
+    assert_eq!(Padding::Bucket(0).padded_len(1), None);
+    assert_eq!(Padding::Padme.padded_len(u64::MAX), None);
+}
+
+#[test]
+fn seal_and_open_padded() {
+    let padding = Padding::Bucket(40);
+    let (mut sealer, ephemeral_pk) = sealer(builder(padding)).unwrap();
+
+    // Plaintext segments ending in bytes which resemble padding
+    let first = [0xaa; SEGMENT_SIZE];
+    let mut second = [0u8; SEGMENT_SIZE];
+    second[0] = 0x80;
+
+    let mut ciphertexts = vec![
+        sealer.seal_next(b"", &first).unwrap(),
+        sealer.seal_next(b"", &second).unwrap(),
+    ];
+    ciphertexts.extend(sealer.finish(b"", b"abc").unwrap());
+
+    // 35 bytes of plaintext padded to 40 bytes spans 3 segments
+    assert_eq!(ciphertexts.len(), 3);
+
+    let mut opener = opener(builder(padding), &ephemeral_pk).unwrap();
+
+    let (last, segments) = ciphertexts.split_last().unwrap();
+    let mut plaintext = Vec::new();
+
+    for segment in segments {
+        plaintext.extend(opener.open_next(b"", segment).unwrap());
+    }
+
+    plaintext.extend(opener.open_last(b"", last).unwrap());
+
+    let mut expected = Vec::new();
+    expected.extend_from_slice(&first);
+    expected.extend_from_slice(&second);
+    expected.extend_from_slice(b"abc");
+    assert_eq!(plaintext, expected);
+}
+
+#[test]
+fn zero_plaintext_not_withheld() {
+    const LARGE_SEGMENT_SIZE: usize = 4096;
+
+    let builder = || {
+        XStreamBuilder::new()
+            .segment_size(LARGE_SEGMENT_SIZE)
+            .padding(Padding::Padme)
+    };
+
+    let (mut sealer, ephemeral_pk) = sealer(builder()).unwrap();
+
+    // 1 MiB of zeros, e.g. a sparse file, which is indistinguishable from
+    // padding until the end of the stream
+    let segment = [0u8; LARGE_SEGMENT_SIZE];
+    let mut ciphertexts: Vec<Vec<u8>> =
+        (0..256).map(|_| sealer.seal_next(b"", &segment).unwrap()).collect();
+    ciphertexts.extend(sealer.finish(b"", b"").unwrap());
+
+    let mut opener = opener(builder(), &ephemeral_pk).unwrap();
+
+    let (last, segments) = ciphertexts.split_last().unwrap();
+    let mut opened = 0;
+    let mut released = 0;
+
+    for ciphertext in segments {
+        opened += LARGE_SEGMENT_SIZE;
+        released += opener.open_next(b"", ciphertext).unwrap().len();
+
+        // Only as much as PADMÉ could have added (under 12%) is withheld
+        assert!(
+            opened - released <= opened / 8 + 1,
+            "{} of {} bytes withheld",
+            opened - released,
+            opened
+        );
+    }
+
+    released += opener.open_last(b"", last).unwrap().len();
+    assert_eq!(released, 256 * LARGE_SEGMENT_SIZE);
+}
+
+#[test]
+fn padding_segments() {
+    let plaintext: Vec<u8> = (0..20u8).collect();
+    let padding = Padding::Bucket(64);
+    let (ephemeral_pk, framed) = seal_framed(builder(padding), &plaintext);
+
+    // Padding fills 4 segments of 16 bytes, each framed and authenticated
+    let header_len = container::inspect(&framed).unwrap().header_len;
+    assert_eq!(framed.len(), header_len + 4 * (5 + SEGMENT_SIZE + 16));
+
+    let mut reader = segment_reader(builder(padding), &ephemeral_pk, framed).unwrap();
+    assert_eq!(reader.plaintext_len(), plaintext.len() as u64);
+
+    let mut decrypted = Vec::new();
+    reader.read_to_end(&mut decrypted).unwrap();
+    assert_eq!(decrypted, plaintext);
+
+    let mut tail = Vec::new();
+    reader.seek(SeekFrom::End(-4)).unwrap();
+    reader.read_to_end(&mut tail).unwrap();
+    assert_eq!(tail, &plaintext[16..]);
+}
+
+#[test]
+fn padding_required() {
+    let (unpadded, ephemeral_pk) = sealer(XStreamBuilder::new()).unwrap();
+    let ciphertext = unpadded.seal_last(b"", b"unpadded").unwrap();
+
+    let padded = opener(XStreamBuilder::new().padding(Padding::Padme), &ephemeral_pk).unwrap();
+    assert!(padded.open_last(b"", &ciphertext).is_err());
+    assert!(sealer(XStreamBuilder::new().padding(Padding::Bucket(0))).is_err());
+}
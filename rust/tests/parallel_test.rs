@@ -0,0 +1,119 @@
+#![cfg(feature = "parallel")]
+
+extern crate rand;
+extern crate xstream;
+
+mod test_rng;
+
+use test_rng::TestRng;
+use xstream::{Algorithm, Aes128GcmSiv, Decryptor, DynDecryptor, DynEncryptor, Encryptor,
+              PrivateKey, Rekey, X25519HkdfSha256Decryptor, X25519HkdfSha256Encryptor};
+
+fn segments() -> Vec<Vec<u8>> {
+    (0..100u32).map(|i| vec![i as u8; 1 + i as usize % 37]).collect()
+}
+
+#[test]
+fn parallel_seal_matches_sequential() {
+    let sealing_pk = PrivateKey::new(&[1u8; 32]).public_key();
+    let segments = segments();
+
+    let (mut sequential, _) = X25519HkdfSha256Encryptor::<Aes128GcmSiv>::new(
+        &mut TestRng::new(&[2u8; 32]),
+        &sealing_pk,
+        None,
+    ).unwrap();
+    let mut expected: Vec<Vec<u8>> = segments[..99]
+        .iter()
+        .map(|segment| sequential.seal_next(b"ad", segment))
+        .collect();
+    expected.push(sequential.seal_last(b"ad", &segments[99]));
+
+    let (mut parallel, _) = X25519HkdfSha256Encryptor::<Aes128GcmSiv>::new(
+        &mut TestRng::new(&[2u8; 32]),
+        &sealing_pk,
+        None,
+    ).unwrap();
+    let mut ciphertexts = parallel.seal_next_par(b"ad", &segments[..40]);
+    ciphertexts.push(parallel.seal_next(b"ad", &segments[40]));
+    ciphertexts.extend(parallel.seal_last_par(b"ad", &segments[41..]));
+
+    assert_eq!(ciphertexts, expected);
+}
+
+#[test]
+fn parallel_open() {
+    let sealing_sk = PrivateKey::new(&[1u8; 32]);
+    let alg = Algorithm::X25519HkdfSha256Aes256PmacSiv;
+    let segments = segments();
+
+    let (encryptor, ephemeral_pk) =
+        DynEncryptor::new(alg, &mut TestRng::new(&[2u8; 32]), &sealing_sk.public_key(), None)
+            .unwrap();
+    let ciphertexts = encryptor.seal_last_par(b"ad", &segments);
+
+    let mut decryptor = DynDecryptor::new(alg, &sealing_sk, &ephemeral_pk, None).unwrap();
+    let mut plaintexts = decryptor.open_next_par(b"ad", &ciphertexts[..50]).unwrap();
+
+    // A failure doesn't advance the stream
+    assert!(decryptor.open_next_par(b"ad", &ciphertexts[51..60]).is_err());
+
+    plaintexts.extend(decryptor.open_last_par(b"ad", &ciphertexts[50..]).unwrap());
+    assert_eq!(plaintexts, segments);
+}
+
+#[test]
+fn parallel_open_requires_last_segment() {
+    let sealing_sk = PrivateKey::new(&[1u8; 32]);
+    let segments = segments();
+
+    let (mut encryptor, ephemeral_pk) = X25519HkdfSha256Encryptor::<Aes128GcmSiv>::new(
+        &mut TestRng::new(&[2u8; 32]),
+        &sealing_sk.public_key(),
+        None,
+    ).unwrap();
+    let ciphertexts = encryptor.seal_next_par(b"ad", &segments);
+
+    // Truncated stream: the final segment wasn't sealed as the last one
+    let decryptor =
+        X25519HkdfSha256Decryptor::<Aes128GcmSiv>::new(&sealing_sk, &ephemeral_pk, None).unwrap();
+    assert!(decryptor.open_last_par(b"ad", &ciphertexts).is_err());
+}
+
+#[test]
+fn parallel_rekey_matches_sequential() {
+    let sealing_sk = PrivateKey::new(&[1u8; 32]);
+    let segments = segments();
+
+    for &rekey in &[Rekey::Segments(7), Rekey::Bytes(300)] {
+        let (mut sequential, ephemeral_pk) = X25519HkdfSha256Encryptor::<Aes128GcmSiv>::new(
+            &mut TestRng::new(&[2u8; 32]),
+            &sealing_sk.public_key(),
+            None,
+        ).unwrap();
+        sequential.set_rekey(rekey).unwrap();
+        let mut expected: Vec<Vec<u8>> = segments[..99]
+            .iter()
+            .map(|segment| sequential.seal_next(b"ad", segment))
+            .collect();
+        expected.push(sequential.seal_last(b"ad", &segments[99]));
+
+        let (mut parallel, _) = X25519HkdfSha256Encryptor::<Aes128GcmSiv>::new(
+            &mut TestRng::new(&[2u8; 32]),
+            &sealing_sk.public_key(),
+            None,
+        ).unwrap();
+        parallel.set_rekey(rekey).unwrap();
+        let mut ciphertexts = parallel.seal_next_par(b"ad", &segments[..40]);
+        ciphertexts.extend(parallel.seal_last_par(b"ad", &segments[40..]));
+        assert_eq!(ciphertexts, expected);
+
+        let mut decryptor =
+            X25519HkdfSha256Decryptor::<Aes128GcmSiv>::new(&sealing_sk, &ephemeral_pk, None)
+                .unwrap();
+        decryptor.set_rekey(rekey).unwrap();
+        let mut plaintexts = decryptor.open_next_par(b"ad", &ciphertexts[..33]).unwrap();
+        plaintexts.extend(decryptor.open_last_par(b"ad", &ciphertexts[33..]).unwrap());
+        assert_eq!(plaintexts, segments);
+    }
+}
@@ -0,0 +1,326 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+extern crate miscreant;
+#[macro_use]
+extern crate proptest;
+extern crate rand;
+extern crate xstream;
+
+mod test_rng;
+
+use miscreant::aead::{Aes128PmacSiv, Aes128Siv, Aes256PmacSiv, Aes256Siv};
+use proptest::prelude::*;
+use proptest::sample::Index;
+use test_rng::TestRng;
+use xstream::hpke::{Aes128Gcm, Aes256Gcm, ChaCha20Poly1305, HpkeDecryptor, HpkeEncryptor};
+use xstream::{Aes128GcmSiv, Aes256GcmSiv, Decryptor, Encryptor, Error, PrivateKey, PublicKey,
+              X25519HkdfSha256Decryptor, X25519HkdfSha256Encryptor};
+
+/// Size of the authentication tag added to every segment
+const TAG_SIZE: usize = 16;
+
+/// Randomly generated inputs for a stream
+#[derive(Clone, Debug)]
+struct Inputs {
+    sealing_sk: [u8; 32],
+    ephemeral_sk: [u8; 32],
+    salt: Option<Vec<u8>>,
+    ads: Vec<Vec<u8>>,
+    plaintexts: Vec<Vec<u8>>,
+}
+
+fn inputs() -> impl Strategy<Value = Inputs> {
+    (
+        any::<[u8; 32]>(),
+        any::<[u8; 32]>(),
+        prop::option::of(prop::collection::vec(any::<u8>(), 0..32)),
+        prop::collection::vec(
+            (
+                prop::collection::vec(any::<u8>(), 0..16),
+                prop::collection::vec(any::<u8>(), 0..256),
+            ),
+            1..6,
+        ),
+    ).prop_map(|(sealing_sk, ephemeral_sk, salt, segments)| {
+        let (ads, plaintexts) = segments.into_iter().unzip();
+
+        Inputs {
+            sealing_sk,
+            ephemeral_sk,
+            salt,
+            ads,
+            plaintexts,
+        }
+    })
+}
+
+/// A single change to a sealed stream, which must make it fail to open
+#[derive(Clone, Debug)]
+enum Mutation {
+    FlipBit(Index, Index),
+    ChangeAd(Index),
+    ChangeSalt,
+    DropSegment(Index),
+    SwapSegments(Index, Index),
+}
+
+fn mutation() -> impl Strategy<Value = Mutation> {
+    prop_oneof![
+        (any::<Index>(), any::<Index>()).prop_map(|(i, bit)| Mutation::FlipBit(i, bit)),
+        any::<Index>().prop_map(Mutation::ChangeAd),
+        Just(Mutation::ChangeSalt),
+        any::<Index>().prop_map(Mutation::DropSegment),
+        (any::<Index>(), any::<Index>()).prop_map(|(i, j)| Mutation::SwapSegments(i, j)),
+    ]
+}
+
+/// A sealed stream, along with what's needed to open it
+#[derive(Clone, Debug, PartialEq)]
+struct Sealed {
+    ephemeral_pk: PublicKey,
+    salt: Option<Vec<u8>>,
+    ads: Vec<Vec<u8>>,
+    ciphertexts: Vec<Vec<u8>>,
+}
+
+fn seal<E: Encryptor<PublicKey = PublicKey>>(inputs: &Inputs) -> Sealed {
+    let sealing_pk = PrivateKey::new(&inputs.sealing_sk).public_key();
+    let mut rng = TestRng::new(&inputs.ephemeral_sk);
+    let (mut encryptor, ephemeral_pk) =
+        E::new(&mut rng, &sealing_pk, inputs.salt.as_deref()).unwrap();
+
+    let (last, rest) = inputs.plaintexts.split_last().unwrap();
+    let mut ciphertexts: Vec<Vec<u8>> = rest
+        .iter()
+        .zip(&inputs.ads)
+        .map(|(plaintext, ad)| encryptor.seal_next(ad, plaintext))
+        .collect();
+    ciphertexts.push(encryptor.seal_last(inputs.ads.last().unwrap(), last));
+
+    Sealed {
+        ephemeral_pk,
+        salt: inputs.salt.clone(),
+        ads: inputs.ads.clone(),
+        ciphertexts,
+    }
+}
+
+fn seal_in_place<E: Encryptor<PublicKey = PublicKey>>(inputs: &Inputs) -> Sealed {
+    let sealing_pk = PrivateKey::new(&inputs.sealing_sk).public_key();
+    let mut rng = TestRng::new(&inputs.ephemeral_sk);
+    let (mut encryptor, ephemeral_pk) =
+        E::new(&mut rng, &sealing_pk, inputs.salt.as_deref()).unwrap();
+
+    let mut buffers: Vec<Vec<u8>> = inputs
+        .plaintexts
+        .iter()
+        .map(|plaintext| {
+            let mut buffer = vec![0u8; plaintext.len() + TAG_SIZE];
+            buffer[..plaintext.len()].copy_from_slice(plaintext);
+            buffer
+        })
+        .collect();
+
+    {
+        let (last, rest) = buffers.split_last_mut().unwrap();
+
+        for (buffer, ad) in rest.iter_mut().zip(&inputs.ads) {
+            encryptor.seal_next_in_place(ad, buffer);
+        }
+
+        encryptor.seal_last_in_place(inputs.ads.last().unwrap(), last);
+    }
+
+    Sealed {
+        ephemeral_pk,
+        salt: inputs.salt.clone(),
+        ads: inputs.ads.clone(),
+        ciphertexts: buffers,
+    }
+}
+
+fn open<D>(sealing_sk: &[u8; 32], sealed: &Sealed) -> Result<Vec<Vec<u8>>, Error>
+where
+    D: Decryptor<PrivateKey = PrivateKey, PublicKey = PublicKey>,
+{
+    let sealing_sk = PrivateKey::new(sealing_sk);
+    let mut decryptor = D::new(&sealing_sk, &sealed.ephemeral_pk, sealed.salt.as_deref())?;
+    let (last, rest) = sealed.ciphertexts.split_last().ok_or(Error)?;
+    let mut plaintexts = Vec::new();
+
+    for (ciphertext, ad) in rest.iter().zip(&sealed.ads) {
+        plaintexts.push(decryptor.open_next(ad, ciphertext)?);
+    }
+
+    plaintexts.push(decryptor.open_last(sealed.ads.last().unwrap(), last)?);
+    Ok(plaintexts)
+}
+
+fn open_in_place<D>(sealing_sk: &[u8; 32], sealed: &Sealed) -> Result<Vec<Vec<u8>>, Error>
+where
+    D: Decryptor<PrivateKey = PrivateKey, PublicKey = PublicKey>,
+{
+    let sealing_sk = PrivateKey::new(sealing_sk);
+    let mut decryptor = D::new(&sealing_sk, &sealed.ephemeral_pk, sealed.salt.as_deref())?;
+    let mut buffers = sealed.ciphertexts.clone();
+    let (last, rest) = buffers.split_last_mut().ok_or(Error)?;
+    let mut plaintexts = Vec::new();
+
+    for (buffer, ad) in rest.iter_mut().zip(&sealed.ads) {
+        plaintexts.push(decryptor.open_next_in_place(ad, buffer)?.to_vec());
+    }
+
+    plaintexts.push(decryptor.open_last_in_place(sealed.ads.last().unwrap(), last)?.to_vec());
+    Ok(plaintexts)
+}
+
+/// Apply a mutation to a sealed stream, or return `None` if it wouldn't
+/// change the stream, or is a truncation the scheme can't detect (HPKE seals
+/// the last message like any other)
+fn mutate(sealed: &Sealed, mutation: &Mutation, detects_truncation: bool) -> Option<Sealed> {
+    let mut sealed = sealed.clone();
+    let count = sealed.ciphertexts.len();
+
+    match *mutation {
+        Mutation::FlipBit(ref i, ref bit) => {
+            let ciphertext = &mut sealed.ciphertexts[i.index(count)];
+            let bit = bit.index(ciphertext.len() * 8);
+            ciphertext[bit / 8] ^= 1 << (bit % 8);
+        }
+        Mutation::ChangeAd(ref i) => sealed.ads[i.index(count)].push(0xff),
+        Mutation::ChangeSalt => sealed.salt.get_or_insert_with(Vec::new).push(0xff),
+        Mutation::DropSegment(ref i) => {
+            let i = i.index(count);
+
+            if count < 2 || (i == count - 1 && !detects_truncation) {
+                return None;
+            }
+
+            sealed.ads.remove(i);
+            sealed.ciphertexts.remove(i);
+        }
+        Mutation::SwapSegments(ref i, ref j) => {
+            let (i, j) = (i.index(count), j.index(count));
+
+            if i == j {
+                return None;
+            }
+
+            sealed.ads.swap(i, j);
+            sealed.ciphertexts.swap(i, j);
+        }
+    }
+
+    Some(sealed)
+}
+
+macro_rules! stream_properties {
+    ($name:ident, $encryptor:ty, $decryptor:ty, $detects_truncation:expr) => {
+        mod $name {
+            use super::*;
+
+            proptest! {
+                #![proptest_config(ProptestConfig::with_cases(64))]
+
+                #[test]
+                fn round_trip(inputs in inputs()) {
+                    let sealed = seal::<$encryptor>(&inputs);
+
+                    for (ciphertext, plaintext) in sealed.ciphertexts.iter().zip(&inputs.plaintexts) {
+                        prop_assert_eq!(ciphertext.len(), plaintext.len() + TAG_SIZE);
+                    }
+
+                    prop_assert_eq!(
+                        open::<$decryptor>(&inputs.sealing_sk, &sealed).unwrap(),
+                        inputs.plaintexts
+                    );
+                }
+
+                #[test]
+                fn mutation_fails(inputs in inputs(), mutation in mutation()) {
+                    let sealed = seal::<$encryptor>(&inputs);
+                    let mutated = mutate(&sealed, &mutation, $detects_truncation);
+                    prop_assume!(mutated.is_some());
+
+                    let mutated = mutated.unwrap();
+                    prop_assert!(open::<$decryptor>(&inputs.sealing_sk, &mutated).is_err());
+                    prop_assert!(open_in_place::<$decryptor>(&inputs.sealing_sk, &mutated).is_err());
+                }
+
+                #[test]
+                fn in_place_matches_allocating(inputs in inputs()) {
+                    let sealed = seal::<$encryptor>(&inputs);
+                    prop_assert_eq!(&seal_in_place::<$encryptor>(&inputs), &sealed);
+
+                    prop_assert_eq!(
+                        open_in_place::<$decryptor>(&inputs.sealing_sk, &sealed).unwrap(),
+                        inputs.plaintexts
+                    );
+                }
+            }
+        }
+    };
+}
+
+stream_properties!(
+    aes128siv,
+    X25519HkdfSha256Encryptor<Aes128Siv>,
+    X25519HkdfSha256Decryptor<Aes128Siv>,
+    true
+);
+
+stream_properties!(
+    aes256siv,
+    X25519HkdfSha256Encryptor<Aes256Siv>,
+    X25519HkdfSha256Decryptor<Aes256Siv>,
+    true
+);
+
+stream_properties!(
+    aes128pmacsiv,
+    X25519HkdfSha256Encryptor<Aes128PmacSiv>,
+    X25519HkdfSha256Decryptor<Aes128PmacSiv>,
+    true
+);
+
+stream_properties!(
+    aes256pmacsiv,
+    X25519HkdfSha256Encryptor<Aes256PmacSiv>,
+    X25519HkdfSha256Decryptor<Aes256PmacSiv>,
+    true
+);
+
+stream_properties!(
+    aes128gcmsiv,
+    X25519HkdfSha256Encryptor<Aes128GcmSiv>,
+    X25519HkdfSha256Decryptor<Aes128GcmSiv>,
+    true
+);
+
+stream_properties!(
+    aes256gcmsiv,
+    X25519HkdfSha256Encryptor<Aes256GcmSiv>,
+    X25519HkdfSha256Decryptor<Aes256GcmSiv>,
+    true
+);
+
+stream_properties!(
+    hpke_aes128gcm,
+    HpkeEncryptor<Aes128Gcm>,
+    HpkeDecryptor<Aes128Gcm>,
+    false
+);
+
+stream_properties!(
+    hpke_aes256gcm,
+    HpkeEncryptor<Aes256Gcm>,
+    HpkeDecryptor<Aes256Gcm>,
+    false
+);
+
+stream_properties!(
+    hpke_chacha20poly1305,
+    HpkeEncryptor<ChaCha20Poly1305>,
+    HpkeDecryptor<ChaCha20Poly1305>,
+    false
+);
@@ -0,0 +1,206 @@
+extern crate rand;
+extern crate xstream;
+
+mod fixture;
+mod test_rng;
+
+use fixture::{opener, rng, sealer, sealing_key, seal_framed, segment_reader};
+use std::io::Read;
+use xstream::{Aes128GcmSiv, Decryptor, Encryptor, PublicKey, Rekey, X25519HkdfSha256Decryptor,
+              X25519HkdfSha256Encryptor, XStreamBuilder};
+
+fn segments() -> Vec<Vec<u8>> {
+    (0..10u32).map(|i| vec![i as u8; 1 + i as usize % 7]).collect()
+}
+
+fn seal(rekey: Option<Rekey>) -> (PublicKey, Vec<Vec<u8>>) {
+    let (mut encryptor, ephemeral_pk) = X25519HkdfSha256Encryptor::<Aes128GcmSiv>::new(
+        &mut rng(),
+        &sealing_key().public_key(),
+        None,
+    ).unwrap();
+
+    if let Some(rekey) = rekey {
+        encryptor.set_rekey(rekey).unwrap();
+    }
+
+    let segments = segments();
+    let (last, rest) = segments.split_last().unwrap();
+    let mut ciphertexts: Vec<Vec<u8>> = rest
+        .iter()
+        .map(|segment| encryptor.seal_next(b"ad", segment))
+        .collect();
+    ciphertexts.push(encryptor.seal_last(b"ad", last));
+
+    (ephemeral_pk, ciphertexts)
+}
+
+fn open(
+    rekey: Option<Rekey>,
+    ephemeral_pk: &PublicKey,
+    ciphertexts: &[Vec<u8>],
+) -> Result<Vec<Vec<u8>>, xstream::Error> {
+    let mut decryptor =
+        X25519HkdfSha256Decryptor::<Aes128GcmSiv>::new(&sealing_key(), ephemeral_pk, None)
+            .unwrap();
+
+    if let Some(rekey) = rekey {
+        decryptor.set_rekey(rekey).unwrap();
+    }
+
+    let (last, rest) = ciphertexts.split_last().unwrap();
+    let mut plaintexts = Vec::new();
+
+    for ciphertext in rest {
+        plaintexts.push(decryptor.open_next(b"ad", ciphertext)?);
+    }
+
+    plaintexts.push(decryptor.open_last(b"ad", last)?);
+    Ok(plaintexts)
+}
+
+#[test]
+fn rekey_by_segments() {
+    let rekey = Some(Rekey::Segments(3));
+    let (_, plain) = seal(None);
+    let (ephemeral_pk, ciphertexts) = seal(rekey);
+
+    // Segments sealed under the initial key are unchanged, and later ones differ
+    assert_eq!(ciphertexts[..3], plain[..3]);
+    assert!(ciphertexts[3..].iter().zip(&plain[3..]).all(|(a, b)| a != b));
+
+    assert_eq!(
+        open(rekey, &ephemeral_pk, &ciphertexts).unwrap(),
+        segments()
+    );
+
+    // The decryptor must follow the same schedule
+    assert!(open(None, &ephemeral_pk, &ciphertexts).is_err());
+    assert!(open(Some(Rekey::Segments(4)), &ephemeral_pk, &ciphertexts).is_err());
+}
+
+#[test]
+fn rekey_by_bytes() {
+    let rekey = Some(Rekey::Bytes(40));
+    let (ephemeral_pk, ciphertexts) = seal(rekey);
+
+    assert_eq!(
+        open(rekey, &ephemeral_pk, &ciphertexts).unwrap(),
+        segments()
+    );
+    assert!(open(Some(Rekey::Bytes(35)), &ephemeral_pk, &ciphertexts).is_err());
+
+    // Random access can't locate keys when rekeying by bytes
+    let mut decryptor =
+        X25519HkdfSha256Decryptor::<Aes128GcmSiv>::new(&sealing_key(), &ephemeral_pk, None)
+            .unwrap();
+    decryptor.set_rekey(Rekey::Bytes(40)).unwrap();
+    assert!(decryptor.open_at(0, false, b"ad", &ciphertexts[0]).is_err());
+}
+
+#[test]
+fn rekey_random_access() {
+    let (ephemeral_pk, ciphertexts) = seal(Some(Rekey::Segments(3)));
+    let segments = segments();
+
+    let mut decryptor =
+        X25519HkdfSha256Decryptor::<Aes128GcmSiv>::new(&sealing_key(), &ephemeral_pk, None)
+            .unwrap();
+    decryptor.set_rekey(Rekey::Segments(3)).unwrap();
+
+    assert_eq!(decryptor.open_at(7, false, b"ad", &ciphertexts[7]).unwrap(), segments[7]);
+    assert_eq!(decryptor.open_at(9, true, b"ad", &ciphertexts[9]).unwrap(), segments[9]);
+
+    for i in 0..4 {
+        assert_eq!(decryptor.open_next(b"ad", &ciphertexts[i]).unwrap(), segments[i]);
+    }
+
+    // Keys for earlier segments have been erased
+    assert!(decryptor.open_at(1, false, b"ad", &ciphertexts[1]).is_err());
+    assert_eq!(decryptor.open_at(4, false, b"ad", &ciphertexts[4]).unwrap(), segments[4]);
+}
+
+#[test]
+fn rekey_invalid_schedules() {
+    let (mut encryptor, _) = X25519HkdfSha256Encryptor::<Aes128GcmSiv>::new(
+        &mut rng(),
+        &sealing_key().public_key(),
+        None,
+    ).unwrap();
+
+    assert!(encryptor.set_rekey(Rekey::Segments(0)).is_err());
+    assert!(encryptor.set_rekey(Rekey::Bytes(0)).is_err());
+
+    // The schedule can't change once segments have been sealed
+    encryptor.seal_next(b"", b"segment");
+    assert!(encryptor.set_rekey(Rekey::Segments(1)).is_err());
+
+    assert!(sealer(XStreamBuilder::new().rekey(Rekey::Segments(0))).is_err());
+}
+
+#[test]
+fn rekey_checkpoint_and_resume() {
+    let rekey = Some(Rekey::Segments(2));
+    let state_key = [0x5a; xstream::STATE_KEY_SIZE];
+
+    let (mut encryptor, ephemeral_pk) = X25519HkdfSha256Encryptor::<Aes128GcmSiv>::new(
+        &mut rng(),
+        &sealing_key().public_key(),
+        None,
+    ).unwrap();
+    encryptor.set_rekey(Rekey::Segments(2)).unwrap();
+
+    let segments = segments();
+    let mut ciphertexts: Vec<Vec<u8>> = segments[..5]
+        .iter()
+        .map(|segment| encryptor.seal_next(b"ad", segment))
+        .collect();
+
+    let blob = encryptor.checkpoint(&state_key);
+    let mut encryptor =
+        X25519HkdfSha256Encryptor::<Aes128GcmSiv>::resume(&state_key, &blob, &mut 0).unwrap();
+
+    for segment in &segments[5..9] {
+        ciphertexts.push(encryptor.seal_next(b"ad", segment));
+    }
+
+    ciphertexts.push(encryptor.seal_last(b"ad", &segments[9]));
+
+    assert_eq!(
+        open(rekey, &ephemeral_pk, &ciphertexts).unwrap(),
+        segments
+    );
+}
+
+#[test]
+fn rekey_builder() {
+    let builder = || XStreamBuilder::new().rekey(Rekey::Segments(1));
+    let (mut sealer, ephemeral_pk) = sealer(builder()).unwrap();
+
+    let first = sealer.seal_next(b"", b"first").unwrap();
+    let second = sealer.seal_next(b"", b"second").unwrap();
+    let last = sealer.seal_last(b"", b"last").unwrap();
+
+    let mut opener = opener(builder(), &ephemeral_pk).unwrap();
+
+    assert_eq!(opener.open_next(b"", &first).unwrap(), b"first");
+    assert_eq!(opener.open_next(b"", &second).unwrap(), b"second");
+    assert_eq!(opener.open_last(b"", &last).unwrap(), b"last");
+}
+
+#[test]
+fn rekey_framed() {
+    let builder = |rekey| XStreamBuilder::new().segment_size(16).rekey(rekey);
+
+    // Segment readers locate keys by index, which rekeying by bytes can't do
+    assert!(sealer(builder(Rekey::Bytes(64))).is_err());
+    assert!(opener(builder(Rekey::Bytes(64)), &sealing_key().public_key()).is_err());
+
+    let plaintext: Vec<u8> = (0..100u8).collect();
+    let (ephemeral_pk, framed) = seal_framed(builder(Rekey::Segments(2)), &plaintext);
+
+    let mut reader = segment_reader(builder(Rekey::Segments(2)), &ephemeral_pk, framed).unwrap();
+    let mut decrypted = Vec::new();
+    reader.read_to_end(&mut decrypted).unwrap();
+    assert_eq!(decrypted, plaintext);
+}
@@ -0,0 +1,126 @@
+extern crate rand;
+extern crate xstream;
+
+mod fixture;
+mod test_rng;
+
+use fixture::{opener, sealer, seal_framed, segment_reader};
+use std::io::Read;
+use xstream::container;
+use xstream::{SigningKey, VerifyingKey, XStreamBuilder};
+
+const SEGMENT_SIZE: usize = 16;
+
+fn signing_key() -> SigningKey {
+    SigningKey::new(&[7u8; 32])
+}
+
+fn sealer_builder() -> XStreamBuilder {
+    XStreamBuilder::new().salt(b"release").signing_key(&signing_key())
+}
+
+fn opener_builder(verifying_key: &VerifyingKey) -> XStreamBuilder {
+    XStreamBuilder::new().salt(b"release").verifying_key(verifying_key)
+}
+
+#[test]
+fn signed_stream() {
+    let (mut sealer, ephemeral_pk) = sealer(sealer_builder()).unwrap();
+
+    let first = sealer.seal_next(b"header", b"first").unwrap();
+    let last = sealer.seal_last(b"", b"last").unwrap();
+
+    let verifying_key = signing_key().verifying_key();
+    let signed_opener = || opener(opener_builder(&verifying_key), &ephemeral_pk).unwrap();
+
+    let mut valid = signed_opener();
+    assert_eq!(valid.open_next(b"header", &first).unwrap(), b"first");
+    assert_eq!(valid.open_last(b"", &last).unwrap(), b"last");
+
+    // The last segment isn't released if the signature is invalid
+    let mut tampered = last.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+
+    let mut invalid = signed_opener();
+    invalid.open_next(b"header", &first).unwrap();
+    assert!(invalid.open_last(b"", &tampered).is_err());
+
+    // Or if it was produced by a different publisher
+    let other_key = SigningKey::new(&[8u8; 32]).verifying_key();
+    let mut other = opener(opener_builder(&other_key), &ephemeral_pk).unwrap();
+
+    other.open_next(b"header", &first).unwrap();
+    assert!(other.open_last(b"", &last).is_err());
+
+    // Or if the stream was opened with the wrong salt
+    let mut wrong_salt =
+        opener(opener_builder(&verifying_key).salt(b"other"), &ephemeral_pk).unwrap();
+
+    assert!(wrong_salt.open_next(b"header", &first).is_err());
+}
+
+#[test]
+fn random_access_withholds_last_segment() {
+    let (mut sealer, ephemeral_pk) = sealer(sealer_builder()).unwrap();
+
+    let first = sealer.seal_next(b"", b"first").unwrap();
+    let last = sealer.seal_last(b"", b"last").unwrap();
+
+    let mut forged = last.clone();
+    *forged.last_mut().unwrap() ^= 1;
+
+    let mut signed = opener(opener_builder(&signing_key().verifying_key()), &ephemeral_pk).unwrap();
+
+    // Earlier segments can be opened in any order, but the last one can't be
+    // released without verifying the signature over every segment
+    assert_eq!(signed.open_at(0, false, b"", &first).unwrap(), b"first");
+    assert!(signed.open_at(1, true, b"", &forged).is_err());
+    assert!(signed.open_at(1, true, b"", &last).is_err());
+}
+
+#[test]
+fn unsigned_stream_rejected() {
+    let (unsigned, ephemeral_pk) = sealer(XStreamBuilder::new().salt(b"release")).unwrap();
+
+    // Padded to the length of a signature, which must still be rejected
+    let last = unsigned.seal_last(b"", &[0u8; 64]).unwrap();
+
+    let signed = opener(opener_builder(&signing_key().verifying_key()), &ephemeral_pk).unwrap();
+    assert!(signed.open_last(b"", &last).is_err());
+}
+
+#[test]
+fn signed_segment_reader() {
+    let plaintext: Vec<u8> = (0..40u8).collect();
+    let (ephemeral_pk, framed) =
+        seal_framed(sealer_builder().segment_size(SEGMENT_SIZE), &plaintext);
+
+    let reader = |framed: Vec<u8>| {
+        let builder = opener_builder(&signing_key().verifying_key()).segment_size(SEGMENT_SIZE);
+        segment_reader(builder, &ephemeral_pk, framed)
+    };
+
+    let mut decrypted = Vec::new();
+    reader(framed.clone()).unwrap().read_to_end(&mut decrypted).unwrap();
+    assert_eq!(decrypted, plaintext);
+
+    // Tampering with any segment is detected before plaintext is returned
+    let header_len = container::inspect(&framed).unwrap().header_len;
+    let mut tampered = framed.clone();
+    tampered[header_len + 10] ^= 1;
+    assert!(reader(tampered).is_err());
+}
+
+#[test]
+fn signing_options_validated() {
+    let other_key = SigningKey::new(&[8u8; 32]).verifying_key();
+
+    // Mismatched keys, or a verifying key without a signing key
+    assert!(sealer(sealer_builder().verifying_key(&other_key)).is_err());
+    assert!(sealer(XStreamBuilder::new().verifying_key(&other_key)).is_err());
+
+    assert_eq!(
+        VerifyingKey::from_bytes(other_key.as_bytes()).unwrap(),
+        other_key
+    );
+}
@@ -0,0 +1,79 @@
+// Tests for the JavaScript bindings generated by wasm-bindgen, run by
+// `wasm.sh` after it builds the Node package in pkg/
+
+"use strict";
+
+const assert = require("node:assert");
+const { test } = require("node:test");
+const { keygen, publicKey, StreamEncryptor, StreamDecryptor } = require("../../pkg/xstream.js");
+
+const ALGORITHM = "XSTREAM_X25519_HKDF_SHA256_AES128_SIV";
+const AD = new Uint8Array([0x61, 0x64]);
+const PLAINTEXT = new TextEncoder().encode("Hello, world!");
+
+function seal(key, salt) {
+  const encryptor = new StreamEncryptor(ALGORITHM, publicKey(key), salt);
+  const first = encryptor.sealNext(AD, PLAINTEXT);
+  const last = encryptor.sealLast(AD, PLAINTEXT);
+  return { ephemeralKey: encryptor.ephemeralKey, ciphertexts: [first, last] };
+}
+
+function open(key, sealed, salt) {
+  const decryptor = new StreamDecryptor(ALGORITHM, key, sealed.ephemeralKey, salt);
+  return [
+    decryptor.openNext(AD, sealed.ciphertexts[0]),
+    decryptor.openLast(AD, sealed.ciphertexts[1]),
+  ];
+}
+
+test("round trip with a salt", () => {
+  const key = keygen();
+  const salt = new Uint8Array(32).fill(7);
+  const plaintexts = open(key, seal(key, salt), salt);
+
+  assert.deepStrictEqual(plaintexts, [PLAINTEXT, PLAINTEXT]);
+});
+
+test("null and undefined both mean no salt", () => {
+  const key = keygen();
+
+  for (const sealSalt of [null, undefined]) {
+    for (const openSalt of [null, undefined]) {
+      const plaintexts = open(key, seal(key, sealSalt), openSalt);
+      assert.deepStrictEqual(plaintexts, [PLAINTEXT, PLAINTEXT]);
+    }
+  }
+
+  // An omitted salt is the same as an explicit null
+  const encryptor = new StreamEncryptor(ALGORITHM, publicKey(key));
+  const sealed = {
+    ephemeralKey: encryptor.ephemeralKey,
+    ciphertexts: [encryptor.sealNext(AD, PLAINTEXT), encryptor.sealLast(AD, PLAINTEXT)],
+  };
+  assert.deepStrictEqual(open(key, sealed, null), [PLAINTEXT, PLAINTEXT]);
+});
+
+test("errors are thrown as JavaScript errors", () => {
+  const key = keygen();
+  const sealed = seal(key, new Uint8Array(32).fill(7));
+
+  assert.throws(() => open(key, sealed, null), {
+    name: "Error",
+    message: "authentication failed",
+  });
+  assert.throws(() => new StreamEncryptor("XSTREAM_X25519_HKDF_SHA256_ROT13", publicKey(key)), {
+    name: "Error",
+    message: "unknown algorithm",
+  });
+  assert.throws(() => publicKey(new Uint8Array(31)), {
+    name: "Error",
+    message: "private key must be 32 bytes",
+  });
+
+  const encryptor = new StreamEncryptor(ALGORITHM, publicKey(key));
+  encryptor.sealLast(AD, PLAINTEXT);
+  assert.throws(() => encryptor.sealNext(AD, PLAINTEXT), {
+    name: "Error",
+    message: "stream already finished",
+  });
+});
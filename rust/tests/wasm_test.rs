@@ -0,0 +1,128 @@
+//! Tests for the `wasm` feature's JavaScript bindings, run under Node with
+//! `wasm-bindgen-test-runner` (see README.md)
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+extern crate wasm_bindgen;
+extern crate wasm_bindgen_test;
+extern crate xstream;
+
+use wasm_bindgen::JsError;
+use wasm_bindgen_test::*;
+use xstream::wasm::{keygen, public_key, WasmDecryptor, WasmEncryptor};
+use xstream::{Algorithm, PrivateKey};
+
+const SALT: &[u8] = b"salt";
+const AD: &[u8] = b"associated data";
+const MESSAGES: &[&[u8]] = &[b"Hello, ", b"world", b"!"];
+
+/// Unwrap a result whose error is a `JsError`, which doesn't implement `Debug`
+fn ok<T>(result: Result<T, JsError>) -> T {
+    result.unwrap_or_else(|_| panic!("unexpected error"))
+}
+
+/// Seal `MESSAGES` under a new key, returning the private key, ephemeral
+/// key and ciphertexts
+fn seal(algorithm: Algorithm, salt: Option<&[u8]>) -> (Vec<u8>, Vec<u8>, Vec<Vec<u8>>) {
+    let private_key = keygen();
+    let public_key = ok(public_key(&private_key));
+    let mut encryptor = ok(WasmEncryptor::new(
+        algorithm.as_str(),
+        &public_key,
+        salt.map(Vec::from),
+    ));
+
+    let (last, rest) = MESSAGES.split_last().unwrap();
+    let mut ciphertexts: Vec<Vec<u8>> = rest
+        .iter()
+        .map(|message| ok(encryptor.seal_next(AD, message)))
+        .collect();
+    ciphertexts.push(ok(encryptor.seal_last(AD, last)));
+
+    assert!(encryptor.seal_next(AD, b"").is_err());
+    (private_key, encryptor.ephemeral_key(), ciphertexts)
+}
+
+#[wasm_bindgen_test]
+fn keygen_and_public_key() {
+    let private_key = keygen();
+    assert_eq!(private_key.len(), 32);
+    assert_ne!(keygen(), private_key);
+
+    assert_eq!(
+        ok(public_key(&private_key)),
+        PrivateKey::new(&private_key).public_key().as_bytes()
+    );
+    assert!(public_key(&private_key[1..]).is_err());
+}
+
+#[wasm_bindgen_test]
+fn round_trip() {
+    for &algorithm in Algorithm::all() {
+        for salt in &[Some(SALT), None] {
+            let (private_key, ephemeral_key, ciphertexts) = seal(algorithm, *salt);
+            let mut decryptor = ok(WasmDecryptor::new(
+                algorithm.as_str(),
+                &private_key,
+                &ephemeral_key,
+                salt.map(Vec::from),
+            ));
+
+            for (i, ciphertext) in ciphertexts.iter().enumerate() {
+                assert_eq!(ciphertext.len(), MESSAGES[i].len() + 16);
+
+                let plaintext = if i + 1 < ciphertexts.len() {
+                    ok(decryptor.open_next(AD, ciphertext))
+                } else {
+                    ok(decryptor.open_last(AD, ciphertext))
+                };
+
+                assert_eq!(plaintext, MESSAGES[i]);
+            }
+
+            assert!(decryptor.open_next(AD, &ciphertexts[0]).is_err());
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn authentication_failure() {
+    let algorithm = Algorithm::X25519HkdfSha256Aes128Siv;
+    let (private_key, ephemeral_key, ciphertexts) = seal(algorithm, Some(SALT));
+    let decryptor = || {
+        ok(WasmDecryptor::new(
+            algorithm.as_str(),
+            &private_key,
+            &ephemeral_key,
+            Some(Vec::from(SALT)),
+        ))
+    };
+
+    let mut tampered = ciphertexts[0].clone();
+    tampered[0] ^= 1;
+    assert!(decryptor().open_next(AD, &tampered).is_err());
+    assert!(decryptor().open_next(b"other", &ciphertexts[0]).is_err());
+
+    // The first segment wasn't sealed as the last, so truncation is detected
+    assert!(decryptor().open_last(AD, &ciphertexts[0]).is_err());
+
+    let mut wrong_salt = ok(WasmDecryptor::new(
+        algorithm.as_str(),
+        &private_key,
+        &ephemeral_key,
+        None,
+    ));
+    assert!(wrong_salt.open_next(AD, &ciphertexts[0]).is_err());
+}
+
+#[wasm_bindgen_test]
+fn invalid_arguments() {
+    let private_key = keygen();
+    let public_key = ok(public_key(&private_key));
+    let algorithm = Algorithm::X25519HkdfSha256Aes128Siv.as_str();
+
+    assert!(WasmEncryptor::new("XSTREAM_UNKNOWN", &public_key, None).is_err());
+    assert!(WasmEncryptor::new(algorithm, &public_key[1..], None).is_err());
+    assert!(WasmEncryptor::new(algorithm, &[0u8; 32], None).is_err());
+    assert!(WasmDecryptor::new(algorithm, &private_key, &[0u8; 32], None).is_err());
+}
@@ -5,11 +5,13 @@ extern crate xstream;
 mod test_rng;
 mod xstream_vectors;
 
-use miscreant::aead::{Aes128Siv, Aes128PmacSiv};
+use miscreant::aead::{Aes128Siv, Aes128PmacSiv, Aes256Siv, Aes256PmacSiv};
 use test_rng::TestRng;
-use xstream::{Encryptor, Decryptor, PrivateKey, PublicKey};
+use xstream::{Aes128GcmSiv, Aes256GcmSiv};
+use xstream::{Algorithm, DynEncryptor, DynDecryptor};
+use xstream::{Encryptor, Decryptor, Exporter, PrivateKey, PublicKey};
 use xstream::{X25519HkdfSha256Encryptor, X25519HkdfSha256Decryptor};
-use xstream_vectors::{XStreamExample, Block};
+use xstream_vectors::{XStreamExample, XStreamInvalidExample, Block};
 
 #[test]
 fn xstream_examples_seal() {
@@ -24,14 +26,48 @@ fn xstream_examples_seal() {
         match ex.alg.as_ref() {
             "XSTREAM_X25519_HKDF_SHA256_AES128_SIV" => {
                 let (mut encryptor, pubkey) =
-                    X25519HkdfSha256Encryptor::<Aes128Siv>::new(&mut rng, &sealing_pk, salt);
+                    X25519HkdfSha256Encryptor::<Aes128Siv>::new(&mut rng, &sealing_pk, salt)
+                        .expect("valid public key");
 
                 test_encryptor(encryptor, &ex.blocks);
                 assert_eq!(pubkey.as_bytes(), &ex.ephemeralkey.pubkey[..]);
             }
             "XSTREAM_X25519_HKDF_SHA256_AES128_PMAC_SIV" => {
                 let (mut encryptor, pubkey) =
-                    X25519HkdfSha256Encryptor::<Aes128PmacSiv>::new(&mut rng, &sealing_pk, salt);
+                    X25519HkdfSha256Encryptor::<Aes128PmacSiv>::new(&mut rng, &sealing_pk, salt)
+                        .expect("valid public key");
+
+                test_encryptor(encryptor, &ex.blocks);
+                assert_eq!(pubkey.as_bytes(), &ex.ephemeralkey.pubkey[..]);
+            }
+            "XSTREAM_X25519_HKDF_SHA256_AES256_SIV" => {
+                let (mut encryptor, pubkey) =
+                    X25519HkdfSha256Encryptor::<Aes256Siv>::new(&mut rng, &sealing_pk, salt)
+                        .expect("valid public key");
+
+                test_encryptor(encryptor, &ex.blocks);
+                assert_eq!(pubkey.as_bytes(), &ex.ephemeralkey.pubkey[..]);
+            }
+            "XSTREAM_X25519_HKDF_SHA256_AES256_PMAC_SIV" => {
+                let (mut encryptor, pubkey) =
+                    X25519HkdfSha256Encryptor::<Aes256PmacSiv>::new(&mut rng, &sealing_pk, salt)
+                        .expect("valid public key");
+
+                test_encryptor(encryptor, &ex.blocks);
+                assert_eq!(pubkey.as_bytes(), &ex.ephemeralkey.pubkey[..]);
+            }
+            "XSTREAM_X25519_HKDF_SHA256_AES128_GCM_SIV" => {
+                let (mut encryptor, pubkey) =
+                    X25519HkdfSha256Encryptor::<Aes128GcmSiv>::new(&mut rng, &sealing_pk, salt)
+                        .expect("valid public key");
+
+                test_encryptor(encryptor, &ex.blocks);
+                assert_eq!(pubkey.as_bytes(), &ex.ephemeralkey.pubkey[..]);
+            }
+            "XSTREAM_X25519_HKDF_SHA256_AES256_GCM_SIV" => {
+                let (mut encryptor, pubkey) =
+                    X25519HkdfSha256Encryptor::<Aes256GcmSiv>::new(&mut rng, &sealing_pk, salt)
+                        .expect("valid public key");
 
                 test_encryptor(encryptor, &ex.blocks);
                 assert_eq!(pubkey.as_bytes(), &ex.ephemeralkey.pubkey[..]);
@@ -67,7 +103,8 @@ fn xstream_examples_open() {
         match ex.alg.as_ref() {
             "XSTREAM_X25519_HKDF_SHA256_AES128_SIV" => {
                 let decryptor =
-                    X25519HkdfSha256Decryptor::<Aes128Siv>::new(&sealing_sk, &ephemeral_pk, salt);
+                    X25519HkdfSha256Decryptor::<Aes128Siv>::new(&sealing_sk, &ephemeral_pk, salt)
+                        .expect("valid ephemeral key");
 
                 test_decryptor(decryptor, &ex.blocks)
             }
@@ -76,7 +113,43 @@ fn xstream_examples_open() {
                     &sealing_sk,
                     &ephemeral_pk,
                     salt,
-                );
+                ).expect("valid ephemeral key");
+
+                test_decryptor(decryptor, &ex.blocks)
+            }
+            "XSTREAM_X25519_HKDF_SHA256_AES256_SIV" => {
+                let decryptor = X25519HkdfSha256Decryptor::<Aes256Siv>::new(
+                    &sealing_sk,
+                    &ephemeral_pk,
+                    salt,
+                ).expect("valid ephemeral key");
+
+                test_decryptor(decryptor, &ex.blocks)
+            }
+            "XSTREAM_X25519_HKDF_SHA256_AES256_PMAC_SIV" => {
+                let decryptor = X25519HkdfSha256Decryptor::<Aes256PmacSiv>::new(
+                    &sealing_sk,
+                    &ephemeral_pk,
+                    salt,
+                ).expect("valid ephemeral key");
+
+                test_decryptor(decryptor, &ex.blocks)
+            }
+            "XSTREAM_X25519_HKDF_SHA256_AES128_GCM_SIV" => {
+                let decryptor = X25519HkdfSha256Decryptor::<Aes128GcmSiv>::new(
+                    &sealing_sk,
+                    &ephemeral_pk,
+                    salt,
+                ).expect("valid ephemeral key");
+
+                test_decryptor(decryptor, &ex.blocks)
+            }
+            "XSTREAM_X25519_HKDF_SHA256_AES256_GCM_SIV" => {
+                let decryptor = X25519HkdfSha256Decryptor::<Aes256GcmSiv>::new(
+                    &sealing_sk,
+                    &ephemeral_pk,
+                    salt,
+                ).expect("valid ephemeral key");
 
                 test_decryptor(decryptor, &ex.blocks)
             }
@@ -103,3 +176,408 @@ fn test_decryptor<D: Decryptor>(mut decryptor: D, blocks: &[Block]) {
         }
     }
 }
+
+#[test]
+fn xstream_examples_seal_dyn() {
+    for ex in XStreamExample::load_all() {
+        let mut rng = TestRng::new(ex.ephemeralkey.seckey.as_slice());
+        let sealing_pk = PublicKey::new(ex.sealingkey.pubkey.as_slice());
+        let salt = match ex.salt {
+            Some(ref vec) => Some(vec.as_ref()),
+            None => None,
+        };
+
+        let alg: Algorithm = ex.alg.parse().expect("known algorithm");
+        assert_eq!(alg.to_string(), ex.alg);
+
+        let (mut encryptor, pubkey) = DynEncryptor::new(alg, &mut rng, &sealing_pk, salt)
+            .expect("valid public key");
+        assert_eq!(encryptor.algorithm(), alg);
+        assert_eq!(pubkey.as_bytes(), &ex.ephemeralkey.pubkey[..]);
+
+        for (i, block) in ex.blocks.iter().enumerate() {
+            if i < ex.blocks.len() - 1 {
+                let ciphertext = encryptor.seal_next(&block.ad, &block.plaintext);
+                assert_eq!(ciphertext, block.ciphertext);
+            } else {
+                let ciphertext = encryptor.seal_last(&block.ad, &block.plaintext);
+                assert_eq!(ciphertext, block.ciphertext);
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn xstream_examples_open_dyn() {
+    for ex in XStreamExample::load_all() {
+        let sealing_sk = PrivateKey::new(ex.sealingkey.seckey.as_slice());
+        let ephemeral_pk = PublicKey::new(ex.ephemeralkey.pubkey.as_slice());
+        let salt = match ex.salt {
+            Some(ref vec) => Some(vec.as_ref()),
+            None => None,
+        };
+
+        let mut decryptor = xstream::decryptor_for(&ex.alg, &sealing_sk, &ephemeral_pk, salt)
+            .expect("known algorithm");
+
+        for (i, block) in ex.blocks.iter().enumerate() {
+            if i < ex.blocks.len() - 1 {
+                let plaintext = decryptor.open_next(&block.ad, &block.ciphertext).expect(
+                    "decrypt failure",
+                );
+
+                assert_eq!(plaintext, block.plaintext);
+            } else {
+                let plaintext = decryptor.open_last(&block.ad, &block.ciphertext).expect(
+                    "decrypt failure",
+                );
+
+                assert_eq!(plaintext, block.plaintext);
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn xstream_vectors_are_generated() {
+    // Regenerate with `cargo run --example generate_vectors`. The
+    // known-answer examples in xstream.tjson are checked by the tests above.
+    let vectors = std::fs::read_to_string("../vectors/xstream_generated.tjson").unwrap();
+    assert!(
+        vectors == xstream_vectors::generator::generate(),
+        "xstream_generated.tjson is out of date"
+    );
+
+    let vectors = std::fs::read_to_string("../vectors/xstream_invalid.tjson").unwrap();
+    assert!(
+        vectors == xstream_vectors::generator::generate_invalid(),
+        "xstream_invalid.tjson is out of date"
+    );
+}
+
+#[test]
+fn xstream_invalid_examples() {
+    for ex in XStreamInvalidExample::load_all() {
+        assert_eq!(open_invalid(&ex), ex.error, "{}", ex.name);
+    }
+}
+
+/// Open an invalid example, returning the kind of error which occurred
+fn open_invalid(ex: &XStreamInvalidExample) -> &'static str {
+    let mut decryptor = match DynDecryptor::new(
+        ex.alg.parse().unwrap(),
+        &PrivateKey::new(&ex.sealingkey.seckey),
+        &PublicKey::new(&ex.ephemeral_pubkey),
+        ex.salt.as_deref(),
+    ) {
+        Ok(decryptor) => decryptor,
+        Err(_) => return xstream_vectors::INVALID_KEY,
+    };
+
+    let (last, rest) = ex.blocks.split_last().unwrap();
+
+    for block in rest {
+        assert!(!block.last, "{}: blocks follow the last block", ex.name);
+
+        if decryptor.open_next(&block.ad, &block.ciphertext).is_err() {
+            return xstream_vectors::AUTHENTICATION;
+        }
+    }
+
+    let result = if last.last {
+        decryptor.open_last(&last.ad, &last.ciphertext)
+    } else {
+        decryptor.open_next(&last.ad, &last.ciphertext)
+    };
+
+    match result {
+        Err(_) => xstream_vectors::AUTHENTICATION,
+        Ok(_) if !last.last => xstream_vectors::TRUNCATED,
+        Ok(_) => panic!("{}: opened successfully", ex.name),
+    }
+}
+
+#[test]
+fn unknown_algorithm() {
+    assert!("XSTREAM_X25519_HKDF_SHA256_ROT13".parse::<Algorithm>().is_err());
+
+    let sealing_pk = PrivateKey::new(&[1u8; 32]).public_key();
+    assert!(xstream::encryptor_for("XSTREAM_X25519_HKDF_SHA256_ROT13", &sealing_pk, None).is_err());
+}
+
+#[test]
+fn encryptor_for_round_trip() {
+    let sealing_sk = PrivateKey::new(&[1u8; 32]);
+    let alg = "XSTREAM_X25519_HKDF_SHA256_AES128_PMAC_SIV";
+
+    let (encryptor, ephemeral_pk) =
+        xstream::encryptor_for(alg, &sealing_sk.public_key(), None).unwrap();
+    let ciphertext = encryptor.seal_last(b"ad", b"plaintext");
+
+    let decryptor =
+        DynDecryptor::new(alg.parse().unwrap(), &sealing_sk, &ephemeral_pk, None).unwrap();
+    assert_eq!(decryptor.open_last(b"ad", &ciphertext).unwrap(), b"plaintext");
+}
+
+#[test]
+fn low_order_public_key_rejected() {
+    let mut rng = TestRng::new(&[0x42; 32]);
+    let low_order_pk = PublicKey::from([0u8; 32]);
+    assert!(X25519HkdfSha256Encryptor::<Aes128Siv>::new(&mut rng, &low_order_pk, None).is_err());
+
+    let sealing_sk = PrivateKey::new(&[1u8; 32]);
+    assert!(X25519HkdfSha256Decryptor::<Aes128Siv>::new(&sealing_sk, &low_order_pk, None).is_err());
+}
+
+#[test]
+fn public_key_from_slice() {
+    let public_key = PublicKey::from_slice(&[7u8; 32]).unwrap();
+    assert_eq!(public_key.as_bytes(), &[7u8; 32][..]);
+
+    assert!(PublicKey::from_slice(&[7u8; 31]).is_err());
+    assert!(PublicKey::from_slice(&[7u8; 33]).is_err());
+    assert!(PublicKey::from_slice(b"").is_err());
+}
+
+#[test]
+fn exported_secrets() {
+    let sealing_sk = PrivateKey::new(&[1u8; 32]);
+    let mut rng = TestRng::new(&[2u8; 32]);
+
+    let (encryptor, ephemeral_pk) =
+        X25519HkdfSha256Encryptor::<Aes128Siv>::new(&mut rng, &sealing_sk.public_key(), None)
+            .unwrap();
+    let decryptor =
+        X25519HkdfSha256Decryptor::<Aes128Siv>::new(&sealing_sk, &ephemeral_pk, None).unwrap();
+
+    let mac_key = encryptor.export(b"manifest mac", b"", 32).unwrap();
+    assert_eq!(
+        mac_key,
+        &[
+            0x58, 0x24, 0xd6, 0xa0, 0xf0, 0x54, 0x95, 0xf6, 0xb5, 0x19, 0x8e, 0xa9, 0xb8, 0x28,
+            0x09, 0x08, 0xd0, 0x92, 0xef, 0xf2, 0xa9, 0x3d, 0x53, 0x79, 0x8c, 0xf3, 0x4e, 0xa3,
+            0x89, 0x27, 0x9e, 0x84,
+        ][..]
+    );
+    assert_eq!(decryptor.export(b"manifest mac", b"", 32).unwrap(), mac_key);
+
+    let binding = decryptor.export(b"channel binding", b"ctx", 16).unwrap();
+    assert_eq!(
+        binding,
+        &[
+            0xaa, 0x22, 0x97, 0x9e, 0x51, 0x86, 0xd3, 0x9d, 0x06, 0x13, 0x3b, 0x37, 0x5d, 0x12,
+            0x56, 0x11,
+        ][..]
+    );
+    assert_eq!(encryptor.export(b"channel binding", b"ctx", 16).unwrap(), binding);
+
+    // The label is length-prefixed, so moving bytes between the label and
+    // the context yields an unrelated secret
+    assert_ne!(
+        encryptor.export(b"channel", b" bindingctx", 16).unwrap(),
+        binding
+    );
+
+    assert!(encryptor.export(b"too long", b"", 255 * 32 + 1).is_err());
+}
+
+#[test]
+fn context_binding() {
+    let sealing_sk = PrivateKey::new(&[1u8; 32]);
+    let mut rng = TestRng::new(&[2u8; 32]);
+
+    let (encryptor, ephemeral_pk) = X25519HkdfSha256Encryptor::<Aes128Siv>::new_with_context(
+        &mut rng,
+        &sealing_sk.public_key(),
+        None,
+        b"tenant-42",
+    ).unwrap();
+
+    let ciphertext = encryptor.seal_last(b"", b"plaintext");
+    assert_eq!(
+        ciphertext,
+        &[
+            0x21, 0x72, 0x4f, 0xf9, 0x94, 0xe4, 0x75, 0x85, 0x0f, 0xee, 0x05, 0x49, 0x3b, 0xce,
+            0x4b, 0x3d, 0xf0, 0x19, 0x99, 0x4c, 0xde, 0x24, 0x97, 0xff, 0x27,
+        ][..]
+    );
+
+    let decryptor = X25519HkdfSha256Decryptor::<Aes128Siv>::new_with_context(
+        &sealing_sk,
+        &ephemeral_pk,
+        None,
+        b"tenant-42",
+    ).unwrap();
+    assert_eq!(decryptor.open_last(b"", &ciphertext).unwrap(), b"plaintext");
+
+    // A ciphertext moved to another context (or to no context) fails to open
+    for context in &[&b"tenant-43"[..], &b""[..]] {
+        let decryptor = DynDecryptor::new_with_context(
+            Algorithm::X25519HkdfSha256Aes128Siv,
+            &sealing_sk,
+            &ephemeral_pk,
+            None,
+            context,
+        ).unwrap();
+        assert!(decryptor.open_last(b"", &ciphertext).is_err());
+    }
+}
+
+#[test]
+fn checkpoint_and_resume() {
+    let sealing_sk = PrivateKey::new(&[1u8; 32]);
+    let state_key = [0x5a; xstream::STATE_KEY_SIZE];
+    let mut rng = TestRng::new(&[2u8; 32]);
+
+    let (mut encryptor, ephemeral_pk) =
+        X25519HkdfSha256Encryptor::<Aes128Siv>::new(&mut rng, &sealing_sk.public_key(), None)
+            .unwrap();
+
+    let first = encryptor.seal_next(b"", b"first");
+    let export = encryptor.export(b"label", b"", 16).unwrap();
+    let blob = encryptor.checkpoint(&state_key);
+    let mut generation = 0;
+
+    // Blobs are authenticated, and bound to the algorithm
+    let mut tampered = blob.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(
+        X25519HkdfSha256Encryptor::<Aes128Siv>::resume(&state_key, &tampered, &mut generation)
+            .is_err()
+    );
+    assert!(
+        X25519HkdfSha256Encryptor::<Aes128Siv>::resume(&[0; 32], &blob, &mut generation).is_err()
+    );
+    assert!(DynEncryptor::resume(&state_key, &blob, &mut generation).is_err());
+    assert_eq!(generation, 0);
+
+    let encryptor =
+        X25519HkdfSha256Encryptor::<Aes128Siv>::resume(&state_key, &blob, &mut generation)
+            .unwrap();
+    assert_eq!(generation, 1);
+    assert_eq!(encryptor.export(b"label", b"", 16).unwrap(), export);
+    let last = encryptor.seal_last(b"", b"last");
+
+    let mut decryptor =
+        X25519HkdfSha256Decryptor::<Aes128Siv>::new(&sealing_sk, &ephemeral_pk, None).unwrap();
+    assert_eq!(decryptor.open_next(b"", &first).unwrap(), b"first");
+    assert_eq!(decryptor.open_last(b"", &last).unwrap(), b"last");
+}
+
+#[test]
+fn checkpoint_and_resume_dyn() {
+    let sealing_sk = PrivateKey::new(&[1u8; 32]);
+    let state_key = [0x5a; xstream::STATE_KEY_SIZE];
+    let alg = Algorithm::X25519HkdfSha256Aes256GcmSiv;
+    let mut rng = TestRng::new(&[2u8; 32]);
+
+    let (mut encryptor, ephemeral_pk) =
+        DynEncryptor::new(alg, &mut rng, &sealing_sk.public_key(), None).unwrap();
+    let first = encryptor.seal_next(b"", b"first");
+    let blob = encryptor.checkpoint(&state_key);
+    let mut generation = 0;
+
+    // A blob tagged with one algorithm can't be resumed as another
+    assert!(
+        X25519HkdfSha256Encryptor::<Aes128Siv>::resume(&state_key, &blob, &mut generation)
+            .is_err()
+    );
+
+    let encryptor = DynEncryptor::resume(&state_key, &blob, &mut generation).unwrap();
+    assert_eq!(encryptor.algorithm(), alg);
+    let last = encryptor.seal_last(b"", b"last");
+
+    let mut decryptor = DynDecryptor::new(alg, &sealing_sk, &ephemeral_pk, None).unwrap();
+    assert_eq!(decryptor.open_next(b"", &first).unwrap(), b"first");
+    assert_eq!(decryptor.open_last(b"", &last).unwrap(), b"last");
+}
+
+#[test]
+fn checkpoint_resumed_once() {
+    let sealing_sk = PrivateKey::new(&[1u8; 32]);
+    let state_key = [0x5a; xstream::STATE_KEY_SIZE];
+    let mut rng = TestRng::new(&[2u8; 32]);
+
+    let alg = Algorithm::X25519HkdfSha256Aes128Siv;
+
+    let (mut encryptor, ephemeral_pk) =
+        DynEncryptor::new(alg, &mut rng, &sealing_sk.public_key(), None).unwrap();
+    let first = encryptor.seal_next(b"", b"first");
+    let blob = encryptor.checkpoint(&state_key);
+    let mut generation = 0;
+
+    let mut resumed = DynEncryptor::resume(&state_key, &blob, &mut generation).unwrap();
+    let second = resumed.seal_next(b"", b"second");
+
+    // Resuming the same blob again would seal under the counter value used
+    // for `second`, so it must be refused
+    assert!(DynEncryptor::resume(&state_key, &blob, &mut generation).is_err());
+    assert_eq!(generation, 1);
+
+    // A later checkpoint supersedes the earlier blob
+    let blob2 = resumed.checkpoint(&state_key);
+    let resumed = DynEncryptor::resume(&state_key, &blob2, &mut generation).unwrap();
+    assert_eq!(generation, 2);
+    assert!(DynEncryptor::resume(&state_key, &blob, &mut generation).is_err());
+    assert!(DynEncryptor::resume(&state_key, &blob2, &mut generation).is_err());
+
+    let last = resumed.seal_last(b"", b"last");
+
+    // Every segment of the stream was sealed under a distinct nonce, so the
+    // stream opens in order
+    let mut decryptor = DynDecryptor::new(alg, &sealing_sk, &ephemeral_pk, None).unwrap();
+    assert_eq!(decryptor.open_next(b"", &first).unwrap(), b"first");
+    assert_eq!(decryptor.open_next(b"", &second).unwrap(), b"second");
+    assert_eq!(decryptor.open_last(b"", &last).unwrap(), b"last");
+}
+
+#[test]
+fn counter_exhaustion() {
+    use miscreant::aead::Algorithm as AeadAlgorithm;
+
+    let sealing_sk = PrivateKey::new(&[1u8; 32]);
+    let state_key = [0x5a; xstream::STATE_KEY_SIZE];
+    let mut rng = TestRng::new(&[2u8; 32]);
+
+    let (mut encryptor, ephemeral_pk) =
+        X25519HkdfSha256Encryptor::<Aes128Siv>::new(&mut rng, &sealing_sk.public_key(), None)
+            .unwrap();
+
+    let first = encryptor.try_seal_next(b"", b"first").unwrap();
+    encryptor.seal_next(b"", b"second");
+    assert_eq!(encryptor.segments_sealed(), 2);
+    assert_eq!(encryptor.bytes_sealed(), 11 + 2 * 16);
+    assert_eq!(encryptor.remaining_segments(), Some(u64::from(u32::MAX) - 2));
+
+    // Rewrite the counter in a checkpoint to fast-forward the stream to the
+    // point where only one more segment fits before the last one. The
+    // counter follows the rekeying state (25 bytes) and totals (16 bytes).
+    let blob = encryptor.checkpoint(&state_key);
+    let (header, ciphertext) = blob.split_at(2);
+    let mut siv = <Aes128Siv as AeadAlgorithm>::new(&state_key);
+    let mut state = siv.open(b"XSTREAM_STATE", header, ciphertext).unwrap();
+    state[41..45].copy_from_slice(&(u32::MAX - 1).to_be_bytes());
+
+    let mut blob = Vec::from(header);
+    blob.extend(siv.seal(b"XSTREAM_STATE", header, &state));
+
+    let mut encryptor =
+        X25519HkdfSha256Encryptor::<Aes128Siv>::resume(&state_key, &blob, &mut 0).unwrap();
+    assert_eq!(encryptor.remaining_segments(), Some(1));
+
+    let next = encryptor.try_seal_next(b"", b"next").unwrap();
+    assert_eq!(encryptor.remaining_segments(), Some(0));
+    assert!(encryptor.try_seal_next(b"", b"too many").is_err());
+    assert_eq!(encryptor.segments_sealed(), 3);
+
+    // The last segment takes the final counter value
+    let last = encryptor.seal_last(b"", b"last");
+
+    let mut decryptor =
+        X25519HkdfSha256Decryptor::<Aes128Siv>::new(&sealing_sk, &ephemeral_pk, None).unwrap();
+    assert_eq!(decryptor.open_next(b"", &first).unwrap(), b"first");
+    assert_eq!(decryptor.open_at(u32::MAX - 1, false, b"", &next).unwrap(), b"next");
+    assert_eq!(decryptor.open_at(u32::MAX, true, b"", &last).unwrap(), b"last");
+}
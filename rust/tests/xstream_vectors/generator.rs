@@ -0,0 +1,420 @@
+//! Deterministic generator for `vectors/xstream_generated.tjson` and
+//! `vectors/xstream_invalid.tjson`
+//!
+//! The known-answer examples in `xstream.tjson` are maintained by hand and
+//! aren't generated.
+//!
+//! `xstream_generated.tjson` contains an example for every supported
+//! algorithm, number of blocks (1 to 3), and combination of with/without
+//! salt and with/without associated data. Every input is derived from the example's seed string,
+//! `<algorithm>/<blocks>-block/<salt|no-salt>/<ad|no-ad>`, as the first
+//! `len` bytes of:
+//!
+//! ```text
+//! SHA-256(seed || 0x00 || label || 0x00 || 0x00000000) ||
+//! SHA-256(seed || 0x00 || label || 0x00 || 0x00000001) || ...
+//! ```
+//!
+//! with the labels `sealingkey` and `ephemeralkey` (32 bytes each), `salt`
+//! (12 bytes), and `ad/<i>` (32 bytes) and `plaintext/<i>` (see
+//! `PLAINTEXT_LENGTHS`) for each block. Other implementations can derive
+//! the same inputs and compare their output against the file.
+//!
+//! `xstream_invalid.tjson` contains examples which must fail to open, made
+//! by tampering with a 3-block stream (with salt and associated data) whose
+//! seed is `<algorithm>/invalid`. Each states the error expected:
+//!
+//! - `invalid_key`: creating the decryptor fails
+//! - `authentication`: opening one of the blocks fails
+//! - `truncated`: every block opens, but none is the last block
+//!
+//! Blocks are opened in order, as the last block if their `last` flag is
+//! set and otherwise as the next one.
+
+extern crate sha2;
+
+use self::sha2::{Digest, Sha256};
+use test_rng::TestRng;
+use xstream::{Algorithm, DynEncryptor, PrivateKey, PublicKey};
+
+/// Maximum number of blocks in an example
+const MAX_BLOCKS: usize = 3;
+
+/// Length of each block's plaintext
+const PLAINTEXT_LENGTHS: [usize; MAX_BLOCKS] = [28, 24, 12];
+
+/// Length of salts
+const SALT_SIZE: usize = 12;
+
+/// Length of each block's associated data
+const AD_SIZE: usize = 32;
+
+/// Low-order X25519 public keys (and non-canonical encodings of them),
+/// which must be rejected
+const LOW_ORDER_KEYS: [(&str, &str); 7] = [
+    ("zero", "0000000000000000000000000000000000000000000000000000000000000000"),
+    ("one", "0100000000000000000000000000000000000000000000000000000000000000"),
+    ("order 8", "e0eb7a7c3b41b8ae1656e3faf19fc46ada098deb9c32b1fd866205165f49b800"),
+    ("order 8", "5f9c95bca3508c24b1d0b1559c83ef5b04445cc4581c8e86d8224eddd09f1157"),
+    ("p - 1", "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f"),
+    ("p", "edffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f"),
+    ("p + 1", "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f"),
+];
+
+/// A stream sealed from seed-derived inputs
+struct Sealed {
+    algorithm: Algorithm,
+    sealing_sk: Vec<u8>,
+    sealing_pk: PublicKey,
+    ephemeral_sk: Vec<u8>,
+    ephemeral_pk: PublicKey,
+    salt: Option<Vec<u8>>,
+    ads: Vec<Vec<u8>>,
+    plaintexts: Vec<Vec<u8>>,
+    ciphertexts: Vec<Vec<u8>>,
+}
+
+impl Sealed {
+    /// Derive the inputs for the given seed and seal them
+    fn new(algorithm: Algorithm, seed: &str, blocks: usize, salt: bool, ad: bool) -> Self {
+        let sealing_sk = derive(seed, "sealingkey", 32);
+        let ephemeral_sk = derive(seed, "ephemeralkey", 32);
+        let salt = if salt { Some(derive(seed, "salt", SALT_SIZE)) } else { None };
+        let sealing_pk = PrivateKey::new(&sealing_sk).public_key();
+
+        let (mut encryptor, ephemeral_pk) = DynEncryptor::new(
+            algorithm,
+            &mut TestRng::new(&ephemeral_sk),
+            &sealing_pk,
+            salt.as_deref(),
+        ).expect("valid sealing key");
+
+        let ads: Vec<Vec<u8>> = (0..blocks)
+            .map(|i| if ad { derive(seed, &format!("ad/{}", i), AD_SIZE) } else { vec![] })
+            .collect();
+        let plaintexts: Vec<Vec<u8>> = PLAINTEXT_LENGTHS[..blocks]
+            .iter()
+            .enumerate()
+            .map(|(i, &len)| derive(seed, &format!("plaintext/{}", i), len))
+            .collect();
+
+        let mut ciphertexts: Vec<Vec<u8>> = (0..blocks - 1)
+            .map(|i| encryptor.seal_next(&ads[i], &plaintexts[i]))
+            .collect();
+        ciphertexts.push(encryptor.seal_last(&ads[blocks - 1], &plaintexts[blocks - 1]));
+
+        Self {
+            algorithm,
+            sealing_sk,
+            sealing_pk,
+            ephemeral_sk,
+            ephemeral_pk,
+            salt,
+            ads,
+            plaintexts,
+            ciphertexts,
+        }
+    }
+}
+
+/// Generate the contents of `vectors/xstream_generated.tjson`
+pub fn generate() -> String {
+    let mut examples = Vec::new();
+
+    for &algorithm in Algorithm::all() {
+        for blocks in 1..=MAX_BLOCKS {
+            for &salt in &[true, false] {
+                for &ad in &[false, true] {
+                    examples.push(example(algorithm, blocks, salt, ad));
+                }
+            }
+        }
+    }
+
+    format!(
+        "{{\n    \"examples:A<O>\":[\n{}\n    ]\n}}\n",
+        examples.join(",\n")
+    )
+}
+
+/// Generate the contents of `vectors/xstream_invalid.tjson`
+pub fn generate_invalid() -> String {
+    let examples: Vec<String> = Algorithm::all()
+        .iter()
+        .flat_map(|&algorithm| invalid_examples(algorithm))
+        .collect();
+
+    format!(
+        "{{\n    \"examples:A<O>\":[\n{}\n    ]\n}}\n",
+        examples.join(",\n")
+    )
+}
+
+/// Generate a single example, formatted as a TJSON object
+fn example(algorithm: Algorithm, blocks: usize, salt: bool, ad: bool) -> String {
+    let seed = format!(
+        "{}/{}-block/{}/{}",
+        algorithm,
+        blocks,
+        if salt { "salt" } else { "no-salt" },
+        if ad { "ad" } else { "no-ad" }
+    );
+
+    let sealed = Sealed::new(algorithm, &seed, blocks, salt, ad);
+    let block_json: Vec<String> = (0..blocks)
+        .map(|i| block(&sealed.ads[i], &sealed.plaintexts[i], &sealed.ciphertexts[i]))
+        .collect();
+
+    let mut json = String::from("        {\n");
+    json.push_str(&format!(
+        "            \"name:s\":\"{}\",\n",
+        name(algorithm, blocks, &sealed.salt, ad)
+    ));
+    json.push_str(&format!("            \"alg:s\":\"{}\",\n", algorithm));
+    json.push_str(&keypair("sealingkey", &sealed.sealing_sk, sealed.sealing_pk.as_bytes()));
+    json.push_str(&keypair("ephemeralkey", &sealed.ephemeral_sk, sealed.ephemeral_pk.as_bytes()));
+
+    if let Some(ref salt) = sealed.salt {
+        json.push_str(&format!("            \"salt:d16\":\"{}\",\n", to_hex(salt)));
+    }
+
+    json.push_str("            \"blocks:A<O>\":[\n");
+    json.push_str(&block_json.join(",\n"));
+    json.push_str("\n            ]\n        }");
+    json
+}
+
+/// An example which must fail to open
+struct Invalid<'a> {
+    sealed: &'a Sealed,
+    description: String,
+    error: &'static str,
+    ephemeral_pk: Vec<u8>,
+    salt: Option<Vec<u8>>,
+    blocks: Vec<(Vec<u8>, Vec<u8>, bool)>,
+}
+
+impl<'a> Invalid<'a> {
+    /// Start from the untampered stream
+    fn new(sealed: &'a Sealed, description: &str, error: &'static str) -> Self {
+        let count = sealed.ciphertexts.len();
+
+        Self {
+            sealed,
+            description: description.to_owned(),
+            error,
+            ephemeral_pk: sealed.ephemeral_pk.as_bytes().to_vec(),
+            salt: sealed.salt.clone(),
+            blocks: (0..count)
+                .map(|i| (sealed.ads[i].clone(), sealed.ciphertexts[i].clone(), i + 1 == count))
+                .collect(),
+        }
+    }
+
+    /// Format this example as a TJSON object
+    fn to_json(&self) -> String {
+        let blocks: Vec<String> = self
+            .blocks
+            .iter()
+            .map(|(ad, ciphertext, last)| invalid_block(ad, ciphertext, *last))
+            .collect();
+
+        let mut json = String::from("        {\n");
+        json.push_str(&format!(
+            "            \"name:s\":\"{} XSTREAM Invalid Example: {}\",\n",
+            cipher_name(self.sealed.algorithm),
+            self.description
+        ));
+        json.push_str(&format!("            \"alg:s\":\"{}\",\n", self.sealed.algorithm));
+        json.push_str(&format!("            \"error:s\":\"{}\",\n", self.error));
+        json.push_str(&keypair(
+            "sealingkey",
+            &self.sealed.sealing_sk,
+            self.sealed.sealing_pk.as_bytes(),
+        ));
+        json.push_str(&format!(
+            concat!(
+                "            \"ephemeralkey:O\":{{\n",
+                "                \"pubkey:d16\":\"{}\"\n",
+                "            }},\n"
+            ),
+            to_hex(&self.ephemeral_pk)
+        ));
+
+        if let Some(ref salt) = self.salt {
+            json.push_str(&format!("            \"salt:d16\":\"{}\",\n", to_hex(salt)));
+        }
+
+        json.push_str("            \"blocks:A<O>\":[\n");
+        json.push_str(&blocks.join(",\n"));
+        json.push_str("\n            ]\n        }");
+        json
+    }
+}
+
+/// Generate the invalid examples for an algorithm
+fn invalid_examples(algorithm: Algorithm) -> Vec<String> {
+    let sealed = Sealed::new(algorithm, &format!("{}/invalid", algorithm), 3, true, true);
+    let mut examples = Vec::new();
+
+    let mut example = Invalid::new(&sealed, "dropped last segment", "truncated");
+    example.blocks.pop();
+    examples.push(example);
+
+    let mut example = Invalid::new(&sealed, "dropped first segment", "authentication");
+    example.blocks.remove(0);
+    examples.push(example);
+
+    let mut example = Invalid::new(&sealed, "swapped segments", "authentication");
+    example.blocks.swap(0, 1);
+    examples.push(example);
+
+    let mut example = Invalid::new(&sealed, "last flag on a middle segment", "authentication");
+    example.blocks.pop();
+    example.blocks[1].2 = true;
+    examples.push(example);
+
+    let mut example = Invalid::new(&sealed, "last flag missing", "authentication");
+    example.blocks[2].2 = false;
+    examples.push(example);
+
+    let mut example = Invalid::new(&sealed, "bit flip in first byte", "authentication");
+    example.blocks[0].1[0] ^= 0x01;
+    examples.push(example);
+
+    let mut example = Invalid::new(&sealed, "bit flip in last byte", "authentication");
+    *example.blocks[2].1.last_mut().unwrap() ^= 0x80;
+    examples.push(example);
+
+    let mut example = Invalid::new(&sealed, "wrong salt", "authentication");
+    example.salt.as_mut().unwrap()[0] ^= 0x01;
+    examples.push(example);
+
+    let mut example = Invalid::new(&sealed, "missing salt", "authentication");
+    example.salt = None;
+    examples.push(example);
+
+    let mut example = Invalid::new(&sealed, "wrong associated data", "authentication");
+    example.blocks[1].0[0] ^= 0x01;
+    examples.push(example);
+
+    let mut example = Invalid::new(&sealed, "missing associated data", "authentication");
+    example.blocks[0].0.clear();
+    examples.push(example);
+
+    let mut example = Invalid::new(&sealed, "zero-length segment", "authentication");
+    example.blocks[1].1.clear();
+    examples.push(example);
+
+    let mut example = Invalid::new(&sealed, "segment shorter than a tag", "authentication");
+    example.blocks[1].1.truncate(15);
+    examples.push(example);
+
+    for &(description, key) in &LOW_ORDER_KEYS {
+        let description = format!("low-order ephemeral key ({})", description);
+        let mut example = Invalid::new(&sealed, &description, "invalid_key");
+        example.ephemeral_pk = from_hex(key);
+        examples.push(example);
+    }
+
+    examples.iter().map(Invalid::to_json).collect()
+}
+
+/// Describe an example, e.g. "AES-128-SIV XSTREAM 2-Block Example with Salt"
+fn name(algorithm: Algorithm, blocks: usize, salt: &Option<Vec<u8>>, ad: bool) -> String {
+    let inputs = match (salt.is_some(), ad) {
+        (true, false) => "with Salt",
+        (true, true) => "with Salt and Associated Data",
+        (false, false) => "without Salt",
+        (false, true) => "without Salt, with Associated Data",
+    };
+
+    format!("{} XSTREAM {}-Block Example {}", cipher_name(algorithm), blocks, inputs)
+}
+
+/// Name an algorithm's cipher, e.g. "AES-128-PMAC-SIV"
+fn cipher_name(algorithm: Algorithm) -> String {
+    algorithm
+        .as_str()
+        .trim_start_matches("XSTREAM_X25519_HKDF_SHA256_")
+        .replace("AES128", "AES-128")
+        .replace("AES256", "AES-256")
+        .replace('_', "-")
+}
+
+/// Format a keypair as a TJSON object member
+fn keypair(name: &str, seckey: &[u8], pubkey: &[u8]) -> String {
+    format!(
+        concat!(
+            "            \"{}:O\":{{\n",
+            "                \"seckey:d16\":\"{}\",\n",
+            "                \"pubkey:d16\":\"{}\"\n",
+            "            }},\n"
+        ),
+        name,
+        to_hex(seckey),
+        to_hex(pubkey)
+    )
+}
+
+/// Format a block as a TJSON object
+fn block(ad: &[u8], plaintext: &[u8], ciphertext: &[u8]) -> String {
+    format!(
+        concat!(
+            "                {{\n",
+            "                    \"ad:d16\":\"{}\",\n",
+            "                    \"plaintext:d16\":\"{}\",\n",
+            "                    \"ciphertext:d16\":\"{}\"\n",
+            "                }}"
+        ),
+        to_hex(ad),
+        to_hex(plaintext),
+        to_hex(ciphertext)
+    )
+}
+
+/// Format a block of an invalid example as a TJSON object
+fn invalid_block(ad: &[u8], ciphertext: &[u8], last: bool) -> String {
+    format!(
+        concat!(
+            "                {{\n",
+            "                    \"ad:d16\":\"{}\",\n",
+            "                    \"ciphertext:d16\":\"{}\",\n",
+            "                    \"last:b\":{}\n",
+            "                }}"
+        ),
+        to_hex(ad),
+        to_hex(ciphertext),
+        last
+    )
+}
+
+/// Derive `len` bytes for the given label from an example's seed
+fn derive(seed: &str, label: &str, len: usize) -> Vec<u8> {
+    let mut output = Vec::with_capacity(len);
+    let mut counter = 0u32;
+
+    while output.len() < len {
+        let mut hash = Sha256::default();
+        hash.input(seed.as_bytes());
+        hash.input(&[0]);
+        hash.input(label.as_bytes());
+        hash.input(&[0]);
+        hash.input(&counter.to_be_bytes());
+        output.extend_from_slice(&hash.result());
+        counter += 1;
+    }
+
+    output.truncate(len);
+    output
+}
+
+fn from_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len() / 2)
+        .map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap())
+        .collect()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -1,6 +1,8 @@
 extern crate data_encoding;
 extern crate serde_json;
 
+pub mod generator;
+
 use self::data_encoding::HEXLOWER;
 pub use self::serde_json::Value as JsonValue;
 use std::fs::File;
@@ -32,11 +34,24 @@ pub struct Block {
 }
 
 impl XStreamExample {
-    /// Load examples from xstream.tjson
+    /// Load the known-answer examples from xstream.tjson, followed by the
+    /// examples generated from seeds in xstream_generated.tjson
     pub fn load_all() -> Vec<Self> {
+        let mut examples = Self::load_known();
+        examples.extend(Self::load_generated());
+        examples
+    }
+
+    /// Load the known-answer examples from xstream.tjson
+    pub fn load_known() -> Vec<Self> {
         Self::load_from_file(Path::new("../vectors/xstream.tjson"))
     }
 
+    /// Load the examples generated from seeds in xstream_generated.tjson
+    pub fn load_generated() -> Vec<Self> {
+        Self::load_from_file(Path::new("../vectors/xstream_generated.tjson"))
+    }
+
     /// Load examples from a file at the given path
     pub fn load_from_file(path: &Path) -> Vec<Self> {
         let mut file = File::open(&path).expect("valid xstream.tjson");
@@ -132,3 +147,75 @@ impl XStreamExample {
             .collect()
     }
 }
+
+/// Expected outcomes of opening invalid examples (see `generator`)
+pub const INVALID_KEY: &str = "invalid_key";
+pub const AUTHENTICATION: &str = "authentication";
+pub const TRUNCATED: &str = "truncated";
+
+/// XSTREAM test vectors which must fail to open
+#[derive(Debug)]
+pub struct XStreamInvalidExample {
+    pub name: String,
+    pub alg: String,
+    pub error: String,
+    pub sealingkey: Keypair,
+    pub ephemeral_pubkey: Vec<u8>,
+    pub salt: Option<Vec<u8>>,
+    pub blocks: Vec<InvalidBlock>,
+}
+
+#[derive(Debug)]
+pub struct InvalidBlock {
+    pub ad: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+    pub last: bool,
+}
+
+impl XStreamInvalidExample {
+    /// Load examples from xstream_invalid.tjson
+    pub fn load_all() -> Vec<Self> {
+        let mut tjson_string = String::new();
+        File::open(Path::new("../vectors/xstream_invalid.tjson"))
+            .expect("valid xstream_invalid.tjson")
+            .read_to_string(&mut tjson_string)
+            .expect("xstream_invalid.tjson read successfully");
+
+        let tjson: serde_json::Value = serde_json::from_str(&tjson_string)
+            .expect("xstream_invalid.tjson parses successfully");
+
+        tjson["examples:A<O>"]
+            .as_array()
+            .expect("xstream_invalid.tjson examples array")
+            .iter()
+            .map(|ex| Self {
+                name: ex["name:s"].as_str().expect("example name").to_owned(),
+                alg: ex["alg:s"].as_str().expect("algorithm name").to_owned(),
+                error: ex["error:s"].as_str().expect("expected error").to_owned(),
+                sealingkey: Keypair {
+                    seckey: hex(&ex["sealingkey:O"]["seckey:d16"]),
+                    pubkey: hex(&ex["sealingkey:O"]["pubkey:d16"]),
+                },
+                ephemeral_pubkey: hex(&ex["ephemeralkey:O"]["pubkey:d16"]),
+                salt: ex.get("salt:d16").map(hex),
+                blocks: ex["blocks:A<O>"]
+                    .as_array()
+                    .expect("encoded example")
+                    .iter()
+                    .map(|block| InvalidBlock {
+                        ad: hex(&block["ad:d16"]),
+                        ciphertext: hex(&block["ciphertext:d16"]),
+                        last: block["last:b"].as_bool().expect("last flag"),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// Decode a hex-encoded TJSON value
+fn hex(value: &JsonValue) -> Vec<u8> {
+    HEXLOWER
+        .decode(value.as_str().expect("encoded example").as_bytes())
+        .expect("hex encoded")
+}
@@ -0,0 +1,11 @@
+#!/bin/bash
+# Build the Node package in pkg/ and test it, both from Rust (under the
+# runner in .cargo/config.toml) and through the generated JavaScript bindings
+
+set -e
+
+cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib
+wasm-bindgen --target nodejs --out-dir pkg target/wasm32-unknown-unknown/release/xstream.wasm
+
+cargo test --target wasm32-unknown-unknown --features wasm --test wasm_test
+node --test tests/wasm/